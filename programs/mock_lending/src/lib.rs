@@ -0,0 +1,616 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::{self, Mint, Token, TokenAccount};
+
+declare_id!("9JtmtMTwFZ5qDkHbXZYUD9kNAHLtHLHrtWQjZsEmkXq");
+
+// Minimal lending program with the SPL token-lending instruction layout:
+// one-byte tags (2 = InitReserve, 4 = DepositReserveLiquidity,
+// 5 = RedeemReserveCollateral), a little-endian u64 amount, and the same
+// account order. The reserve account holds the token-lending `Reserve`
+// layout (with Solend's accumulated protocol fees), so the vault values its
+// collateral exactly as it would against a real reserve:
+//
+//   (available + borrowed - protocol fees) / collateral supply
+//
+// Borrows, repayments, interest and bad debt are simulated by the test-only
+// instructions at the end, which only move the reserve's books and tokens.
+#[program]
+pub mod mock_lending {
+    use super::*;
+
+    #[instruction(discriminator = [2])]
+    pub fn init_reserve(ctx: Context<InitReserve>) -> Result<()> {
+        let reserve = ReserveState {
+            lending_market: ctx.accounts.lending_market.key(),
+            liquidity_mint: ctx.accounts.liquidity_mint.key(),
+            liquidity_mint_decimals: ctx.accounts.liquidity_mint.decimals,
+            liquidity_supply: ctx.accounts.reserve_liquidity_supply.key(),
+            available_amount: 0,
+            borrowed_amount_wads: 0,
+            collateral_mint: ctx.accounts.reserve_collateral_mint.key(),
+            collateral_mint_total_supply: 0,
+            accumulated_protocol_fees_wads: 0,
+        };
+        reserve.store(&ctx.accounts.reserve)?;
+
+        msg!("Reserve initialized for mint: {}", reserve.liquidity_mint);
+        Ok(())
+    }
+
+    /// Deposit liquidity, receive collateral at the current exchange rate
+    #[instruction(discriminator = [4])]
+    pub fn deposit_reserve_liquidity(
+        ctx: Context<DepositReserveLiquidity>,
+        liquidity_amount: u64,
+    ) -> Result<()> {
+        require!(liquidity_amount > 0, MockLendingError::InvalidAmount);
+        let mut reserve =
+            ReserveState::load(&ctx.accounts.reserve, &ctx.accounts.lending_market.key())?;
+        reserve.check(
+            &ctx.accounts.reserve_liquidity_supply.key(),
+            &ctx.accounts.reserve_collateral_mint.key(),
+        )?;
+
+        // collateral = liquidity * collateral_supply / total_liquidity (1:1 when empty)
+        let total_liquidity = reserve.total_liquidity()?;
+        let collateral_supply = ctx.accounts.reserve_collateral_mint.supply;
+        let collateral_amount = if collateral_supply == 0 || total_liquidity == 0 {
+            liquidity_amount
+        } else {
+            mul_div(liquidity_amount, collateral_supply, total_liquidity)?
+        };
+        require!(collateral_amount > 0, MockLendingError::InvalidAmount);
+
+        token::transfer(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                token::Transfer {
+                    from: ctx.accounts.source_liquidity.to_account_info(),
+                    to: ctx.accounts.reserve_liquidity_supply.to_account_info(),
+                    authority: ctx.accounts.user_transfer_authority.to_account_info(),
+                },
+            ),
+            liquidity_amount,
+        )?;
+
+        let lending_market = ctx.accounts.lending_market.key();
+        let seeds = &[
+            lending_market.as_ref(),
+            &[ctx.bumps.lending_market_authority],
+        ];
+        let signer_seeds = &[&seeds[..]];
+
+        token::mint_to(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                token::MintTo {
+                    mint: ctx.accounts.reserve_collateral_mint.to_account_info(),
+                    to: ctx.accounts.destination_collateral.to_account_info(),
+                    authority: ctx.accounts.lending_market_authority.to_account_info(),
+                },
+                signer_seeds,
+            ),
+            collateral_amount,
+        )?;
+
+        reserve.available_amount = reserve
+            .available_amount
+            .checked_add(liquidity_amount)
+            .ok_or(MockLendingError::MathOverflow)?;
+        reserve.collateral_mint_total_supply = reserve
+            .collateral_mint_total_supply
+            .checked_add(collateral_amount)
+            .ok_or(MockLendingError::MathOverflow)?;
+        reserve.store(&ctx.accounts.reserve)?;
+
+        msg!(
+            "Deposited {} liquidity for {} collateral",
+            liquidity_amount,
+            collateral_amount
+        );
+        Ok(())
+    }
+
+    /// Burn collateral, receive liquidity at the current exchange rate
+    #[instruction(discriminator = [5])]
+    pub fn redeem_reserve_collateral(
+        ctx: Context<RedeemReserveCollateral>,
+        collateral_amount: u64,
+    ) -> Result<()> {
+        require!(collateral_amount > 0, MockLendingError::InvalidAmount);
+        let mut reserve =
+            ReserveState::load(&ctx.accounts.reserve, &ctx.accounts.lending_market.key())?;
+        reserve.check(
+            &ctx.accounts.reserve_liquidity_supply.key(),
+            &ctx.accounts.reserve_collateral_mint.key(),
+        )?;
+
+        // liquidity = collateral * total_liquidity / collateral_supply
+        let liquidity_amount = mul_div(
+            collateral_amount,
+            reserve.total_liquidity()?,
+            ctx.accounts.reserve_collateral_mint.supply,
+        )?;
+        require!(liquidity_amount > 0, MockLendingError::InvalidAmount);
+        // Borrowed liquidity has to come back before it can be redeemed
+        require!(
+            liquidity_amount <= reserve.available_amount,
+            MockLendingError::InsufficientLiquidity
+        );
+
+        token::burn(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                token::Burn {
+                    mint: ctx.accounts.reserve_collateral_mint.to_account_info(),
+                    from: ctx.accounts.source_collateral.to_account_info(),
+                    authority: ctx.accounts.user_transfer_authority.to_account_info(),
+                },
+            ),
+            collateral_amount,
+        )?;
+
+        let lending_market = ctx.accounts.lending_market.key();
+        let seeds = &[
+            lending_market.as_ref(),
+            &[ctx.bumps.lending_market_authority],
+        ];
+        let signer_seeds = &[&seeds[..]];
+
+        token::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                token::Transfer {
+                    from: ctx.accounts.reserve_liquidity_supply.to_account_info(),
+                    to: ctx.accounts.destination_liquidity.to_account_info(),
+                    authority: ctx.accounts.lending_market_authority.to_account_info(),
+                },
+                signer_seeds,
+            ),
+            liquidity_amount,
+        )?;
+
+        reserve.available_amount -= liquidity_amount;
+        reserve.collateral_mint_total_supply = reserve
+            .collateral_mint_total_supply
+            .saturating_sub(collateral_amount);
+        reserve.store(&ctx.accounts.reserve)?;
+
+        msg!(
+            "Redeemed {} collateral for {} liquidity",
+            collateral_amount,
+            liquidity_amount
+        );
+        Ok(())
+    }
+
+    /// Test-only: a borrower defaults and `liquidity_amount` of the debt is
+    /// written off, lowering the exchange rate. Not part of the SPL layout.
+    #[instruction(discriminator = [200])]
+    pub fn write_off_liquidity(ctx: Context<UpdateReserve>, liquidity_amount: u64) -> Result<()> {
+        require!(liquidity_amount > 0, MockLendingError::InvalidAmount);
+        let mut reserve =
+            ReserveState::load(&ctx.accounts.reserve, &ctx.accounts.lending_market.key())?;
+
+        reserve.borrowed_amount_wads = reserve
+            .borrowed_amount_wads
+            .checked_sub(wads(liquidity_amount))
+            .ok_or(MockLendingError::InsufficientLiquidity)?;
+        reserve.store(&ctx.accounts.reserve)?;
+
+        msg!("Wrote off {} liquidity", liquidity_amount);
+        Ok(())
+    }
+
+    /// Test-only: lend `liquidity_amount` out of the supply. The exchange
+    /// rate is unchanged; the liquidity moves from available to borrowed.
+    #[instruction(discriminator = [201])]
+    pub fn borrow_liquidity(ctx: Context<BorrowLiquidity>, liquidity_amount: u64) -> Result<()> {
+        require!(liquidity_amount > 0, MockLendingError::InvalidAmount);
+        let mut reserve =
+            ReserveState::load(&ctx.accounts.reserve, &ctx.accounts.lending_market.key())?;
+        require_keys_eq!(
+            ctx.accounts.reserve_liquidity_supply.key(),
+            reserve.liquidity_supply,
+            MockLendingError::ReserveMismatch
+        );
+        require!(
+            liquidity_amount <= reserve.available_amount,
+            MockLendingError::InsufficientLiquidity
+        );
+
+        let lending_market = ctx.accounts.lending_market.key();
+        let seeds = &[
+            lending_market.as_ref(),
+            &[ctx.bumps.lending_market_authority],
+        ];
+        let signer_seeds = &[&seeds[..]];
+
+        token::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                token::Transfer {
+                    from: ctx.accounts.reserve_liquidity_supply.to_account_info(),
+                    to: ctx.accounts.destination_liquidity.to_account_info(),
+                    authority: ctx.accounts.lending_market_authority.to_account_info(),
+                },
+                signer_seeds,
+            ),
+            liquidity_amount,
+        )?;
+
+        reserve.available_amount -= liquidity_amount;
+        reserve.borrowed_amount_wads = reserve
+            .borrowed_amount_wads
+            .checked_add(wads(liquidity_amount))
+            .ok_or(MockLendingError::MathOverflow)?;
+        reserve.store(&ctx.accounts.reserve)?;
+
+        msg!("Borrowed {} liquidity", liquidity_amount);
+        Ok(())
+    }
+
+    /// Test-only: pay `liquidity_amount` of the debt back into the supply
+    #[instruction(discriminator = [202])]
+    pub fn repay_liquidity(ctx: Context<RepayLiquidity>, liquidity_amount: u64) -> Result<()> {
+        require!(liquidity_amount > 0, MockLendingError::InvalidAmount);
+        let mut reserve =
+            ReserveState::load(&ctx.accounts.reserve, &ctx.accounts.lending_market.key())?;
+        require_keys_eq!(
+            ctx.accounts.reserve_liquidity_supply.key(),
+            reserve.liquidity_supply,
+            MockLendingError::ReserveMismatch
+        );
+
+        token::transfer(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                token::Transfer {
+                    from: ctx.accounts.source_liquidity.to_account_info(),
+                    to: ctx.accounts.reserve_liquidity_supply.to_account_info(),
+                    authority: ctx.accounts.user_transfer_authority.to_account_info(),
+                },
+            ),
+            liquidity_amount,
+        )?;
+
+        reserve.borrowed_amount_wads = reserve
+            .borrowed_amount_wads
+            .checked_sub(wads(liquidity_amount))
+            .ok_or(MockLendingError::InsufficientLiquidity)?;
+        reserve.available_amount = reserve
+            .available_amount
+            .checked_add(liquidity_amount)
+            .ok_or(MockLendingError::MathOverflow)?;
+        reserve.store(&ctx.accounts.reserve)?;
+
+        msg!("Repaid {} liquidity", liquidity_amount);
+        Ok(())
+    }
+
+    /// Test-only: borrowers accrue `interest`, of which `protocol_fee` is
+    /// kept by the protocol rather than earned by depositors
+    #[instruction(discriminator = [203])]
+    pub fn accrue_interest(
+        ctx: Context<UpdateReserve>,
+        interest: u64,
+        protocol_fee: u64,
+    ) -> Result<()> {
+        require!(protocol_fee <= interest, MockLendingError::InvalidAmount);
+        let mut reserve =
+            ReserveState::load(&ctx.accounts.reserve, &ctx.accounts.lending_market.key())?;
+
+        reserve.borrowed_amount_wads = reserve
+            .borrowed_amount_wads
+            .checked_add(wads(interest))
+            .ok_or(MockLendingError::MathOverflow)?;
+        reserve.accumulated_protocol_fees_wads = reserve
+            .accumulated_protocol_fees_wads
+            .checked_add(wads(protocol_fee))
+            .ok_or(MockLendingError::MathOverflow)?;
+        reserve.store(&ctx.accounts.reserve)?;
+
+        msg!(
+            "Accrued {} interest ({} protocol fee)",
+            interest,
+            protocol_fee
+        );
+        Ok(())
+    }
+}
+
+/// Size of the token-lending `Reserve` account
+pub const RESERVE_LEN: usize = 619;
+
+/// 1.0 in the reserve's fixed-point `Decimal` fields
+const WAD: u128 = 1_000_000_000_000_000_000;
+
+// Offsets of the fields the mock uses in the `Reserve` layout
+const LENDING_MARKET: usize = 10;
+const LIQUIDITY_MINT: usize = 42;
+const LIQUIDITY_MINT_DECIMALS: usize = 74;
+const LIQUIDITY_SUPPLY: usize = 75;
+const LIQUIDITY_AVAILABLE_AMOUNT: usize = 171;
+const LIQUIDITY_BORROWED_AMOUNT_WADS: usize = 179;
+const COLLATERAL_MINT: usize = 227;
+const COLLATERAL_MINT_TOTAL_SUPPLY: usize = 259;
+const LIQUIDITY_ACCUMULATED_PROTOCOL_FEES_WADS: usize = 373;
+
+/// The fields of a `Reserve` the mock reads and writes
+struct ReserveState {
+    lending_market: Pubkey,
+    liquidity_mint: Pubkey,
+    liquidity_mint_decimals: u8,
+    liquidity_supply: Pubkey,
+    available_amount: u64,
+    borrowed_amount_wads: u128,
+    collateral_mint: Pubkey,
+    collateral_mint_total_supply: u64,
+    accumulated_protocol_fees_wads: u128,
+}
+
+impl ReserveState {
+    fn load(info: &AccountInfo, lending_market: &Pubkey) -> Result<Self> {
+        require_keys_eq!(*info.owner, crate::ID, MockLendingError::ReserveMismatch);
+        let data = info.try_borrow_data()?;
+        require!(data.len() == RESERVE_LEN, MockLendingError::ReserveMismatch);
+
+        let pubkey = |at: usize| Pubkey::new_from_array(data[at..at + 32].try_into().unwrap());
+        let u64_at = |at: usize| u64::from_le_bytes(data[at..at + 8].try_into().unwrap());
+        let u128_at = |at: usize| u128::from_le_bytes(data[at..at + 16].try_into().unwrap());
+
+        let reserve = Self {
+            lending_market: pubkey(LENDING_MARKET),
+            liquidity_mint: pubkey(LIQUIDITY_MINT),
+            liquidity_mint_decimals: data[LIQUIDITY_MINT_DECIMALS],
+            liquidity_supply: pubkey(LIQUIDITY_SUPPLY),
+            available_amount: u64_at(LIQUIDITY_AVAILABLE_AMOUNT),
+            borrowed_amount_wads: u128_at(LIQUIDITY_BORROWED_AMOUNT_WADS),
+            collateral_mint: pubkey(COLLATERAL_MINT),
+            collateral_mint_total_supply: u64_at(COLLATERAL_MINT_TOTAL_SUPPLY),
+            accumulated_protocol_fees_wads: u128_at(LIQUIDITY_ACCUMULATED_PROTOCOL_FEES_WADS),
+        };
+        require_keys_eq!(
+            reserve.lending_market,
+            *lending_market,
+            MockLendingError::ReserveMismatch
+        );
+        Ok(reserve)
+    }
+
+    fn store(&self, info: &AccountInfo) -> Result<()> {
+        let mut data = info.try_borrow_mut_data()?;
+        data[0] = 1; // version
+        data[LENDING_MARKET..LENDING_MARKET + 32].copy_from_slice(self.lending_market.as_ref());
+        data[LIQUIDITY_MINT..LIQUIDITY_MINT + 32].copy_from_slice(self.liquidity_mint.as_ref());
+        data[LIQUIDITY_MINT_DECIMALS] = self.liquidity_mint_decimals;
+        data[LIQUIDITY_SUPPLY..LIQUIDITY_SUPPLY + 32]
+            .copy_from_slice(self.liquidity_supply.as_ref());
+        data[LIQUIDITY_AVAILABLE_AMOUNT..LIQUIDITY_AVAILABLE_AMOUNT + 8]
+            .copy_from_slice(&self.available_amount.to_le_bytes());
+        data[LIQUIDITY_BORROWED_AMOUNT_WADS..LIQUIDITY_BORROWED_AMOUNT_WADS + 16]
+            .copy_from_slice(&self.borrowed_amount_wads.to_le_bytes());
+        data[COLLATERAL_MINT..COLLATERAL_MINT + 32].copy_from_slice(self.collateral_mint.as_ref());
+        data[COLLATERAL_MINT_TOTAL_SUPPLY..COLLATERAL_MINT_TOTAL_SUPPLY + 8]
+            .copy_from_slice(&self.collateral_mint_total_supply.to_le_bytes());
+        data[LIQUIDITY_ACCUMULATED_PROTOCOL_FEES_WADS
+            ..LIQUIDITY_ACCUMULATED_PROTOCOL_FEES_WADS + 16]
+            .copy_from_slice(&self.accumulated_protocol_fees_wads.to_le_bytes());
+        Ok(())
+    }
+
+    fn check(&self, liquidity_supply: &Pubkey, collateral_mint: &Pubkey) -> Result<()> {
+        require_keys_eq!(
+            *liquidity_supply,
+            self.liquidity_supply,
+            MockLendingError::ReserveMismatch
+        );
+        require_keys_eq!(
+            *collateral_mint,
+            self.collateral_mint,
+            MockLendingError::ReserveMismatch
+        );
+        Ok(())
+    }
+
+    /// Liquidity owed to depositors: available + borrowed - protocol fees
+    fn total_liquidity(&self) -> Result<u64> {
+        let total = (self.available_amount as u128)
+            .checked_mul(WAD)
+            .and_then(|available| available.checked_add(self.borrowed_amount_wads))
+            .ok_or(MockLendingError::MathOverflow)?
+            .saturating_sub(self.accumulated_protocol_fees_wads)
+            / WAD;
+        require!(total <= u64::MAX as u128, MockLendingError::MathOverflow);
+        Ok(total as u64)
+    }
+}
+
+fn wads(amount: u64) -> u128 {
+    amount as u128 * WAD
+}
+
+#[derive(Accounts)]
+pub struct InitReserve<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    /// CHECK: only used as an identity and PDA seed
+    pub lending_market: UncheckedAccount<'info>,
+
+    /// CHECK: PDA that owns the reserve's supply and collateral mint
+    #[account(seeds = [lending_market.key().as_ref()], bump)]
+    pub lending_market_authority: UncheckedAccount<'info>,
+
+    /// CHECK: created here with the token-lending `Reserve` layout
+    #[account(
+        init,
+        payer = payer,
+        space = RESERVE_LEN,
+        seeds = [b"reserve", lending_market.key().as_ref(), liquidity_mint.key().as_ref()],
+        bump
+    )]
+    pub reserve: UncheckedAccount<'info>,
+
+    pub liquidity_mint: Account<'info, Mint>,
+
+    #[account(
+        init,
+        payer = payer,
+        seeds = [b"liquidity_supply", reserve.key().as_ref()],
+        bump,
+        token::mint = liquidity_mint,
+        token::authority = lending_market_authority,
+    )]
+    pub reserve_liquidity_supply: Account<'info, TokenAccount>,
+
+    #[account(
+        init,
+        payer = payer,
+        seeds = [b"collateral_mint", reserve.key().as_ref()],
+        bump,
+        mint::decimals = liquidity_mint.decimals,
+        mint::authority = lending_market_authority,
+    )]
+    pub reserve_collateral_mint: Account<'info, Mint>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+pub struct DepositReserveLiquidity<'info> {
+    #[account(mut)]
+    pub source_liquidity: Account<'info, TokenAccount>,
+
+    #[account(mut)]
+    pub destination_collateral: Account<'info, TokenAccount>,
+
+    /// CHECK: parsed and matched against the market
+    #[account(mut)]
+    pub reserve: UncheckedAccount<'info>,
+
+    #[account(mut)]
+    pub reserve_liquidity_supply: Account<'info, TokenAccount>,
+
+    #[account(mut)]
+    pub reserve_collateral_mint: Account<'info, Mint>,
+
+    /// CHECK: matched against the reserve
+    pub lending_market: UncheckedAccount<'info>,
+
+    /// CHECK: PDA signer for the reserve
+    #[account(seeds = [lending_market.key().as_ref()], bump)]
+    pub lending_market_authority: UncheckedAccount<'info>,
+
+    pub user_transfer_authority: Signer<'info>,
+
+    pub clock: Sysvar<'info, Clock>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct RedeemReserveCollateral<'info> {
+    #[account(mut)]
+    pub source_collateral: Account<'info, TokenAccount>,
+
+    #[account(mut)]
+    pub destination_liquidity: Account<'info, TokenAccount>,
+
+    /// CHECK: parsed and matched against the market
+    #[account(mut)]
+    pub reserve: UncheckedAccount<'info>,
+
+    #[account(mut)]
+    pub reserve_collateral_mint: Account<'info, Mint>,
+
+    #[account(mut)]
+    pub reserve_liquidity_supply: Account<'info, TokenAccount>,
+
+    /// CHECK: matched against the reserve
+    pub lending_market: UncheckedAccount<'info>,
+
+    /// CHECK: PDA signer for the reserve
+    #[account(seeds = [lending_market.key().as_ref()], bump)]
+    pub lending_market_authority: UncheckedAccount<'info>,
+
+    pub user_transfer_authority: Signer<'info>,
+
+    pub clock: Sysvar<'info, Clock>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateReserve<'info> {
+    /// CHECK: parsed and matched against the market
+    #[account(mut)]
+    pub reserve: UncheckedAccount<'info>,
+
+    /// CHECK: matched against the reserve
+    pub lending_market: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct BorrowLiquidity<'info> {
+    #[account(mut)]
+    pub destination_liquidity: Account<'info, TokenAccount>,
+
+    /// CHECK: parsed and matched against the market
+    #[account(mut)]
+    pub reserve: UncheckedAccount<'info>,
+
+    #[account(mut)]
+    pub reserve_liquidity_supply: Account<'info, TokenAccount>,
+
+    /// CHECK: matched against the reserve
+    pub lending_market: UncheckedAccount<'info>,
+
+    /// CHECK: PDA signer for the reserve
+    #[account(seeds = [lending_market.key().as_ref()], bump)]
+    pub lending_market_authority: UncheckedAccount<'info>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct RepayLiquidity<'info> {
+    #[account(mut)]
+    pub source_liquidity: Account<'info, TokenAccount>,
+
+    /// CHECK: parsed and matched against the market
+    #[account(mut)]
+    pub reserve: UncheckedAccount<'info>,
+
+    #[account(mut)]
+    pub reserve_liquidity_supply: Account<'info, TokenAccount>,
+
+    /// CHECK: matched against the reserve
+    pub lending_market: UncheckedAccount<'info>,
+
+    pub user_transfer_authority: Signer<'info>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[error_code]
+pub enum MockLendingError {
+    #[msg("Invalid amount")]
+    InvalidAmount,
+    #[msg("Math overflow occurred")]
+    MathOverflow,
+    #[msg("Account does not belong to this reserve")]
+    ReserveMismatch,
+    #[msg("Not enough available liquidity in the reserve")]
+    InsufficientLiquidity,
+}
+
+fn mul_div(value: u64, numerator: u64, denominator: u64) -> Result<u64> {
+    let result = (value as u128)
+        .checked_mul(numerator as u128)
+        .ok_or(MockLendingError::MathOverflow)?
+        .checked_div(denominator as u128)
+        .ok_or(MockLendingError::MathOverflow)?;
+    require!(result <= u64::MAX as u128, MockLendingError::MathOverflow);
+    Ok(result as u64)
+}
@@ -0,0 +1,103 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::{self, Token, TokenAccount};
+
+declare_id!("4NeDuQW2K6nsKL7bt4dgFPwHLSQ87MiDXYbRUAhKGcxc");
+
+// Minimal swap program with the discriminator and leading accounts of
+// Jupiter's `route`: token program, user transfer authority, user source and
+// destination token accounts, then the optional `destination_token_account`
+// that receives the output instead when set. The caller names the fill:
+// `amount_in` goes into the pool and `amount_out` comes back out of it, so a
+// route leaves the same balance changes a real fill would.
+#[program]
+pub mod mock_swap {
+    use super::*;
+
+    #[instruction(discriminator = [229, 23, 203, 151, 122, 227, 173, 42])]
+    pub fn swap(ctx: Context<Swap>, amount_in: u64, amount_out: u64) -> Result<()> {
+        require!(amount_in > 0, MockSwapError::InvalidAmount);
+
+        token::transfer(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                token::Transfer {
+                    from: ctx.accounts.user_source_token_account.to_account_info(),
+                    to: ctx.accounts.pool_source.to_account_info(),
+                    authority: ctx.accounts.user_transfer_authority.to_account_info(),
+                },
+            ),
+            amount_in,
+        )?;
+
+        let destination = match &ctx.accounts.destination_token_account {
+            Some(account) => account.to_account_info(),
+            None => ctx
+                .accounts
+                .user_destination_token_account
+                .to_account_info(),
+        };
+        let seeds = &[b"pool_authority".as_ref(), &[ctx.bumps.pool_authority]];
+        let signer_seeds = &[&seeds[..]];
+
+        if amount_out > 0 {
+            token::transfer(
+                CpiContext::new_with_signer(
+                    ctx.accounts.token_program.to_account_info(),
+                    token::Transfer {
+                        from: ctx.accounts.pool_destination.to_account_info(),
+                        to: destination,
+                        authority: ctx.accounts.pool_authority.to_account_info(),
+                    },
+                    signer_seeds,
+                ),
+                amount_out,
+            )?;
+        }
+
+        msg!("Swapped {} for {}", amount_in, amount_out);
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+pub struct Swap<'info> {
+    pub token_program: Program<'info, Token>,
+
+    pub user_transfer_authority: Signer<'info>,
+
+    #[account(mut)]
+    pub user_source_token_account: Account<'info, TokenAccount>,
+
+    #[account(mut)]
+    pub user_destination_token_account: Account<'info, TokenAccount>,
+
+    /// Receives the output instead of the user's destination when set
+    #[account(mut)]
+    pub destination_token_account: Option<Account<'info, TokenAccount>>,
+
+    /// Pool account the input is paid into
+    #[account(
+        mut,
+        token::mint = user_source_token_account.mint,
+        token::authority = pool_authority,
+    )]
+    pub pool_source: Account<'info, TokenAccount>,
+
+    /// Pool account the output is paid from
+    #[account(
+        mut,
+        token::mint = user_destination_token_account.mint,
+        token::authority = pool_authority,
+    )]
+    pub pool_destination: Account<'info, TokenAccount>,
+
+    /// CHECK: PDA that owns the pool accounts
+    #[account(seeds = [b"pool_authority"], bump)]
+    pub pool_authority: UncheckedAccount<'info>,
+}
+
+#[error_code]
+pub enum MockSwapError {
+    #[msg("Invalid amount")]
+    InvalidAmount,
+}
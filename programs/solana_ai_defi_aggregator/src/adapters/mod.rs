@@ -0,0 +1,23 @@
+pub mod token_lending;
+
+pub use token_lending::*;
+
+use anchor_lang::prelude::*;
+
+/// A protocol the vault can park base tokens in to earn yield.
+///
+/// Amounts are in base-token units except where noted; `signer_seeds` are the
+/// vault PDA seeds, since the vault owns both the liquidity and the receipt.
+pub trait LendingAdapter {
+    /// Move `amount` base tokens from the vault into the protocol
+    fn deposit(&self, amount: u64, signer_seeds: &[&[&[u8]]]) -> Result<()>;
+
+    /// Redeem `collateral` receipt tokens back into base tokens
+    fn withdraw(&self, collateral: u64, signer_seeds: &[&[&[u8]]]) -> Result<()>;
+
+    /// Base-token value of the vault's whole position
+    fn balance(&self) -> Result<u64>;
+
+    /// Receipt tokens to redeem for at least `amount` base tokens (capped at the position)
+    fn collateral_for(&self, amount: u64) -> Result<u64>;
+}
@@ -0,0 +1,195 @@
+use crate::adapters::LendingAdapter;
+use crate::error::vault_error::VaultError;
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::instruction::{AccountMeta, Instruction};
+use anchor_lang::solana_program::program::invoke_signed;
+use anchor_spl::token::{accessor, Mint};
+
+/// SPL token-lending instruction tags
+const DEPOSIT_RESERVE_LIQUIDITY: u8 = 4;
+const REDEEM_RESERVE_COLLATERAL: u8 = 5;
+
+/// Offsets into the token-lending `Reserve` account, in the layout Solend
+/// extends with accumulated protocol fees
+const RESERVE_LIQUIDITY_SUPPLY: usize = 75;
+const RESERVE_AVAILABLE_AMOUNT: usize = 171;
+const RESERVE_BORROWED_AMOUNT_WADS: usize = 179;
+const RESERVE_COLLATERAL_MINT: usize = 227;
+const RESERVE_ACCUMULATED_PROTOCOL_FEES_WADS: usize = 373;
+const RESERVE_MIN_LEN: usize = RESERVE_ACCUMULATED_PROTOCOL_FEES_WADS + 16;
+
+/// 1.0 in the reserve's fixed-point `Decimal` fields
+const WAD: u128 = 1_000_000_000_000_000_000;
+
+/// Adapter for programs exposing the SPL token-lending reserve interface.
+///
+/// Deposits go through `DepositReserveLiquidity` and withdrawals through
+/// `RedeemReserveCollateral`. The position is valued from the reserve's own
+/// books, liquidity available plus borrowed less protocol fees, against the
+/// collateral mint's supply. Tokens sent straight to the supply account
+/// don't move it, and borrows don't either. Lending programs accrue
+/// interest when the reserve is refreshed, so the mark is as of the last
+/// refresh.
+pub struct TokenLendingAdapter<'a, 'info> {
+    pub lending_program: &'a AccountInfo<'info>,
+    pub reserve: &'a AccountInfo<'info>,
+    pub reserve_liquidity_supply: &'a AccountInfo<'info>,
+    pub reserve_collateral_mint: &'a AccountInfo<'info>,
+    pub lending_market: &'a AccountInfo<'info>,
+    pub lending_market_authority: &'a AccountInfo<'info>,
+    /// Vault's base token account
+    pub liquidity_account: &'a AccountInfo<'info>,
+    /// Vault's collateral (receipt) token account
+    pub collateral_account: &'a AccountInfo<'info>,
+    /// Vault PDA (owner of both token accounts)
+    pub authority: &'a AccountInfo<'info>,
+    pub clock: &'a AccountInfo<'info>,
+    pub token_program: &'a AccountInfo<'info>,
+}
+
+impl<'a, 'info> TokenLendingAdapter<'a, 'info> {
+    fn invoke(
+        &self,
+        tag: u8,
+        amount: u64,
+        accounts: Vec<&'a AccountInfo<'info>>,
+        signer_seeds: &[&[&[u8]]],
+    ) -> Result<()> {
+        let mut data = Vec::with_capacity(9);
+        data.push(tag);
+        data.extend_from_slice(&amount.to_le_bytes());
+
+        let metas = accounts
+            .iter()
+            .map(|info| {
+                if info.is_writable {
+                    AccountMeta::new(info.key(), info.key() == self.authority.key())
+                } else {
+                    AccountMeta::new_readonly(info.key(), info.key() == self.authority.key())
+                }
+            })
+            .collect();
+
+        let ix = Instruction {
+            program_id: self.lending_program.key(),
+            accounts: metas,
+            data,
+        };
+
+        let infos: Vec<AccountInfo<'info>> = accounts.into_iter().cloned().collect();
+        invoke_signed(&ix, &infos, signer_seeds)?;
+        Ok(())
+    }
+
+    /// (liquidity owed to depositors, collateral supply)
+    fn exchange_rate(&self) -> Result<(u64, u64)> {
+        let total_liquidity = reserve_liquidity(&self.reserve.try_borrow_data()?)?;
+        let collateral_supply =
+            Mint::try_deserialize(&mut &self.reserve_collateral_mint.try_borrow_data()?[..])?
+                .supply;
+        Ok((total_liquidity, collateral_supply))
+    }
+}
+
+/// The reserve's liquidity supply and collateral mint, as it records them
+pub fn reserve_accounts(data: &[u8]) -> Result<(Pubkey, Pubkey)> {
+    require!(data.len() >= RESERVE_MIN_LEN, VaultError::ReserveMismatch);
+    let pubkey_at = |at: usize| Pubkey::new_from_array(data[at..at + 32].try_into().unwrap());
+    Ok((
+        pubkey_at(RESERVE_LIQUIDITY_SUPPLY),
+        pubkey_at(RESERVE_COLLATERAL_MINT),
+    ))
+}
+
+/// Liquidity the reserve owes its depositors: available + borrowed - protocol fees
+fn reserve_liquidity(data: &[u8]) -> Result<u64> {
+    require!(data.len() >= RESERVE_MIN_LEN, VaultError::ReserveMismatch);
+    let u128_at = |at: usize| u128::from_le_bytes(data[at..at + 16].try_into().unwrap());
+    let available = u64::from_le_bytes(
+        data[RESERVE_AVAILABLE_AMOUNT..RESERVE_AVAILABLE_AMOUNT + 8]
+            .try_into()
+            .unwrap(),
+    );
+
+    let total = (available as u128)
+        .checked_mul(WAD)
+        .and_then(|available| available.checked_add(u128_at(RESERVE_BORROWED_AMOUNT_WADS)))
+        .ok_or(VaultError::MathOverflow)?
+        .saturating_sub(u128_at(RESERVE_ACCUMULATED_PROTOCOL_FEES_WADS))
+        / WAD;
+    require!(total <= u64::MAX as u128, VaultError::MathOverflow);
+    Ok(total as u64)
+}
+
+impl<'a, 'info> LendingAdapter for TokenLendingAdapter<'a, 'info> {
+    fn deposit(&self, amount: u64, signer_seeds: &[&[&[u8]]]) -> Result<()> {
+        msg!("Token lending: depositing {} liquidity", amount);
+        self.invoke(
+            DEPOSIT_RESERVE_LIQUIDITY,
+            amount,
+            vec![
+                self.liquidity_account,
+                self.collateral_account,
+                self.reserve,
+                self.reserve_liquidity_supply,
+                self.reserve_collateral_mint,
+                self.lending_market,
+                self.lending_market_authority,
+                self.authority,
+                self.clock,
+                self.token_program,
+            ],
+            signer_seeds,
+        )
+    }
+
+    fn withdraw(&self, collateral: u64, signer_seeds: &[&[&[u8]]]) -> Result<()> {
+        msg!("Token lending: redeeming {} collateral", collateral);
+        self.invoke(
+            REDEEM_RESERVE_COLLATERAL,
+            collateral,
+            vec![
+                self.collateral_account,
+                self.liquidity_account,
+                self.reserve,
+                self.reserve_collateral_mint,
+                self.reserve_liquidity_supply,
+                self.lending_market,
+                self.lending_market_authority,
+                self.authority,
+                self.clock,
+                self.token_program,
+            ],
+            signer_seeds,
+        )
+    }
+
+    fn balance(&self) -> Result<u64> {
+        let collateral = accessor::amount(self.collateral_account)?;
+        let (total_liquidity, collateral_supply) = self.exchange_rate()?;
+        if collateral == 0 || collateral_supply == 0 {
+            return Ok(0);
+        }
+
+        // liquidity = collateral * total_liquidity / collateral_supply (rounded down)
+        let value = (collateral as u128)
+            .checked_mul(total_liquidity as u128)
+            .ok_or(VaultError::MathOverflow)?
+            / collateral_supply as u128;
+        require!(value <= u64::MAX as u128, VaultError::MathOverflow);
+        Ok(value as u64)
+    }
+
+    fn collateral_for(&self, amount: u64) -> Result<u64> {
+        let collateral = accessor::amount(self.collateral_account)?;
+        let (total_liquidity, collateral_supply) = self.exchange_rate()?;
+        require!(total_liquidity > 0, VaultError::InsufficientAssets);
+
+        // collateral = amount * collateral_supply / total_liquidity (rounded up)
+        let needed = (amount as u128)
+            .checked_mul(collateral_supply as u128)
+            .ok_or(VaultError::MathOverflow)?
+            .div_ceil(total_liquidity as u128);
+        Ok(needed.min(collateral as u128) as u64)
+    }
+}
@@ -0,0 +1,17 @@
+use anchor_lang::prelude::*;
+
+#[error_code]
+pub enum MigrationError {
+    #[msg("Account is not of the expected type")]
+    AccountTypeMismatch,
+    #[msg("Account is already at the current version")]
+    AlreadyMigrated,
+    #[msg("Account version is newer than this program")]
+    UnknownVersion,
+    #[msg("Account size and version match no known layout of this type")]
+    UnknownLayout,
+    #[msg("Vaults from the original mint-seeded layout cannot be migrated")]
+    BaselineVaultNotMigratable,
+    #[msg("Vault migrations need the vault's claim escrow as the first remaining account")]
+    ClaimEscrowRequired,
+}
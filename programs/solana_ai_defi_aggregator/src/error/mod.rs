@@ -1,7 +1,11 @@
 pub mod jupiter_swap_error;
+pub mod migration_error;
+pub mod risk_error;
 pub mod strategy_error;
 pub mod vault_error;
 
 pub use jupiter_swap_error::*;
+pub use migration_error::*;
+pub use risk_error::*;
 pub use strategy_error::*;
 pub use vault_error::*;
@@ -0,0 +1,27 @@
+use anchor_lang::prelude::*;
+
+#[error_code]
+pub enum RiskError {
+    #[msg("Math overflow occurred")]
+    MathOverflow,
+    #[msg("Trade exceeds max notional per execution")]
+    TradeNotionalExceeded,
+    #[msg("Trade exceeds max notional per 24h")]
+    DailyNotionalExceeded,
+    #[msg("Daily loss limit reached")]
+    DailyLossLimitReached,
+    #[msg("Execution cooldown not elapsed")]
+    ExecutionCooldown,
+    #[msg("Mint not allowed by risk policy")]
+    MintNotAllowed,
+    #[msg("Too many mints in allowlist")]
+    TooManyMints,
+    #[msg("Invalid risk limits")]
+    InvalidRiskLimits,
+    #[msg("Oracle price is stale")]
+    StalePrice,
+    #[msg("Invalid oracle price")]
+    InvalidPrice,
+    #[msg("Price feed does not match mint")]
+    PriceFeedMismatch,
+}
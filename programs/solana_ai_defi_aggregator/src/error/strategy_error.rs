@@ -11,4 +11,68 @@ pub enum StrategyError {
     InvalidPrice,
     #[msg("Unauthorized")]
     Unauthorized,
+    #[msg("Strategy has no payment mint")]
+    PaymentMintNotSet,
+    #[msg("Token account is not in the strategy's payment mint")]
+    PaymentMintMismatch,
+    #[msg("Token account does not belong to the expected owner")]
+    InvalidPaymentAccount,
+    #[msg("Creator does not match the strategy")]
+    InvalidCreator,
+    #[msg("Commission exceeds the maximum")]
+    InvalidCommission,
+    #[msg("Subscription and grace periods must be non-negative")]
+    InvalidSubscriptionTerms,
+    #[msg("Strategy is a one-time purchase, not a subscription")]
+    NotASubscription,
+    #[msg("Strategy subscription has expired: renew it to execute")]
+    SubscriptionExpired,
+    #[msg("New parameters are identical to the current version")]
+    ParametersUnchanged,
+    #[msg("Max slippage exceeds 100%")]
+    InvalidSlippage,
+    #[msg("Pass the strategy version that superseded the pinned one")]
+    PinnedVersionMissing,
+    #[msg("Signer is not the execution attestor")]
+    NotAttestor,
+    #[msg("Execution does not belong to this strategy purchase")]
+    InvalidExecution,
+    #[msg("Execution result already recorded")]
+    ResultAlreadyRecorded,
+    #[msg("Invalid amount")]
+    InvalidAmount,
+    #[msg("Insufficient balance")]
+    InsufficientBalance,
+    #[msg("Token does not match the strategy parameters")]
+    TokenMismatch,
+    #[msg("Execution interval has not elapsed since the last execution")]
+    ExecutionTooSoon,
+    #[msg("Protected account found in remaining accounts")]
+    ProtectedAccountInRemainingAccounts,
+    #[msg("Swap spent more than the input amount")]
+    UnexpectedInputAmount,
+    #[msg("Slippage exceeded")]
+    SlippageExceeded,
+    #[msg("Output below the strategy's minimum profit")]
+    ProfitBelowMinimum,
+    #[msg("Math overflow")]
+    MathOverflow,
+    #[msg("Profit share exceeds the maximum")]
+    InvalidProfitShare,
+    #[msg("Profit share can only be lowered once the strategy has buyers")]
+    ProfitShareIncrease,
+    #[msg("Pass the creator's output token account to pay the profit share")]
+    CreatorAccountMissing,
+    #[msg("Strategy price is above the buyer's maximum")]
+    PriceAboveMaximum,
+    #[msg("Arbitrage must route back into its input token")]
+    ArbitrageNotRoundTrip,
+    #[msg("Input and output token accounts must differ")]
+    DuplicateTokenAccount,
+    #[msg("Swap route must pay out to the executor's output token account")]
+    RouteOutputMismatch,
+    #[msg("Swap instruction must be Jupiter's route")]
+    UnsupportedSwapInstruction,
+    #[msg("Strategies with a profit share must realize profit in the execution: a round trip, not yield farming")]
+    ProfitShareNotRealizable,
 }
@@ -35,4 +35,84 @@ pub enum VaultError {
     SlippageExceeded,
     #[msg("Invalid fee rate")]
     InvalidFeeRate,
+    #[msg("Deposit below vault minimum")]
+    DepositTooSmall,
+    #[msg("Vault deposit cap exceeded")]
+    DepositCapExceeded,
+    #[msg("Per-depositor cap exceeded")]
+    UserDepositCapExceeded,
+    #[msg("Withdrawal epoch has no queued shares")]
+    EmptyWithdrawEpoch,
+    #[msg("Withdrawal epoch not settled yet")]
+    EpochNotSettled,
+    #[msg("Invalid remaining accounts")]
+    InvalidRemainingAccounts,
+    #[msg("Token account passed more than once")]
+    DuplicateTokenAccount,
+    #[msg("Not enough shares")]
+    InsufficientShares,
+    #[msg("Vault not found in registry")]
+    VaultNotRegistered,
+    #[msg("Target weights must be unique mints summing to 10000 bps")]
+    InvalidTargetWeights,
+    #[msg("Vault is within its drift threshold")]
+    RebalanceNotNeeded,
+    #[msg("Rebalance must sell an overweight mint for an underweight one")]
+    InvalidRebalanceDirection,
+    #[msg("Rebalance did not move the vault toward target")]
+    DeviationNotReduced,
+    #[msg("Holding missing from rebalance accounts")]
+    MissingHolding,
+    #[msg("Swap used more input than allowed")]
+    UnexpectedInputAmount,
+    #[msg("Snapshot interval must be positive")]
+    InvalidSnapshotInterval,
+    #[msg("Too early for the next NAV snapshot")]
+    SnapshotNotDue,
+    #[msg("Vault is shut down")]
+    VaultShutdown,
+    #[msg("Vault is shut down: withdraw in kind")]
+    WithdrawInKindOnly,
+    #[msg("Vault is not shut down")]
+    VaultNotShutdown,
+    #[msg("Status can only be set to Active or Paused")]
+    InvalidVaultStatus,
+    #[msg("Lock period must be non-negative and penalty at most 5000 bps")]
+    InvalidLockup,
+    #[msg("Depositor is not on the vault allowlist")]
+    DepositorNotAllowlisted,
+    #[msg("Invalid merkle proof for depositor")]
+    InvalidMerkleProof,
+    #[msg("Vault still has shares or tokens")]
+    VaultNotEmpty,
+    #[msg("Vault total_shares does not match the share mint supply")]
+    ShareSupplyMismatch,
+    #[msg("Vault total_assets exceeds its token balance")]
+    AssetsExceedBalance,
+    #[msg("Child must be another vault with the same base token and no children of its own")]
+    InvalidChildVault,
+    #[msg("Too many child vaults")]
+    TooManyChildVaults,
+    #[msg("Every child vault must be passed exactly once")]
+    ChildVaultsMissing,
+    #[msg("Vault still holds child vaults")]
+    VaultHasChildren,
+    #[msg("Token mint has too many decimals for the share mint offset")]
+    InvalidMintDecimals,
+    #[msg("Strategies already hold the maximum number of mints")]
+    TooManyStrategyMints,
+    #[msg("Lending program is not approved by the admin")]
+    LendingProgramNotApproved,
+    #[msg("Withdrawal epoch already settled")]
+    EpochAlreadySettled,
+    #[msg("Deposits into a vault with a lockup need the locked share account")]
+    LockedShareAccountRequired,
+    #[msg("Account does not match the lending reserve")]
+    ReserveMismatch,
+    #[msg("Settled withdrawals are still waiting to be claimed")]
+    ClaimsPending,
+    #[msg("A vault token account with a balance needs a fee recipient account of its mint")]
+    SweepAccountRequired,
+    #[msg("Vault is held as a child of another vault")]
+    VaultIsChild,
 }
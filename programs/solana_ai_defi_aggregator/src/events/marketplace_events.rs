@@ -0,0 +1,103 @@
+use anchor_lang::prelude::*;
+
+#[event]
+pub struct StrategyPurchased {
+    pub strategy: Pubkey,
+    pub buyer: Pubkey,
+    pub creator: Pubkey,
+    pub payment_mint: Pubkey,
+    /// Price paid by the buyer
+    pub gross_amount: u64,
+    /// Protocol share, sent to the fee treasury
+    pub commission: u64,
+    /// What the creator received
+    pub creator_amount: u64,
+    /// End of the first subscription period (0 = one-time purchase)
+    pub expires_at: i64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct SubscriptionRenewed {
+    pub strategy: Pubkey,
+    pub subscriber: Pubkey,
+    pub gross_amount: u64,
+    pub commission: u64,
+    pub creator_amount: u64,
+    pub expires_at: i64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ProfitShareUpdated {
+    pub strategy: Pubkey,
+    pub old_profit_share_bps: u16,
+    pub new_profit_share_bps: u16,
+}
+
+#[event]
+pub struct CreatorProfitSharePaid {
+    pub strategy: Pubkey,
+    pub execution: Pubkey,
+    pub executor: Pubkey,
+    pub creator: Pubkey,
+    /// Output mint the share was paid in
+    pub mint: Pubkey,
+    /// Tokens transferred to the creator
+    pub amount: u64,
+    /// USD value of the share at the oracle price, 6 decimals (added to
+    /// `creator_earnings`)
+    pub value: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct SubscriptionTermsUpdated {
+    pub strategy: Pubkey,
+    pub subscription_period: i64,
+    pub grace_period: i64,
+}
+
+#[event]
+pub struct StrategyParametersUpdated {
+    pub strategy: Pubkey,
+    pub parameters_version: u32,
+    /// PARAM_* bits of the fields that changed
+    pub changed_fields: u8,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct StrategyVersionAdopted {
+    pub strategy: Pubkey,
+    pub owner: Pubkey,
+    pub from_version: u32,
+    pub to_version: u32,
+}
+
+#[event]
+pub struct ExecutionResultRecorded {
+    pub strategy: Pubkey,
+    pub execution: Pubkey,
+    pub executor: Pubkey,
+    /// Who vouched for the result
+    pub attestor: Pubkey,
+    pub output_amount: u64,
+    pub profit: i64,
+    pub success: bool,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ExecutionAttestorUpdated {
+    pub old_attestor: Pubkey,
+    pub new_attestor: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct MarketplaceCommissionUpdated {
+    pub old_commission_bps: u16,
+    pub new_commission_bps: u16,
+    pub timestamp: i64,
+}
@@ -1,5 +1,11 @@
+pub mod marketplace_events;
+pub mod migration_events;
+pub mod risk_events;
 pub mod swap_events;
 pub mod vault_events;
 
+pub use marketplace_events::*;
+pub use migration_events::*;
+pub use risk_events::*;
 pub use swap_events::*;
 pub use vault_events::*;
@@ -0,0 +1,26 @@
+use anchor_lang::prelude::*;
+
+#[event]
+pub struct PriceFeedUpdated {
+    pub mint: Pubkey,
+    pub price: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct RiskPolicyUpdated {
+    pub vault: Pubkey,
+    pub max_trade_notional: u64,
+    pub max_daily_notional: u64,
+    pub max_daily_loss: u64,
+    pub min_execution_interval: i64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct LossLimitTriggered {
+    pub vault: Pubkey,
+    pub window_loss: u64,
+    pub max_daily_loss: u64,
+    pub timestamp: i64,
+}
@@ -1,5 +1,15 @@
 use crate::instructions::vault::JupiterStrategyType;
+use crate::state::{DepositGate, TargetWeight, VaultLifecycle};
 use anchor_lang::prelude::*;
+#[event]
+pub struct VaultRegistered {
+    pub vault: Pubkey,
+    pub vault_id: u64,
+    pub token_mint: Pubkey,
+    pub manager: Pubkey,
+    pub timestamp: i64,
+}
+
 #[event]
 pub struct DepositEvent {
     pub user: Pubkey,
@@ -7,6 +17,7 @@ pub struct DepositEvent {
     pub shares: u64,
     pub total_assets: u64,
     pub total_shares: u64,
+    pub lock_until: i64,
     pub timestamp: i64,
 }
 
@@ -20,6 +31,17 @@ pub struct WithdrawEvent {
     pub timestamp: i64,
 }
 
+#[event]
+pub struct WithdrawInKindEvent {
+    pub user: Pubkey,
+    pub shares: u64,
+    pub base_amount: u64,
+    pub other_accounts: u8,
+    pub total_assets: u64,
+    pub total_shares: u64,
+    pub timestamp: i64,
+}
+
 #[event]
 pub struct StrategyExecutedEvent {
     pub vault: Pubkey,
@@ -27,5 +49,355 @@ pub struct StrategyExecutedEvent {
     pub amount: u64,
     pub input_used: u64,
     pub output_received: u64,
+    /// Oracle value of the output minus the input spent, in base tokens
+    pub realized_pnl: i64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct DepositLimitsUpdated {
+    pub vault: Pubkey,
+    pub deposit_cap: u64,
+    pub user_deposit_cap: u64,
+    pub min_deposit: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct WithdrawRequestCancelledEvent {
+    pub vault: Pubkey,
+    pub user: Pubkey,
+    pub epoch: u64,
+    pub shares: u64,
+    pub epoch_shares: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct WithdrawRequestedEvent {
+    pub vault: Pubkey,
+    pub user: Pubkey,
+    pub epoch: u64,
+    pub shares: u64,
+    pub epoch_shares: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct WithdrawEpochSettledEvent {
+    pub vault: Pubkey,
+    pub epoch: u64,
+    pub shares: u64,
+    pub assets: u64,
+    pub share_price: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct WithdrawalClaimedEvent {
+    pub vault: Pubkey,
+    pub user: Pubkey,
+    pub epoch: u64,
+    pub shares: u64,
+    pub amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct VaultStatusUpdated {
+    pub vault: Pubkey,
+    pub old_status: VaultLifecycle,
+    pub new_status: VaultLifecycle,
+    pub updated_by: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct VaultShutdownTriggered {
+    pub vault: Pubkey,
+    pub triggered_by: Pubkey,
+    pub total_assets: u64,
+    pub adapter_assets: u64,
+    pub total_shares: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct AdapterUnwound {
+    pub vault: Pubkey,
+    pub received: u64,
+    pub adapter_assets: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct PerformanceFeeUpdated {
+    pub vault: Pubkey,
+    pub old_fee_bps: u16,
+    pub new_fee_bps: u16,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct FeeRecipientUpdated {
+    pub vault: Pubkey,
+    pub old_recipient: Pubkey,
+    pub new_recipient: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct FeesCrystallized {
+    pub vault: Pubkey,
+    pub fee_recipient: Pubkey,
+    pub fee_assets: u64,
+    pub fee_shares: u64,
+    pub previous_high_water_mark: u64,
+    pub high_water_mark: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ManagerUpdated {
+    pub vault: Pubkey,
+    pub old_manager: Pubkey,
+    pub new_manager: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct KeeperUpdated {
+    pub vault: Pubkey,
+    pub old_keeper: Pubkey,
+    pub new_keeper: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct GuardianUpdated {
+    pub vault: Pubkey,
+    pub old_guardian: Pubkey,
+    pub new_guardian: Pubkey,
+    pub timestamp: i64,
+}
+
+/// A mint's current share of vault value
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug)]
+pub struct MintWeight {
+    pub mint: Pubkey,
+    pub weight_bps: u16,
+}
+
+#[event]
+pub struct TargetWeightsUpdated {
+    pub vault: Pubkey,
+    pub target_weights: Vec<TargetWeight>,
+    pub threshold_bps: u16,
+    pub max_slippage_bps: u16,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct RebalanceExecuted {
+    pub vault: Pubkey,
+    pub input_mint: Pubkey,
+    pub output_mint: Pubkey,
+    pub input_used: u64,
+    pub output_received: u64,
+    /// Oracle value of the output minus the input, in base tokens
+    pub realized_pnl: i64,
+    pub weights_before: Vec<MintWeight>,
+    pub weights_after: Vec<MintWeight>,
+    pub deviation_before_bps: u64,
+    pub deviation_after_bps: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct YieldAdapterRegistered {
+    pub vault: Pubkey,
+    pub lending_program: Pubkey,
+    pub reserve: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct LendingProgramUpdated {
+    pub program: Pubkey,
+    pub approved: bool,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct AdapterDeployed {
+    pub vault: Pubkey,
+    pub amount: u64,
+    pub adapter_assets: u64,
+    pub total_assets: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct AdapterRecalled {
+    pub vault: Pubkey,
+    pub collateral: u64,
+    pub received: u64,
+    pub adapter_assets: u64,
+    pub total_assets: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct AdapterSynced {
+    pub vault: Pubkey,
+    pub previous_assets: u64,
+    pub adapter_assets: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct NavHistoryConfigured {
+    pub vault: Pubkey,
+    pub interval_secs: i64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct NavSnapshotRecorded {
+    pub vault: Pubkey,
+    pub share_price: u64,
+    pub total_assets: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct LockupUpdated {
+    pub vault: Pubkey,
+    pub lock_period: i64,
+    pub early_exit_penalty_bps: u16,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct LockedSharesReleased {
+    pub vault: Pubkey,
+    pub owner: Pubkey,
+    pub shares: u64,
+    /// Shares burned for releasing before `lock_until`
+    pub penalty_shares: u64,
+    pub lock_until: i64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct DepositGateUpdated {
+    pub vault: Pubkey,
+    pub deposit_gate: DepositGate,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct MerkleRootUpdated {
+    pub vault: Pubkey,
+    pub old_root: [u8; 32],
+    pub new_root: [u8; 32],
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct AllowlistUpdated {
+    pub vault: Pubkey,
+    pub depositor: Pubkey,
+    pub allowed: bool,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct VaultClosed {
+    pub vault: Pubkey,
+    pub vault_id: u64,
+    pub closed_by: Pubkey,
+    pub token_accounts_closed: u8,
+    pub nav_written_off: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct VaultSynced {
+    pub vault: Pubkey,
+    pub previous_total_assets: u64,
+    pub total_assets: u64,
+    /// New minus previous total assets (positive: surplus credited)
+    pub difference: i64,
+    pub synced_by: Pubkey,
+    pub timestamp: i64,
+}
+
+/// What re-valued the vault when a loss was recognized
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LossSource {
+    Strategy,
+    Rebalance,
+    Adapter,
+    Sync,
+    ChildVault,
+}
+
+#[event]
+pub struct VaultLossRecognized {
+    pub vault: Pubkey,
+    pub source: LossSource,
+    /// NAV lost, in base tokens
+    pub loss: u64,
+    pub share_price_before: u64,
+    pub share_price_after: u64,
+    pub cumulative_pnl: i64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ChildVaultAdded {
+    pub vault: Pubkey,
+    pub child_vault: Pubkey,
+    pub share_account: Pubkey,
+    pub child_count: u8,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ChildVaultRemoved {
+    pub vault: Pubkey,
+    pub child_vault: Pubkey,
+    pub child_count: u8,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ChildVaultAllocated {
+    pub vault: Pubkey,
+    pub child_vault: Pubkey,
+    pub amount: u64,
+    pub shares: u64,
+    pub child_assets: u64,
+    pub total_assets: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ChildVaultWithdrawn {
+    pub vault: Pubkey,
+    pub child_vault: Pubkey,
+    pub shares: u64,
+    pub received: u64,
+    pub child_assets: u64,
+    pub total_assets: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ChildVaultsSynced {
+    pub vault: Pubkey,
+    pub previous_child_assets: u64,
+    pub child_assets: u64,
     pub timestamp: i64,
 }
@@ -1,5 +1,5 @@
 use crate::events::swap_events::GlobalStateInitialized;
-use crate::state::GlobalState;
+use crate::state::{GlobalState, Versioned};
 use anchor_lang::prelude::*;
 
 #[derive(Accounts)]
@@ -10,7 +10,7 @@ pub struct InitializeGlobalState<'info> {
         seeds = [b"global_state"],
         bump,
         payer = admin,
-        space = 8 + GlobalState::LEN,
+        space = 8 + GlobalState::INIT_SPACE,
     )]
     pub global_state: Account<'info, GlobalState>,
 
@@ -29,6 +29,7 @@ pub fn initialize_global_state_handler(
     global_state.admin = ctx.accounts.admin.key();
     global_state.fee_rate = fee_rate;
     global_state.bump = ctx.bumps.global_state;
+    global_state.version = GlobalState::CURRENT_VERSION;
 
     emit!(GlobalStateInitialized {
         admin: global_state.admin.key(),
@@ -1,8 +1,10 @@
 use anchor_lang::prelude::*;
-use anchor_spl::token::{self, Token, TokenAccount, Transfer};
+use anchor_spl::token::{self, Mint, Token, TokenAccount, Transfer};
 
 use crate::error::StrategyError;
-use crate::state::{Strategy, UserStrategy};
+use crate::events::StrategyPurchased;
+use crate::state::{GlobalState, Strategy, UserStrategy, Versioned};
+use crate::utils::fee::calculate_fee;
 
 #[derive(Accounts)]
 pub struct BuyStrategy<'info> {
@@ -12,7 +14,7 @@ pub struct BuyStrategy<'info> {
     #[account(
         init,
         payer = buyer,
-        space = UserStrategy::MAX_SIZE,
+        space = 8 + UserStrategy::INIT_SPACE,
         seeds = [b"user_strategy", buyer.key().as_ref(), strategy.key().as_ref()],
         bump
     )]
@@ -21,36 +23,76 @@ pub struct BuyStrategy<'info> {
     #[account(mut)]
     pub buyer: Signer<'info>,
 
-    #[account(mut)]
+    #[account(
+        mut,
+        address = strategy.creator @ StrategyError::InvalidCreator
+    )]
     pub creator: SystemAccount<'info>,
 
-    // Payment in USDC
-    #[account(mut)]
+    /// Holds the protocol commission rate
+    #[account(
+        seeds = [b"global_state"],
+        bump = global_state.bump,
+    )]
+    pub global_state: Account<'info, GlobalState>,
+
+    #[account(address = strategy.payment_mint @ StrategyError::PaymentMintMismatch)]
+    pub payment_mint: Account<'info, Mint>,
+
+    // Payment in the strategy's payment mint
+    #[account(
+        mut,
+        constraint = buyer_token_account.mint == payment_mint.key() @ StrategyError::PaymentMintMismatch,
+        constraint = buyer_token_account.owner == buyer.key() @ StrategyError::InvalidPaymentAccount,
+    )]
     pub buyer_token_account: Account<'info, TokenAccount>,
 
-    #[account(mut)]
+    #[account(
+        mut,
+        constraint = creator_token_account.mint == payment_mint.key() @ StrategyError::PaymentMintMismatch,
+        constraint = creator_token_account.owner == strategy.creator @ StrategyError::InvalidPaymentAccount,
+    )]
     pub creator_token_account: Account<'info, TokenAccount>,
 
+    /// Fee treasury for the payment mint (receives the commission)
+    #[account(
+        mut,
+        seeds = [b"fee_vault", payment_mint.key().as_ref()],
+        bump,
+    )]
+    pub fee_vault_ata: Account<'info, TokenAccount>,
+
     pub token_program: Program<'info, Token>,
     pub system_program: Program<'info, System>,
 }
 
-pub fn buy_strategy(ctx: Context<BuyStrategy>) -> Result<()> {
+/// Buy at most `max_price` in `expected_payment_mint`: the creator can
+/// change both at any time, so the buyer states the terms they agreed to.
+pub fn buy_strategy(
+    ctx: Context<BuyStrategy>,
+    max_price: u64,
+    expected_payment_mint: Pubkey,
+) -> Result<()> {
     let strategy = &mut ctx.accounts.strategy;
 
     require!(strategy.is_active, StrategyError::StrategyInactive);
     require!(strategy.price > 0, StrategyError::InvalidPrice);
-
-    // Transfer USDC from buyer to creator
-    let cpi_accounts = Transfer {
-        from: ctx.accounts.buyer_token_account.to_account_info(),
-        to: ctx.accounts.creator_token_account.to_account_info(),
-        authority: ctx.accounts.buyer.to_account_info(),
-    };
-
-    let cpi_program = ctx.accounts.token_program.to_account_info();
-    let cpi_ctx = CpiContext::new(cpi_program, cpi_accounts);
-    token::transfer(cpi_ctx, strategy.price)?;
+    require!(
+        strategy.payment_mint != Pubkey::default(),
+        StrategyError::PaymentMintNotSet
+    );
+    strategy.check_terms(max_price, expected_payment_mint)?;
+
+    let gross_amount = strategy.price;
+    let (commission, creator_amount) = collect_payment(
+        &ctx.accounts.token_program,
+        &ctx.accounts.buyer,
+        &ctx.accounts.buyer_token_account,
+        &ctx.accounts.creator_token_account,
+        &ctx.accounts.fee_vault_ata,
+        gross_amount,
+        ctx.accounts.global_state.marketplace_commission_bps,
+    )?;
 
     // Update strategy stats
     strategy.total_purchases = strategy.total_purchases.checked_add(1).unwrap_or(0);
@@ -66,12 +108,70 @@ pub fn buy_strategy(ctx: Context<BuyStrategy>) -> Result<()> {
     user_strategy.times_executed = 0;
     user_strategy.total_profit = 0;
     user_strategy.bump = ctx.bumps.user_strategy;
+    user_strategy.version = UserStrategy::CURRENT_VERSION;
+    user_strategy.pinned_version = strategy.parameters_version;
+    // Subscriptions pay for one period; one-time purchases never expire
+    user_strategy.expires_at = if strategy.subscription_period > 0 {
+        clock
+            .unix_timestamp
+            .saturating_add(strategy.subscription_period)
+    } else {
+        0
+    };
 
     msg!(
-        "Strategy {} purchased by {}",
+        "Strategy {} purchased by {} ({} to creator, {} commission)",
         strategy.name,
-        ctx.accounts.buyer.key()
+        ctx.accounts.buyer.key(),
+        creator_amount,
+        commission
     );
 
+    emit!(StrategyPurchased {
+        strategy: strategy.key(),
+        buyer: ctx.accounts.buyer.key(),
+        creator: strategy.creator,
+        payment_mint: strategy.payment_mint,
+        gross_amount,
+        commission,
+        creator_amount,
+        expires_at: user_strategy.expires_at,
+        timestamp: clock.unix_timestamp,
+    });
+
     Ok(())
 }
+
+/// Pay `price` from `payer`: the commission (rounded up, like every other
+/// protocol fee) to the fee treasury, the rest to the creator.
+/// Returns `(commission, creator_amount)`.
+pub(crate) fn collect_payment<'info>(
+    token_program: &Program<'info, Token>,
+    payer: &Signer<'info>,
+    payer_token_account: &Account<'info, TokenAccount>,
+    creator_token_account: &Account<'info, TokenAccount>,
+    fee_vault: &Account<'info, TokenAccount>,
+    price: u64,
+    commission_bps: u16,
+) -> Result<(u64, u64)> {
+    let commission = calculate_fee(price, commission_bps)?;
+    let creator_amount = price - commission;
+
+    for (destination, amount) in [
+        (creator_token_account, creator_amount),
+        (fee_vault, commission),
+    ] {
+        if amount == 0 {
+            continue;
+        }
+        let cpi_accounts = Transfer {
+            from: payer_token_account.to_account_info(),
+            to: destination.to_account_info(),
+            authority: payer.to_account_info(),
+        };
+        let cpi_program = token_program.to_account_info();
+        token::transfer(CpiContext::new(cpi_program, cpi_accounts), amount)?;
+    }
+
+    Ok((commission, creator_amount))
+}
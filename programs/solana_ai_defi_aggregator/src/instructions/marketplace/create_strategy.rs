@@ -1,6 +1,7 @@
 use crate::error::StrategyError;
-use crate::state::{strategy::Strategy, StrategyParameters, StrategyType};
+use crate::state::{strategy::Strategy, StrategyParameters, StrategyType, Versioned};
 use anchor_lang::prelude::*;
+use anchor_spl::token::Mint;
 // Create Strategy
 //
 
@@ -10,7 +11,7 @@ pub struct CreateStrategy<'info> {
     #[account(
         init,
         payer = creator,
-        space = Strategy::MAX_SIZE,
+        space = 8 + Strategy::INIT_SPACE,
         seeds = [b"strategy", creator.key().as_ref(), strategy_id.to_le_bytes().as_ref()],
         bump
     )]
@@ -19,6 +20,9 @@ pub struct CreateStrategy<'info> {
     #[account(mut)]
     pub creator: Signer<'info>,
 
+    /// Mint buyers pay in
+    pub payment_mint: Account<'info, Mint>,
+
     pub system_program: Program<'info, System>,
 }
 
@@ -47,10 +51,14 @@ pub fn create_strategy(
     strategy.total_executions = 0;
     strategy.total_profit = 0;
     strategy.success_rate = 0;
+    strategy.successful_executions = 0;
+    strategy.recorded_executions = 0;
     strategy.created_at = clock.unix_timestamp;
     strategy.strategy_type = strategy_type;
     strategy.parameters = parameters;
     strategy.bump = ctx.bumps.strategy;
+    strategy.version = Strategy::CURRENT_VERSION;
+    strategy.payment_mint = ctx.accounts.payment_mint.key();
 
     msg!(
         "Strategy created : {} by {}",
@@ -1,6 +1,29 @@
-use crate::state::{Strategy, StrategyExecution, UserStrategy, Vault};
+use crate::error::StrategyError;
+use crate::events::CreatorProfitSharePaid;
+use crate::instructions::jupiter_swap::AccountMetaData;
+#[cfg(not(feature = "test"))]
+use crate::instructions::jupiter_swap::Jupiter;
+use crate::instructions::marketplace::apply_execution_result;
+use crate::state::{
+    PriceFeed, Strategy, StrategyExecution, StrategyType, StrategyVersion, UserStrategy, Versioned,
+};
+use crate::utils::{calculate_fee, calculate_profit};
 use anchor_lang::prelude::*;
-use anchor_spl::token::{self, Token, TokenAccount};
+use anchor_lang::solana_program::instruction::{AccountMeta, Instruction as SolInstruction};
+use anchor_lang::solana_program::program::invoke;
+use anchor_spl::token::{self, Mint, Token, TokenAccount, Transfer};
+
+const BPS: u128 = 10_000;
+
+/// Anchor discriminator of Jupiter's `route`, the only instruction whose
+/// account layout the destination checks below know
+const ROUTE_DISCRIMINATOR: [u8; 8] = [229, 23, 203, 151, 122, 227, 173, 42];
+
+/// Positions in Jupiter's `route` accounts of the user's destination token
+/// account and of the optional `destination_token_account`, which receives
+/// the output instead when set (the route program's id stands for unset)
+const ROUTE_DESTINATION_INDEX: usize = 3;
+const ROUTE_DESTINATION_OVERRIDE_INDEX: usize = 4;
 
 #[derive(Accounts)]
 pub struct ExecuteStrategy<'info> {
@@ -21,51 +44,413 @@ pub struct ExecuteStrategy<'info> {
     #[account(
         init,
         payer = executor,
-        space = StrategyExecution::MAX_SIZE,
+        space = 8 + StrategyExecution::INIT_SPACE,
         seeds = [b"execution", strategy.key().as_ref(), strategy.total_executions.to_le_bytes().as_ref()],
         bump
     )]
     pub execution: Account<'info, StrategyExecution>,
 
-    // Vault accounts for strategy execution
-    #[account(mut)]
-    pub vault: Account<'info, Vault>,
+    /// Version published right after the buyer's pinned one, which holds the
+    /// pinned parameters. Only needed once the strategy has moved past them.
+    #[account(
+        seeds = [
+            b"strategy_version",
+            strategy.key().as_ref(),
+            (user_strategy.pinned_version + 1).to_le_bytes().as_ref()
+        ],
+        bump = superseding_version.bump,
+    )]
+    pub superseding_version: Option<Account<'info, StrategyVersion>>,
 
-    #[account(mut)]
-    pub vault_token_account: Account<'info, TokenAccount>,
+    // ===========================
+    // Accounts for Jupiter Swap
+    // ===========================
+    /// Must match the pinned `parameters.input_token`
+    pub input_mint: Box<Account<'info, Mint>>,
+
+    /// Must match the pinned `parameters.output_token`
+    pub output_mint: Box<Account<'info, Mint>>,
+
+    /// Executor's input token account (the swap spends the executor's own tokens)
+    #[account(
+        mut,
+        constraint = executor_input_ata.owner == executor.key() @ StrategyError::InvalidPaymentAccount,
+        constraint = executor_input_ata.mint == input_mint.key() @ StrategyError::TokenMismatch,
+    )]
+    pub executor_input_ata: Box<Account<'info, TokenAccount>>,
+
+    /// Executor's output token account. Kept apart from the input account,
+    /// even on a round trip, so each side of the fill can be measured.
+    #[account(
+        mut,
+        constraint = executor_output_ata.owner == executor.key() @ StrategyError::InvalidPaymentAccount,
+        constraint = executor_output_ata.mint == output_mint.key() @ StrategyError::TokenMismatch,
+        constraint = executor_output_ata.key() != executor_input_ata.key() @ StrategyError::DuplicateTokenAccount,
+    )]
+    pub executor_output_ata: Box<Account<'info, TokenAccount>>,
+
+    /// Creator's output token account; receives the profit share. Only needed
+    /// when the strategy takes one.
+    #[account(
+        mut,
+        constraint = creator_output_ata.owner == strategy.creator @ StrategyError::InvalidPaymentAccount,
+        constraint = creator_output_ata.mint == output_mint.key() @ StrategyError::TokenMismatch,
+    )]
+    pub creator_output_ata: Option<Box<Account<'info, TokenAccount>>>,
+
+    /// Oracle price of the input mint
+    #[account(
+        seeds = [b"price_feed", input_mint.key().as_ref()],
+        bump = input_price_feed.bump,
+    )]
+    pub input_price_feed: Box<Account<'info, PriceFeed>>,
+
+    /// Oracle price of the output mint (bounds slippage, values the output)
+    #[account(
+        seeds = [b"price_feed", output_mint.key().as_ref()],
+        bump = output_price_feed.bump,
+    )]
+    pub output_price_feed: Box<Account<'info, PriceFeed>>,
+
+    /// Jupiter program
+    #[cfg(feature = "test")]
+    /// CHECK: Test - not validated, so tests can route through mock_swap
+    pub jupiter_program: UncheckedAccount<'info>,
+
+    #[cfg(not(feature = "test"))]
+    pub jupiter_program: Program<'info, Jupiter>,
 
     pub system_program: Program<'info, System>,
     pub token_program: Program<'info, Token>,
 }
 
-pub fn execute_strategy(ctx: Context<ExecuteStrategy>, input_amount: u64) -> Result<()> {
-    let strategy = &mut ctx.accounts.strategy;
-    let user_strategy = &mut ctx.accounts.user_strategy;
-    let execution = &mut ctx.accounts.execution;
+/// Run the buyer's pinned parameters as a Jupiter swap of the executor's own
+/// tokens and record the measured outcome.
+///
+/// Slippage is bounded by `max_slippage_bps` below the oracle price, and by
+/// `min_output` from the quote. Profit is only realized by a round trip, a
+/// route back into the token it spent: its result is the measured output
+/// less the measured input, recorded here, and arbitrage (which must be a
+/// round trip) has to clear `min_profit_bps`. The creator gets
+/// `profit_share_bps` of any realized profit, paid from the output. Any
+/// other route has only changed what the executor holds, and yield
+/// strategies earn after the swap, so their result is left for the
+/// attestor (`record_execution_result`). Nothing can be collected from an
+/// attested result, so strategies with a profit share only run realizing
+/// routes.
+pub fn execute_strategy(
+    ctx: Context<ExecuteStrategy>,
+    input_amount: u64,
+    min_output: u64,
+    swap_ix_data: Vec<u8>,
+    accounts_meta: Vec<AccountMetaData>,
+) -> Result<()> {
     let clock = Clock::get()?;
+    let now = clock.unix_timestamp;
+
+    require!(input_amount > 0, StrategyError::InvalidAmount);
+    require!(
+        !ctx.accounts
+            .user_strategy
+            .is_expired(now, ctx.accounts.strategy.grace_period),
+        StrategyError::SubscriptionExpired
+    );
+
+    // ============================================
+    // 1. ENFORCE THE PINNED PARAMETERS
+    // ============================================
+
+    // Run the parameters the buyer is pinned to, never newer ones they
+    // haven't opted in to
+    let parameters = ctx.accounts.strategy.parameters_at(
+        ctx.accounts.user_strategy.pinned_version,
+        ctx.accounts.superseding_version.as_deref(),
+    )?;
+
+    require_keys_eq!(
+        ctx.accounts.input_mint.key(),
+        parameters.input_token,
+        StrategyError::TokenMismatch
+    );
+    require_keys_eq!(
+        ctx.accounts.output_mint.key(),
+        parameters.output_token,
+        StrategyError::TokenMismatch
+    );
+
+    let round_trip = parameters.input_token == parameters.output_token;
+    require!(
+        round_trip || ctx.accounts.strategy.strategy_type != StrategyType::Arbitrage,
+        StrategyError::ArbitrageNotRoundTrip
+    );
+    let realized = round_trip && ctx.accounts.strategy.strategy_type != StrategyType::YieldFarming;
+    require!(
+        realized || ctx.accounts.strategy.profit_share_bps == 0,
+        StrategyError::ProfitShareNotRealizable
+    );
 
-    // For manual execution, user provides the results
-    // In Week 3, AI agents will actually execute the swaps
+    let last_executed_at = ctx.accounts.user_strategy.last_executed_at;
+    require!(
+        last_executed_at == 0
+            || now >= last_executed_at.saturating_add(parameters.execution_interval),
+        StrategyError::ExecutionTooSoon
+    );
+
+    ctx.accounts.input_price_feed.ensure_fresh(now)?;
+    ctx.accounts.output_price_feed.ensure_fresh(now)?;
+
+    require!(
+        ctx.accounts.executor_input_ata.amount >= input_amount,
+        StrategyError::InsufficientBalance
+    );
+
+    // ============================================
+    // 2. SWAP THROUGH JUPITER (executor signs)
+    // ============================================
+
+    let protected_accounts = [
+        ctx.accounts.strategy.key(),
+        ctx.accounts.user_strategy.key(),
+        ctx.accounts.execution.key(),
+    ];
+    for remaining_acc in ctx.remaining_accounts.iter() {
+        require!(
+            !protected_accounts.contains(remaining_acc.key),
+            StrategyError::ProtectedAccountInRemainingAccounts
+        );
+    }
+
+    // The output has to land where it is measured and the share is paid from.
+    // Other Jupiter instructions order their accounts differently, so only
+    // `route` can be checked.
+    require!(
+        swap_ix_data.starts_with(&ROUTE_DISCRIMINATOR),
+        StrategyError::UnsupportedSwapInstruction
+    );
+    let executor_output = ctx.accounts.executor_output_ata.key();
+    require!(
+        accounts_meta.len() > ROUTE_DESTINATION_OVERRIDE_INDEX,
+        StrategyError::RouteOutputMismatch
+    );
+    require_keys_eq!(
+        accounts_meta[ROUTE_DESTINATION_INDEX].pubkey,
+        executor_output,
+        StrategyError::RouteOutputMismatch
+    );
+    let destination_override = accounts_meta[ROUTE_DESTINATION_OVERRIDE_INDEX].pubkey;
+    require!(
+        destination_override == executor_output
+            || destination_override == ctx.accounts.jupiter_program.key(),
+        StrategyError::RouteOutputMismatch
+    );
 
-    // Record execution
-    execution.strategy = strategy.key();
+    let input_balance_before = ctx.accounts.executor_input_ata.amount;
+    let output_balance_before = ctx.accounts.executor_output_ata.amount;
+
+    forward_jupiter_swap(
+        ctx.accounts.jupiter_program.key(),
+        swap_ix_data,
+        accounts_meta,
+        ctx.remaining_accounts,
+    )?;
+
+    ctx.accounts.executor_input_ata.reload()?;
+    ctx.accounts.executor_output_ata.reload()?;
+
+    let input_used = input_balance_before
+        .checked_sub(ctx.accounts.executor_input_ata.amount)
+        .ok_or(StrategyError::MathOverflow)?;
+    let output_received = ctx
+        .accounts
+        .executor_output_ata
+        .amount
+        .checked_sub(output_balance_before)
+        .ok_or(StrategyError::MathOverflow)?;
+
+    msg!("Input used: {}", input_used);
+    msg!("Output received: {}", output_received);
+
+    // ============================================
+    // 3. SLIPPAGE AND PROFIT BOUNDS
+    // ============================================
+
+    require!(
+        input_used <= input_amount,
+        StrategyError::UnexpectedInputAmount
+    );
+    require_gte!(output_received, min_output, StrategyError::SlippageExceeded);
+
+    let fair_output = ctx
+        .accounts
+        .input_price_feed
+        .quote(input_amount, &ctx.accounts.output_price_feed)?;
+
+    // Oracle bound, scaled to what was actually sold
+    let oracle_min_output = (fair_output as u128)
+        .checked_mul(input_used as u128)
+        .ok_or(StrategyError::MathOverflow)?
+        .checked_div(input_amount as u128)
+        .ok_or(StrategyError::MathOverflow)?
+        .checked_mul(BPS - parameters.max_slippage_bps.min(10_000) as u128)
+        .ok_or(StrategyError::MathOverflow)?
+        / BPS;
+    require!(
+        output_received as u128 >= oracle_min_output,
+        StrategyError::SlippageExceeded
+    );
+
+    msg!("Slippage check passed (oracle min: {})", oracle_min_output);
+
+    // Both sides are in the same token on a round trip, so the difference is
+    // what the executor actually made or lost
+    let (profit, met_target) = if realized {
+        let profit: i64 = (output_received as i128 - input_used as i128)
+            .try_into()
+            .map_err(|_| StrategyError::MathOverflow)?;
+        let met_target = calculate_profit(input_used, output_received, &parameters)? >= 0;
+        (profit, met_target)
+    } else {
+        (0, false)
+    };
+
+    if ctx.accounts.strategy.strategy_type == StrategyType::Arbitrage {
+        require!(met_target, StrategyError::ProfitBelowMinimum);
+    }
+
+    // ============================================
+    // 4. CREATOR PROFIT SHARE
+    // ============================================
+
+    let profit_share_bps = ctx.accounts.strategy.profit_share_bps;
+    let mut share_value = 0;
+    if profit > 0 && profit_share_bps > 0 {
+        // Realized in the output token, so the share is paid straight out of
+        // it. Buyers may be pinned to versions trading different tokens, so
+        // the earnings are booked at its USD value.
+        let share_amount = calculate_fee(profit as u64, profit_share_bps)?;
+        share_value = ctx
+            .accounts
+            .output_price_feed
+            .value_of(share_amount)?
+            .try_into()
+            .map_err(|_| StrategyError::MathOverflow)?;
+        let creator_output_ata = ctx
+            .accounts
+            .creator_output_ata
+            .as_ref()
+            .ok_or(StrategyError::CreatorAccountMissing)?;
+
+        if share_amount > 0 {
+            token::transfer(
+                CpiContext::new(
+                    ctx.accounts.token_program.to_account_info(),
+                    Transfer {
+                        from: ctx.accounts.executor_output_ata.to_account_info(),
+                        to: creator_output_ata.to_account_info(),
+                        authority: ctx.accounts.executor.to_account_info(),
+                    },
+                ),
+                share_amount,
+            )?;
+        }
+
+        msg!(
+            "Profit share: {} (worth {} in USD, 6 decimals) to the creator",
+            share_amount,
+            share_value
+        );
+
+        emit!(CreatorProfitSharePaid {
+            strategy: ctx.accounts.strategy.key(),
+            execution: ctx.accounts.execution.key(),
+            executor: ctx.accounts.executor.key(),
+            creator: ctx.accounts.strategy.creator,
+            mint: ctx.accounts.output_mint.key(),
+            amount: share_amount,
+            value: share_value,
+            timestamp: now,
+        });
+    }
+
+    // ============================================
+    // 5. RECORD THE OUTCOME
+    // ============================================
+
+    let execution = &mut ctx.accounts.execution;
+    execution.strategy = ctx.accounts.strategy.key();
     execution.executor = ctx.accounts.executor.key();
-    execution.executed_at = clock.unix_timestamp;
-    execution.input_amount = input_amount;
-    execution.output_amount = 0; // Will be set after swap
-    execution.profit = 0; // Will be calculated
-    execution.success = false; // Will be updated
+    execution.executed_at = now;
+    execution.input_amount = input_used;
+    execution.output_amount = output_received;
+    execution.profit = 0;
+    execution.success = false;
     execution.bump = ctx.bumps.execution;
+    execution.recorded_at = 0;
+    execution.version = StrategyExecution::CURRENT_VERSION;
 
-    // Update counters
+    let strategy = &mut ctx.accounts.strategy;
+    let user_strategy = &mut ctx.accounts.user_strategy;
     strategy.total_executions = strategy.total_executions.checked_add(1).unwrap_or(0);
     user_strategy.times_executed = user_strategy.times_executed.checked_add(1).unwrap_or(0);
+    user_strategy.last_executed_at = now;
+    strategy.creator_earnings = strategy.creator_earnings.saturating_add(share_value);
+    user_strategy.profit_share_paid = user_strategy.profit_share_paid.saturating_add(share_value);
+
+    if !realized {
+        msg!("Position opened; result left for the attestor");
+    } else {
+        apply_execution_result(
+            strategy,
+            user_strategy,
+            execution,
+            output_received,
+            profit,
+            met_target,
+        )?;
+    }
 
     msg!(
-        "Strategy {} executed by {}",
+        "Strategy {} (version {}: {} -> {}) executed by {}",
         strategy.name,
+        user_strategy.pinned_version,
+        parameters.input_token,
+        parameters.output_token,
         ctx.accounts.executor.key()
     );
 
     Ok(())
 }
+
+/// Build the client-provided Jupiter instruction and invoke it. The executor
+/// signed the transaction, so their signature carries through to Jupiter.
+///
+/// In test mode the program isn't checked, and tests route through mock_swap.
+fn forward_jupiter_swap(
+    jupiter_program: Pubkey,
+    swap_ix_data: Vec<u8>,
+    accounts_meta: Vec<AccountMetaData>,
+    accounts: &[AccountInfo],
+) -> Result<()> {
+    let metas: Vec<AccountMeta> = accounts_meta
+        .into_iter()
+        .map(|meta| {
+            if meta.is_writable {
+                AccountMeta::new(meta.pubkey, meta.is_signer)
+            } else {
+                AccountMeta::new_readonly(meta.pubkey, meta.is_signer)
+            }
+        })
+        .collect();
+
+    let jupiter_ix = SolInstruction {
+        program_id: jupiter_program,
+        accounts: metas,
+        data: swap_ix_data,
+    };
+
+    msg!("Invoking Jupiter CPI...");
+    invoke(&jupiter_ix, accounts)?;
+    msg!("Jupiter CPI completed successfully");
+
+    Ok(())
+}
@@ -2,10 +2,18 @@ pub mod buy_strategy;
 pub mod create_strategy;
 pub mod execute_marketplace_strategy;
 pub mod record_execution_result;
+pub mod renew_subscription;
+pub mod set_attestor;
+pub mod set_commission;
+pub mod strategy_versions;
 pub mod update_strategy;
 
 pub use buy_strategy::*;
 pub use create_strategy::*;
 pub use execute_marketplace_strategy::*;
 pub use record_execution_result::*;
+pub use renew_subscription::*;
+pub use set_attestor::*;
+pub use set_commission::*;
+pub use strategy_versions::*;
 pub use update_strategy::*;
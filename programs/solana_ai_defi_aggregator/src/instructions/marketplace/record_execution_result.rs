@@ -1,49 +1,103 @@
-use crate::state::{Strategy, StrategyExecution, UserStrategy};
+use crate::error::StrategyError;
+use crate::events::ExecutionResultRecorded;
+use crate::state::{GlobalState, Strategy, StrategyExecution, UserStrategy};
 use anchor_lang::prelude::*;
 
 #[derive(Accounts)]
 pub struct RecordExecutionResult<'info> {
+    /// Names the attestor
+    #[account(
+        seeds = [b"global_state"],
+        bump = global_state.bump,
+    )]
+    pub global_state: Account<'info, GlobalState>,
+
     #[account(mut)]
     pub strategy: Account<'info, Strategy>,
 
-    #[account(mut)]
+    #[account(
+        mut,
+        constraint = user_strategy.strategy == strategy.key() @ StrategyError::InvalidExecution,
+        constraint = user_strategy.owner == execution.executor @ StrategyError::InvalidExecution,
+    )]
     pub user_strategy: Account<'info, UserStrategy>,
 
-    #[account(mut)]
+    #[account(
+        mut,
+        constraint = execution.strategy == strategy.key() @ StrategyError::InvalidExecution,
+        constraint = execution.recorded_at == 0 @ StrategyError::ResultAlreadyRecorded,
+    )]
     pub execution: Account<'info, StrategyExecution>,
 
-    pub executor: Signer<'info>,
+    /// Oracle/agent that measured the execution off-chain
+    #[account(
+        constraint = global_state.execution_attestor != Pubkey::default()
+            && attestor.key() == global_state.execution_attestor @ StrategyError::NotAttestor
+    )]
+    pub attestor: Signer<'info>,
 }
 
+/// Record the result of an execution, as measured by the attestor. Each
+/// execution can be recorded once.
 pub fn record_execution_result(
     ctx: Context<RecordExecutionResult>,
     output_amount: u64,
     profit: i64,
     success: bool,
 ) -> Result<()> {
-    let strategy = &mut ctx.accounts.strategy;
-    let user_strategy = &mut ctx.accounts.user_strategy;
-    let execution = &mut ctx.accounts.execution;
+    let recorded_at = apply_execution_result(
+        &mut ctx.accounts.strategy,
+        &mut ctx.accounts.user_strategy,
+        &mut ctx.accounts.execution,
+        output_amount,
+        profit,
+        success,
+    )?;
+
+    emit!(ExecutionResultRecorded {
+        strategy: ctx.accounts.strategy.key(),
+        execution: ctx.accounts.execution.key(),
+        executor: ctx.accounts.execution.executor,
+        attestor: ctx.accounts.attestor.key(),
+        output_amount,
+        profit,
+        success,
+        timestamp: recorded_at,
+    });
+
+    Ok(())
+}
+
+/// Write a result into the execution record and fold it into the strategy
+/// and purchase stats. Returns the time it was recorded.
+pub(crate) fn apply_execution_result(
+    strategy: &mut Account<Strategy>,
+    user_strategy: &mut Account<UserStrategy>,
+    execution: &mut Account<StrategyExecution>,
+    output_amount: u64,
+    profit: i64,
+    success: bool,
+) -> Result<i64> {
+    let clock = Clock::get()?;
 
     // Update execution record
     execution.output_amount = output_amount;
     execution.profit = profit;
     execution.success = success;
+    execution.recorded_at = clock.unix_timestamp;
 
     // Update strategy stats
-    strategy.total_profit += profit;
-    user_strategy.total_profit += profit;
+    strategy.total_profit = strategy.total_profit.saturating_add(profit);
+    user_strategy.total_profit = user_strategy.total_profit.saturating_add(profit);
 
-    // Update success rate
+    // Update success rate over the executions recorded so far, in whatever
+    // order their results arrive
+    strategy.recorded_executions = strategy.recorded_executions.saturating_add(1);
     if success {
-        let total_successes =
-            (strategy.success_rate as u64 * (strategy.total_executions - 1)) / 10000;
-        strategy.success_rate = ((total_successes + 1) * 10000 / strategy.total_executions) as u16;
-    } else {
-        let total_successes =
-            (strategy.success_rate as u64 * (strategy.total_executions - 1)) / 10000;
-        strategy.success_rate = (total_successes * 10000 / strategy.total_executions) as u16;
+        strategy.successful_executions = strategy.successful_executions.saturating_add(1);
     }
+    strategy.success_rate =
+        (strategy.successful_executions * 10_000 / strategy.recorded_executions) as u16;
 
     msg!(
         "Execution result recorded: profit={}, success={}",
@@ -51,5 +105,5 @@ pub fn record_execution_result(
         success
     );
 
-    Ok(())
+    Ok(clock.unix_timestamp)
 }
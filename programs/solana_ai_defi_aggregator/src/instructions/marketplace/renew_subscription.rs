@@ -0,0 +1,110 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::{Mint, Token, TokenAccount};
+
+use crate::error::StrategyError;
+use crate::events::SubscriptionRenewed;
+use crate::instructions::marketplace::collect_payment;
+use crate::state::{GlobalState, Strategy, UserStrategy};
+
+#[derive(Accounts)]
+pub struct RenewSubscription<'info> {
+    pub strategy: Account<'info, Strategy>,
+
+    #[account(
+        mut,
+        seeds = [b"user_strategy", subscriber.key().as_ref(), strategy.key().as_ref()],
+        bump = user_strategy.bump,
+        constraint = user_strategy.owner == subscriber.key() @ StrategyError::Unauthorized,
+    )]
+    pub user_strategy: Account<'info, UserStrategy>,
+
+    pub subscriber: Signer<'info>,
+
+    /// Holds the protocol commission rate
+    #[account(
+        seeds = [b"global_state"],
+        bump = global_state.bump,
+    )]
+    pub global_state: Account<'info, GlobalState>,
+
+    #[account(address = strategy.payment_mint @ StrategyError::PaymentMintMismatch)]
+    pub payment_mint: Account<'info, Mint>,
+
+    #[account(
+        mut,
+        constraint = subscriber_token_account.mint == payment_mint.key() @ StrategyError::PaymentMintMismatch,
+        constraint = subscriber_token_account.owner == subscriber.key() @ StrategyError::InvalidPaymentAccount,
+    )]
+    pub subscriber_token_account: Account<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        constraint = creator_token_account.mint == payment_mint.key() @ StrategyError::PaymentMintMismatch,
+        constraint = creator_token_account.owner == strategy.creator @ StrategyError::InvalidPaymentAccount,
+    )]
+    pub creator_token_account: Account<'info, TokenAccount>,
+
+    /// Fee treasury for the payment mint (receives the commission)
+    #[account(
+        mut,
+        seeds = [b"fee_vault", payment_mint.key().as_ref()],
+        bump,
+    )]
+    pub fee_vault_ata: Account<'info, TokenAccount>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+/// Pay for one more period, at most `max_price` in `expected_payment_mint`.
+/// A live subscription is extended from its expiry; a lapsed one restarts now.
+pub fn renew_subscription(
+    ctx: Context<RenewSubscription>,
+    max_price: u64,
+    expected_payment_mint: Pubkey,
+) -> Result<()> {
+    let strategy = &ctx.accounts.strategy;
+
+    require!(strategy.is_active, StrategyError::StrategyInactive);
+    require!(
+        strategy.subscription_period > 0,
+        StrategyError::NotASubscription
+    );
+    require!(strategy.price > 0, StrategyError::InvalidPrice);
+    strategy.check_terms(max_price, expected_payment_mint)?;
+
+    let gross_amount = strategy.price;
+    let (commission, creator_amount) = collect_payment(
+        &ctx.accounts.token_program,
+        &ctx.accounts.subscriber,
+        &ctx.accounts.subscriber_token_account,
+        &ctx.accounts.creator_token_account,
+        &ctx.accounts.fee_vault_ata,
+        gross_amount,
+        ctx.accounts.global_state.marketplace_commission_bps,
+    )?;
+
+    let now = Clock::get()?.unix_timestamp;
+    let user_strategy = &mut ctx.accounts.user_strategy;
+    user_strategy.expires_at = user_strategy
+        .expires_at
+        .max(now)
+        .saturating_add(strategy.subscription_period);
+
+    msg!(
+        "Subscription to {} renewed until {}",
+        strategy.name,
+        user_strategy.expires_at
+    );
+
+    emit!(SubscriptionRenewed {
+        strategy: strategy.key(),
+        subscriber: ctx.accounts.subscriber.key(),
+        gross_amount,
+        commission,
+        creator_amount,
+        expires_at: user_strategy.expires_at,
+        timestamp: now,
+    });
+
+    Ok(())
+}
@@ -0,0 +1,34 @@
+use crate::error::StrategyError;
+use crate::events::ExecutionAttestorUpdated;
+use crate::state::GlobalState;
+use anchor_lang::prelude::*;
+
+#[derive(Accounts)]
+pub struct SetExecutionAttestor<'info> {
+    #[account(
+        mut,
+        seeds = [b"global_state"],
+        bump = global_state.bump,
+        has_one = admin @ StrategyError::Unauthorized,
+    )]
+    pub global_state: Account<'info, GlobalState>,
+
+    pub admin: Signer<'info>,
+}
+
+/// Name the oracle/agent trusted to record execution results
+/// (`Pubkey::default()` turns recording off)
+pub fn set_execution_attestor(ctx: Context<SetExecutionAttestor>, attestor: Pubkey) -> Result<()> {
+    let global_state = &mut ctx.accounts.global_state;
+    let old_attestor = global_state.execution_attestor;
+    global_state.execution_attestor = attestor;
+
+    msg!("Execution attestor: {} -> {}", old_attestor, attestor);
+
+    emit!(ExecutionAttestorUpdated {
+        old_attestor,
+        new_attestor: attestor,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+    Ok(())
+}
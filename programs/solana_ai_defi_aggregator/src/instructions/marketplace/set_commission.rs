@@ -0,0 +1,45 @@
+use crate::error::StrategyError;
+use crate::events::MarketplaceCommissionUpdated;
+use crate::state::{GlobalState, MAX_MARKETPLACE_COMMISSION_BPS};
+use anchor_lang::prelude::*;
+
+#[derive(Accounts)]
+pub struct SetMarketplaceCommission<'info> {
+    #[account(
+        mut,
+        seeds = [b"global_state"],
+        bump = global_state.bump,
+        has_one = admin @ StrategyError::Unauthorized,
+    )]
+    pub global_state: Account<'info, GlobalState>,
+
+    pub admin: Signer<'info>,
+}
+
+/// Set the protocol's cut of every strategy sale
+pub fn set_marketplace_commission(
+    ctx: Context<SetMarketplaceCommission>,
+    commission_bps: u16,
+) -> Result<()> {
+    require!(
+        commission_bps <= MAX_MARKETPLACE_COMMISSION_BPS,
+        StrategyError::InvalidCommission
+    );
+
+    let global_state = &mut ctx.accounts.global_state;
+    let old_commission_bps = global_state.marketplace_commission_bps;
+    global_state.marketplace_commission_bps = commission_bps;
+
+    msg!(
+        "Marketplace commission: {} -> {} bps",
+        old_commission_bps,
+        commission_bps
+    );
+
+    emit!(MarketplaceCommissionUpdated {
+        old_commission_bps,
+        new_commission_bps: commission_bps,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+    Ok(())
+}
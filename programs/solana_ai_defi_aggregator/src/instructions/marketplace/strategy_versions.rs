@@ -0,0 +1,120 @@
+use anchor_lang::prelude::*;
+
+use crate::error::StrategyError;
+use crate::events::{StrategyParametersUpdated, StrategyVersionAdopted};
+use crate::state::{Strategy, StrategyParameters, StrategyVersion, UserStrategy};
+
+#[derive(Accounts)]
+pub struct UpdateStrategyParameters<'info> {
+    #[account(
+        mut,
+        constraint = strategy.creator == creator.key() @ StrategyError::Unauthorized
+    )]
+    pub strategy: Account<'info, Strategy>,
+
+    /// Record of the new version (numbered after the current one)
+    #[account(
+        init,
+        payer = creator,
+        space = 8 + StrategyVersion::INIT_SPACE,
+        seeds = [
+            b"strategy_version",
+            strategy.key().as_ref(),
+            (strategy.parameters_version + 1).to_le_bytes().as_ref()
+        ],
+        bump
+    )]
+    pub strategy_version: Account<'info, StrategyVersion>,
+
+    #[account(mut)]
+    pub creator: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+/// Publish a new parameter set. New buyers get it; existing buyers keep
+/// executing the version they bought until they adopt the latest.
+pub fn update_strategy_parameters(
+    ctx: Context<UpdateStrategyParameters>,
+    parameters: StrategyParameters,
+) -> Result<()> {
+    require!(
+        parameters.max_slippage_bps <= 10_000,
+        StrategyError::InvalidSlippage
+    );
+
+    let strategy = &mut ctx.accounts.strategy;
+    let changed_fields = parameters.diff(&strategy.parameters);
+    require!(changed_fields != 0, StrategyError::ParametersUnchanged);
+
+    let clock = Clock::get()?;
+    let parameters_version = strategy.parameters_version + 1;
+
+    let record = &mut ctx.accounts.strategy_version;
+    record.strategy = strategy.key();
+    record.parameters_version = parameters_version;
+    record.previous = strategy.parameters.clone();
+    record.parameters = parameters.clone();
+    record.changed_fields = changed_fields;
+    record.created_at = clock.unix_timestamp;
+    record.bump = ctx.bumps.strategy_version;
+
+    strategy.parameters = parameters;
+    strategy.parameters_version = parameters_version;
+
+    msg!(
+        "Strategy {} parameters now at version {}",
+        strategy.name,
+        parameters_version
+    );
+
+    emit!(StrategyParametersUpdated {
+        strategy: strategy.key(),
+        parameters_version,
+        changed_fields,
+        timestamp: clock.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct AdoptStrategyVersion<'info> {
+    pub strategy: Account<'info, Strategy>,
+
+    #[account(
+        mut,
+        seeds = [b"user_strategy", owner.key().as_ref(), strategy.key().as_ref()],
+        bump = user_strategy.bump,
+        constraint = user_strategy.owner == owner.key() @ StrategyError::Unauthorized,
+    )]
+    pub user_strategy: Account<'info, UserStrategy>,
+
+    pub owner: Signer<'info>,
+}
+
+/// Opt in to the strategy's latest parameters
+pub fn adopt_latest_parameters(ctx: Context<AdoptStrategyVersion>) -> Result<()> {
+    let strategy = &ctx.accounts.strategy;
+    let user_strategy = &mut ctx.accounts.user_strategy;
+
+    let from_version = user_strategy.pinned_version;
+    user_strategy.pinned_version = strategy.parameters_version;
+
+    msg!(
+        "{} moved from version {} to {} of strategy {}",
+        user_strategy.owner,
+        from_version,
+        strategy.parameters_version,
+        strategy.name
+    );
+
+    emit!(StrategyVersionAdopted {
+        strategy: strategy.key(),
+        owner: user_strategy.owner,
+        from_version,
+        to_version: strategy.parameters_version,
+    });
+
+    Ok(())
+}
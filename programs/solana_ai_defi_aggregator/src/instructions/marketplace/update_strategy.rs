@@ -1,5 +1,8 @@
-use crate::state::Strategy;
+use crate::error::StrategyError;
+use crate::events::{ProfitShareUpdated, SubscriptionTermsUpdated};
+use crate::state::{Strategy, StrategyType, MAX_PROFIT_SHARE_BPS};
 use anchor_lang::prelude::*;
+use anchor_spl::token::Mint;
 
 #[derive(Accounts)]
 pub struct UpdateStrategy<'info> {
@@ -29,3 +32,101 @@ pub fn update_strategy_status(
 
     Ok(())
 }
+
+/// Switch between a one-time price (`subscription_period` 0) and a price per
+/// period. Existing subscriptions keep their expiry; the grace period applies
+/// to every subscriber from now on.
+pub fn set_subscription_terms(
+    ctx: Context<UpdateStrategy>,
+    subscription_period: i64,
+    grace_period: i64,
+) -> Result<()> {
+    require!(
+        subscription_period >= 0 && grace_period >= 0,
+        StrategyError::InvalidSubscriptionTerms
+    );
+
+    let strategy = &mut ctx.accounts.strategy;
+    strategy.subscription_period = subscription_period;
+    strategy.grace_period = grace_period;
+
+    msg!(
+        "Strategy {} subscription: {}s period, {}s grace",
+        strategy.name,
+        subscription_period,
+        grace_period
+    );
+
+    emit!(SubscriptionTermsUpdated {
+        strategy: strategy.key(),
+        subscription_period,
+        grace_period,
+    });
+
+    Ok(())
+}
+
+/// Set the creator's cut of realized profit on each execution. Once the
+/// strategy has buyers it can only be lowered, so nobody pays more than they
+/// signed up for. Yield strategies never realize profit in the execution,
+/// so they cannot take a share.
+pub fn set_profit_share(ctx: Context<UpdateStrategy>, profit_share_bps: u16) -> Result<()> {
+    require!(
+        profit_share_bps <= MAX_PROFIT_SHARE_BPS,
+        StrategyError::InvalidProfitShare
+    );
+
+    let strategy = &mut ctx.accounts.strategy;
+    require!(
+        profit_share_bps == 0 || strategy.strategy_type != StrategyType::YieldFarming,
+        StrategyError::ProfitShareNotRealizable
+    );
+    let old_profit_share_bps = strategy.profit_share_bps;
+    require!(
+        strategy.total_purchases == 0 || profit_share_bps <= old_profit_share_bps,
+        StrategyError::ProfitShareIncrease
+    );
+    strategy.profit_share_bps = profit_share_bps;
+
+    msg!(
+        "Strategy {} profit share: {} -> {} bps",
+        strategy.name,
+        old_profit_share_bps,
+        profit_share_bps
+    );
+
+    emit!(ProfitShareUpdated {
+        strategy: strategy.key(),
+        old_profit_share_bps,
+        new_profit_share_bps: profit_share_bps,
+    });
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct SetStrategyPaymentMint<'info> {
+    #[account(
+        mut,
+        constraint = strategy.creator == creator.key()
+    )]
+    pub strategy: Account<'info, Strategy>,
+
+    pub creator: Signer<'info>,
+
+    pub payment_mint: Account<'info, Mint>,
+}
+
+/// Change the mint buyers pay in (strategies migrated from v1 have none)
+pub fn set_strategy_payment_mint(ctx: Context<SetStrategyPaymentMint>) -> Result<()> {
+    let strategy = &mut ctx.accounts.strategy;
+    strategy.payment_mint = ctx.accounts.payment_mint.key();
+
+    msg!(
+        "Strategy {} now priced in {}",
+        strategy.name,
+        strategy.payment_mint
+    );
+
+    Ok(())
+}
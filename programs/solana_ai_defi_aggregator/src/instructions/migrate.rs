@@ -0,0 +1,227 @@
+use crate::error::MigrationError;
+use crate::events::AccountMigrated;
+use crate::state::{
+    DepositorPosition, GlobalState, Strategy, StrategyExecution, UserState, UserStrategy, Vault,
+    Versioned,
+};
+use anchor_lang::prelude::*;
+use anchor_lang::system_program;
+use anchor_lang::Discriminator;
+use anchor_spl::token::{self, TokenAccount};
+
+// ===========================================
+// Account migrations
+// ===========================================
+//
+// Permissionless: an upgrade only grows the account and fills defaults, so
+// anyone willing to pay the extra rent may run it. The account is taken
+// unchecked because an old layout is too short for `Account<T>` to load.
+
+#[derive(Accounts)]
+pub struct MigrateAccount<'info> {
+    /// Pays the rent for the extra bytes
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    /// CHECK: owner checked here, discriminator and layout checked in `migrate`
+    #[account(mut, owner = crate::ID @ MigrationError::AccountTypeMismatch)]
+    pub account: UncheckedAccount<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+// Every size (discriminator included) each type has shipped with, and the
+// version stored at that size. Once the bytes past its serialized data are
+// zeroed, an account without a version byte decodes as version 0 whatever the
+// bytes in front of it are, so the size is what identifies the layout;
+// anything not listed here is rejected rather than reinterpreted.
+
+/// The original vault: authority, token_mint, share_mint, total_assets,
+/// total_shares, bump, strategy_enabled, performance_fee_bps. Its PDA was
+/// seeded by mint rather than `vault_id` and its roles and lifecycle fields
+/// were later replaced rather than appended, so there is no in-place upgrade:
+/// depositors withdraw and redeposit into a new vault.
+const BASELINE_VAULT_LEN: usize = 124;
+
+const VAULT_LAYOUTS: &[(usize, u8)] = &[
+    (548, 0),
+    (549, 1),
+    (559, 2),
+    (592, 3),
+    (608, 4),
+    (617, 5),
+    (625, 6),
+    (885, 7),
+    (893, 8),
+    (961, 9),
+];
+
+// v1 took the spare byte the old GlobalState::LEN reserved
+const GLOBAL_STATE_LAYOUTS: &[(usize, u8)] = &[(44, 0), (44, 1), (46, 2)];
+
+const USER_STATE_LAYOUTS: &[(usize, u8)] = &[(57, 0)];
+
+const STRATEGY_LAYOUTS: &[(usize, u8)] =
+    &[(427, 0), (428, 1), (460, 2), (476, 3), (480, 4), (490, 5)];
+
+const POSITION_LAYOUTS: &[(usize, u8)] = &[(81, 0), (90, 1)];
+
+const USER_STRATEGY_LAYOUTS: &[(usize, u8)] = &[(97, 0), (106, 1), (110, 2), (118, 3)];
+
+/// The original execution record, before `recorded_at`. Its executor could
+/// record (and re-record) the result itself, so whatever it holds is final.
+const BASELINE_EXECUTION_LEN: usize = 106;
+
+// recorded_at was appended before the version byte existed
+const EXECUTION_LAYOUTS: &[(usize, u8)] = &[(BASELINE_EXECUTION_LEN, 0), (114, 0)];
+
+/// Remaining accounts: the vault's claim escrow, whether or not it was ever
+/// created. Vaults before v8 did not book what settled withdrawals are owed,
+/// so the escrow's balance is booked as owed, rounding dust included.
+pub fn migrate_vault(ctx: Context<MigrateAccount>) -> Result<()> {
+    require!(
+        ctx.accounts.account.data_len() != BASELINE_VAULT_LEN,
+        MigrationError::BaselineVaultNotMigratable
+    );
+    let info = ctx.accounts.account.to_account_info();
+    let claim_escrow = ctx.remaining_accounts.first().cloned();
+
+    migrate::<Vault>(ctx, 8 + Vault::INIT_SPACE, VAULT_LAYOUTS)?;
+
+    let claim_escrow = claim_escrow.ok_or(MigrationError::ClaimEscrowRequired)?;
+    let (expected_escrow, _) =
+        Pubkey::find_program_address(&[b"claim_escrow", info.key.as_ref()], &crate::ID);
+    require_keys_eq!(
+        claim_escrow.key(),
+        expected_escrow,
+        MigrationError::ClaimEscrowRequired
+    );
+
+    // Escrows are created lazily; one that never was owes nothing
+    if claim_escrow.owner == &token::ID {
+        let escrow = TokenAccount::try_deserialize(&mut &claim_escrow.try_borrow_data()?[..])?;
+        let mut vault = Vault::try_deserialize(&mut &info.try_borrow_data()?[..])?;
+        vault.pending_claims = escrow.amount;
+        vault.try_serialize(&mut &mut info.try_borrow_mut_data()?[..])?;
+    }
+    Ok(())
+}
+
+pub fn migrate_global_state(ctx: Context<MigrateAccount>) -> Result<()> {
+    migrate::<GlobalState>(ctx, 8 + GlobalState::INIT_SPACE, GLOBAL_STATE_LAYOUTS)
+}
+
+pub fn migrate_user_state(ctx: Context<MigrateAccount>) -> Result<()> {
+    migrate::<UserState>(ctx, 8 + UserState::INIT_SPACE, USER_STATE_LAYOUTS)
+}
+
+pub fn migrate_strategy(ctx: Context<MigrateAccount>) -> Result<()> {
+    migrate::<Strategy>(ctx, 8 + Strategy::INIT_SPACE, STRATEGY_LAYOUTS)
+}
+
+pub fn migrate_position(ctx: Context<MigrateAccount>) -> Result<()> {
+    migrate::<DepositorPosition>(ctx, 8 + DepositorPosition::INIT_SPACE, POSITION_LAYOUTS)
+}
+
+pub fn migrate_user_strategy(ctx: Context<MigrateAccount>) -> Result<()> {
+    migrate::<UserStrategy>(ctx, 8 + UserStrategy::INIT_SPACE, USER_STRATEGY_LAYOUTS)
+}
+
+pub fn migrate_execution(ctx: Context<MigrateAccount>) -> Result<()> {
+    let info = ctx.accounts.account.to_account_info();
+    let legacy_len = info.data_len();
+    migrate::<StrategyExecution>(ctx, 8 + StrategyExecution::INIT_SPACE, EXECUTION_LAYOUTS)?;
+
+    if legacy_len == BASELINE_EXECUTION_LEN {
+        // Its zero-filled recorded_at would read as pending and let the
+        // attestor fold the same execution into the stats a second time
+        let mut execution = StrategyExecution::try_deserialize(&mut &info.try_borrow_data()?[..])?;
+        execution.recorded_at = execution.executed_at;
+        execution.try_serialize(&mut &mut info.try_borrow_mut_data()?[..])?;
+    }
+    Ok(())
+}
+
+/// Check the account's size against the known `layouts` of `T`, grow it to
+/// `space`, zero everything past its serialized data, then load it and
+/// upgrade it in place
+fn migrate<T>(ctx: Context<MigrateAccount>, space: usize, layouts: &[(usize, u8)]) -> Result<()>
+where
+    T: AccountSerialize + AccountDeserialize + Discriminator + Versioned,
+{
+    let info = ctx.accounts.account.to_account_info();
+    let legacy_len = info.data_len();
+    {
+        let data = info.try_borrow_data()?;
+        require!(
+            data.len() >= T::DISCRIMINATOR.len() && data.starts_with(T::DISCRIMINATOR),
+            MigrationError::AccountTypeMismatch
+        );
+    }
+    // Already at the current size: let the version check below report it
+    require!(
+        legacy_len == space || layouts.iter().any(|&(len, _)| len == legacy_len),
+        MigrationError::UnknownLayout
+    );
+
+    if info.data_len() < space {
+        let rent = Rent::get()?.minimum_balance(space);
+        let shortfall = rent.saturating_sub(info.lamports());
+        if shortfall > 0 {
+            system_program::transfer(
+                CpiContext::new(
+                    ctx.accounts.system_program.to_account_info(),
+                    system_program::Transfer {
+                        from: ctx.accounts.payer.to_account_info(),
+                        to: info.clone(),
+                    },
+                ),
+                shortfall,
+            )?;
+        }
+        info.resize(space)?;
+
+        // A shrunk variable-length field leaves stale bytes behind the legacy
+        // serialization, which would otherwise decode as the version and the
+        // appended fields
+        let mut data = info.try_borrow_mut_data()?;
+        let serialized_len = legacy_len
+            .checked_sub(T::unused_len(&data)?)
+            .ok_or(MigrationError::UnknownLayout)?;
+        data[serialized_len..].fill(0);
+    }
+
+    let mut account = T::try_deserialize(&mut &info.try_borrow_data()?[..])?;
+    let from_version = account.version();
+    require!(
+        from_version <= T::CURRENT_VERSION,
+        MigrationError::UnknownVersion
+    );
+    require!(
+        from_version < T::CURRENT_VERSION,
+        MigrationError::AlreadyMigrated
+    );
+    require!(
+        layouts.contains(&(legacy_len, from_version)),
+        MigrationError::UnknownLayout
+    );
+
+    account.upgrade()?;
+    account.try_serialize(&mut &mut info.try_borrow_mut_data()?[..])?;
+
+    msg!(
+        "Migrated {} from v{} to v{}",
+        info.key(),
+        from_version,
+        account.version()
+    );
+
+    emit!(AccountMigrated {
+        account: info.key(),
+        from_version,
+        to_version: account.version(),
+        new_size: info.data_len() as u64,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+    Ok(())
+}
@@ -1,9 +1,13 @@
 pub mod execute_swap;
 pub mod initialize_fee_vault;
 pub mod initialize_global_state;
+// Test mode skips the CPI and the checks that read the swap results
+#[cfg_attr(feature = "test", allow(unused_variables))]
 pub mod jupiter_swap;
 pub mod marketplace;
+pub mod migrate;
 pub mod register_user;
+pub mod update_price_feed;
 pub mod vault;
 
 pub use execute_swap::*;
@@ -11,7 +15,9 @@ pub use initialize_fee_vault::*;
 pub use initialize_global_state::*;
 pub use jupiter_swap::*;
 pub use marketplace::*;
+pub use migrate::*;
 pub use register_user::*;
+pub use update_price_feed::*;
 pub use vault::JupiterStrategyType;
 pub use vault::*;
 
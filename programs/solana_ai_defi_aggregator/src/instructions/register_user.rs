@@ -1,5 +1,5 @@
 use crate::events::swap_events::UserRegistered;
-use crate::state::UserState;
+use crate::state::{UserState, Versioned};
 use anchor_lang::prelude::*;
 
 #[derive(Accounts)]
@@ -9,7 +9,7 @@ pub struct RegisterUser<'info> {
         seeds = [b"user", user.key().as_ref()],
         bump,
         payer = authority,
-        space = 8 + UserState::LEN,
+        space = 8 + UserState::INIT_SPACE,
     )]
     pub user_state: Account<'info, UserState>,
 
@@ -28,6 +28,7 @@ pub fn register_user_handler(ctx: Context<RegisterUser>) -> Result<()> {
     state.total_volume = 0;
     state.swaps = 0;
     state.bump = ctx.bumps.user_state;
+    state.version = UserState::CURRENT_VERSION;
 
     emit!(UserRegistered {
         user: ctx.accounts.user.key(),
@@ -18,7 +18,7 @@ pub struct TestIncreaseAssets<'info> {
     #[account(
 
         mut,
-         seeds = [b"vault", vault.token_mint.as_ref()],
+         seeds = [b"vault", vault.vault_id.to_le_bytes().as_ref()],
          bump = vault.bump
     )]
     pub vault: Account<'info, Vault>,
@@ -0,0 +1,55 @@
+use crate::error::vault_error::VaultError;
+use crate::events::risk_events::PriceFeedUpdated;
+use crate::state::{GlobalState, PriceFeed};
+use anchor_lang::prelude::*;
+use anchor_spl::token::Mint;
+
+#[derive(Accounts)]
+pub struct UpdatePriceFeed<'info> {
+    #[account(
+        mut,
+        constraint = admin.key() == global_state.admin @ VaultError::Unauthorized
+    )]
+    pub admin: Signer<'info>,
+
+    #[account(
+        seeds = [b"global_state"],
+        bump = global_state.bump
+    )]
+    pub global_state: Account<'info, GlobalState>,
+
+    #[account(
+        init_if_needed,
+        payer = admin,
+        space = 8 + PriceFeed::INIT_SPACE,
+        seeds = [b"price_feed", mint.key().as_ref()],
+        bump
+    )]
+    pub price_feed: Account<'info, PriceFeed>,
+
+    pub mint: Account<'info, Mint>,
+
+    pub system_program: Program<'info, System>,
+}
+
+/// Push a new USD price (6 decimals) for `mint`
+pub fn update_price_feed_handler(ctx: Context<UpdatePriceFeed>, price: u64) -> Result<()> {
+    require!(price > 0, VaultError::InvalidAmount);
+
+    let now = Clock::get()?.unix_timestamp;
+    let price_feed = &mut ctx.accounts.price_feed;
+    price_feed.mint = ctx.accounts.mint.key();
+    price_feed.price = price;
+    price_feed.decimals = ctx.accounts.mint.decimals;
+    price_feed.updated_at = now;
+    price_feed.bump = ctx.bumps.price_feed;
+
+    msg!("Price for {}: {}", price_feed.mint, price);
+
+    emit!(PriceFeedUpdated {
+        mint: price_feed.mint,
+        price,
+        timestamp: now,
+    });
+    Ok(())
+}
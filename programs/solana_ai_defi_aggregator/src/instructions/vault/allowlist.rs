@@ -0,0 +1,89 @@
+use crate::error::vault_error::VaultError;
+use crate::events::vault_events::AllowlistUpdated;
+use crate::state::{AllowlistEntry, Vault};
+use anchor_lang::prelude::*;
+
+#[derive(Accounts)]
+#[instruction(depositor: Pubkey)]
+pub struct AddToAllowlist<'info> {
+    #[account(
+        mut,
+        constraint = manager.key() == vault.manager @ VaultError::Unauthorized
+    )]
+    pub manager: Signer<'info>,
+
+    #[account(
+        seeds = [b"vault", vault.vault_id.to_le_bytes().as_ref()],
+        bump = vault.bump,
+    )]
+    pub vault: Account<'info, Vault>,
+
+    #[account(
+        init,
+        payer = manager,
+        space = 8 + AllowlistEntry::INIT_SPACE,
+        seeds = [b"allowlist", vault.key().as_ref(), depositor.as_ref()],
+        bump
+    )]
+    pub allowlist_entry: Account<'info, AllowlistEntry>,
+
+    pub system_program: Program<'info, System>,
+}
+
+/// Approve `depositor` for a vault gated by `DepositGate::Allowlist`
+pub fn add_to_allowlist(ctx: Context<AddToAllowlist>, depositor: Pubkey) -> Result<()> {
+    let entry = &mut ctx.accounts.allowlist_entry;
+    entry.vault = ctx.accounts.vault.key();
+    entry.depositor = depositor;
+    entry.bump = ctx.bumps.allowlist_entry;
+
+    msg!("Allowlisted depositor {}", depositor);
+
+    emit!(AllowlistUpdated {
+        vault: entry.vault,
+        depositor,
+        allowed: true,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct RemoveFromAllowlist<'info> {
+    #[account(
+        mut,
+        constraint = manager.key() == vault.manager @ VaultError::Unauthorized
+    )]
+    pub manager: Signer<'info>,
+
+    #[account(
+        seeds = [b"vault", vault.vault_id.to_le_bytes().as_ref()],
+        bump = vault.bump,
+    )]
+    pub vault: Account<'info, Vault>,
+
+    #[account(
+        mut,
+        close = manager,
+        seeds = [b"allowlist", vault.key().as_ref(), allowlist_entry.depositor.as_ref()],
+        bump = allowlist_entry.bump,
+        has_one = vault,
+    )]
+    pub allowlist_entry: Account<'info, AllowlistEntry>,
+}
+
+/// Revoke a depositor's approval. Existing shares are unaffected; only new
+/// deposits are refused.
+pub fn remove_from_allowlist(ctx: Context<RemoveFromAllowlist>) -> Result<()> {
+    let depositor = ctx.accounts.allowlist_entry.depositor;
+
+    msg!("Removed depositor {} from allowlist", depositor);
+
+    emit!(AllowlistUpdated {
+        vault: ctx.accounts.vault.key(),
+        depositor,
+        allowed: false,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+    Ok(())
+}
@@ -0,0 +1,540 @@
+use crate::error::vault_error::VaultError;
+use crate::events::vault_events::{
+    ChildVaultAdded, ChildVaultAllocated, ChildVaultRemoved, ChildVaultWithdrawn,
+    ChildVaultsSynced, LossSource,
+};
+use crate::instructions::vault::loss::NavMark;
+use crate::program::SolanaAiDefiAggregator;
+use crate::state::{ChildAllocation, DepositorPosition, Vault, MAX_CHILD_VAULTS};
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_lang::solana_program::program::invoke_signed;
+use anchor_lang::InstructionData;
+use anchor_spl::associated_token::AssociatedToken;
+use anchor_spl::token::{self, Mint, Token, TokenAccount};
+
+// ===========================================
+// Child vaults (fund of funds)
+// ===========================================
+//
+// A parent vault can hold shares of other vaults in this program. It enters
+// and leaves a child through the child's own `deposit` / `withdraw`, called
+// by CPI into this program with the parent vault PDA signing as depositor,
+// so the child's gates and caps apply to the parent like to anyone. A child
+// with a lockup refuses the parent: its shares would sit in escrow, out of
+// the parent's `share_account` and so out of its NAV.
+//
+// Child shares count toward the parent's NAV through `vault.child_assets`,
+// valued at each child's share price. Allocations book the shares received
+// and withdrawals release the shares redeemed; `sync_child_vaults` re-marks
+// every child at once.
+//
+// A vault with children can never become a child itself, and a child can
+// never take children (`parent_count`), so nesting stays one level deep and
+// the graph free of cycles. A child stays open while any parent holds it.
+
+#[derive(Accounts)]
+pub struct AddChildVault<'info> {
+    #[account(
+        mut,
+        constraint = manager.key() == vault.manager @ VaultError::Unauthorized
+    )]
+    pub manager: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"vault", vault.vault_id.to_le_bytes().as_ref()],
+        bump = vault.bump,
+        constraint = vault.parent_count == 0 @ VaultError::VaultIsChild,
+    )]
+    pub vault: Box<Account<'info, Vault>>,
+
+    #[account(
+        mut,
+        seeds = [b"vault", child_vault.vault_id.to_le_bytes().as_ref()],
+        bump = child_vault.bump,
+        constraint = child_vault.key() != vault.key() @ VaultError::InvalidChildVault,
+        constraint = child_vault.token_mint == vault.token_mint @ VaultError::InvalidChildVault,
+        constraint = child_vault.child_count == 0 @ VaultError::InvalidChildVault,
+    )]
+    pub child_vault: Box<Account<'info, Vault>>,
+
+    #[account(address = child_vault.share_mint @ VaultError::MintMismatch)]
+    pub child_share_mint: Box<Account<'info, Mint>>,
+
+    #[account(
+        init,
+        payer = manager,
+        space = 8 + ChildAllocation::INIT_SPACE,
+        seeds = [b"child_allocation", vault.key().as_ref(), child_vault.key().as_ref()],
+        bump
+    )]
+    pub child_allocation: Box<Account<'info, ChildAllocation>>,
+
+    /// Parent-owned account for the child's shares
+    #[account(
+        init_if_needed,
+        payer = manager,
+        associated_token::mint = child_share_mint,
+        associated_token::authority = vault,
+    )]
+    pub share_account: Box<Account<'info, TokenAccount>>,
+
+    /// The parent's position in the child. Created here because the parent
+    /// vault cannot pay rent when it deposits through CPI.
+    #[account(
+        init_if_needed,
+        payer = manager,
+        space = 8 + DepositorPosition::INIT_SPACE,
+        seeds = [b"position", child_vault.key().as_ref(), vault.key().as_ref()],
+        bump
+    )]
+    pub child_position: Box<Account<'info, DepositorPosition>>,
+
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+pub fn add_child_vault(ctx: Context<AddChildVault>) -> Result<()> {
+    let vault = &mut ctx.accounts.vault;
+    require!(
+        vault.child_count < MAX_CHILD_VAULTS,
+        VaultError::TooManyChildVaults
+    );
+    vault.child_count += 1;
+
+    let child_vault = &mut ctx.accounts.child_vault;
+    child_vault.parent_count = child_vault
+        .parent_count
+        .checked_add(1)
+        .ok_or(VaultError::MathOverflow)?;
+
+    let child = child_vault.key();
+    let allocation = &mut ctx.accounts.child_allocation;
+    allocation.parent = vault.key();
+    allocation.child = child;
+    allocation.share_account = ctx.accounts.share_account.key();
+    allocation.bump = ctx.bumps.child_allocation;
+
+    ctx.accounts
+        .child_position
+        .ensure_initialized(child, vault.key(), ctx.bumps.child_position);
+
+    msg!(
+        "Child vault {} added ({} children)",
+        child,
+        vault.child_count
+    );
+
+    emit!(ChildVaultAdded {
+        vault: vault.key(),
+        child_vault: child,
+        share_account: allocation.share_account,
+        child_count: vault.child_count,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct RemoveChildVault<'info> {
+    /// Receives the rent of the allocation, share account and position
+    #[account(
+        mut,
+        constraint = manager.key() == vault.manager @ VaultError::Unauthorized
+    )]
+    pub manager: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"vault", vault.vault_id.to_le_bytes().as_ref()],
+        bump = vault.bump,
+    )]
+    pub vault: Box<Account<'info, Vault>>,
+
+    #[account(
+        mut,
+        close = manager,
+        seeds = [b"child_allocation", vault.key().as_ref(), child_allocation.child.as_ref()],
+        bump = child_allocation.bump,
+    )]
+    pub child_allocation: Box<Account<'info, ChildAllocation>>,
+
+    #[account(mut, address = child_allocation.child)]
+    pub child_vault: Box<Account<'info, Vault>>,
+
+    #[account(mut, address = child_allocation.share_account)]
+    pub share_account: Box<Account<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        close = manager,
+        seeds = [b"position", child_allocation.child.as_ref(), vault.key().as_ref()],
+        bump = child_position.bump,
+    )]
+    pub child_position: Box<Account<'info, DepositorPosition>>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+/// Drop a child the vault no longer holds shares of
+pub fn remove_child_vault(ctx: Context<RemoveChildVault>) -> Result<()> {
+    require!(
+        ctx.accounts.share_account.amount == 0,
+        VaultError::VaultNotEmpty
+    );
+
+    let vault = &ctx.accounts.vault;
+    let vault_id_bytes = vault.vault_id.to_le_bytes();
+    let seeds = &[b"vault".as_ref(), vault_id_bytes.as_ref(), &[vault.bump]];
+    let signer_seeds = &[&seeds[..]];
+
+    token::close_account(CpiContext::new_with_signer(
+        ctx.accounts.token_program.to_account_info(),
+        token::CloseAccount {
+            account: ctx.accounts.share_account.to_account_info(),
+            destination: ctx.accounts.manager.to_account_info(),
+            authority: vault.to_account_info(),
+        },
+        signer_seeds,
+    ))?;
+
+    let vault = &mut ctx.accounts.vault;
+    vault.child_count = vault.child_count.saturating_sub(1);
+    if vault.child_count == 0 {
+        // Nothing left to value: drop any rounding left from earlier marks
+        vault.child_assets = 0;
+    }
+    // Saturating: parents that added it before v10 were not counted
+    let child_vault = &mut ctx.accounts.child_vault;
+    child_vault.parent_count = child_vault.parent_count.saturating_sub(1);
+
+    let child = ctx.accounts.child_allocation.child;
+    msg!(
+        "Child vault {} removed ({} children)",
+        child,
+        vault.child_count
+    );
+
+    emit!(ChildVaultRemoved {
+        vault: vault.key(),
+        child_vault: child,
+        child_count: vault.child_count,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct ChildVaultOperation<'info> {
+    /// Keeper or manager (anyone may withdraw once the parent is shut down)
+    pub authority: Signer<'info>,
+
+    /// Parent vault; signs the child's deposit and withdraw as depositor
+    #[account(
+        mut,
+        seeds = [b"vault", vault.vault_id.to_le_bytes().as_ref()],
+        bump = vault.bump,
+    )]
+    pub vault: Box<Account<'info, Vault>>,
+
+    #[account(
+        mut,
+        seeds = [b"vault_token_account", vault.key().as_ref()],
+        bump,
+    )]
+    pub vault_token_account: Box<Account<'info, TokenAccount>>,
+
+    #[account(
+        seeds = [b"child_allocation", vault.key().as_ref(), child_vault.key().as_ref()],
+        bump = child_allocation.bump,
+    )]
+    pub child_allocation: Box<Account<'info, ChildAllocation>>,
+
+    #[account(mut, address = child_allocation.child)]
+    pub child_vault: Box<Account<'info, Vault>>,
+
+    /// CHECK: validated by the child's deposit / withdraw
+    #[account(mut)]
+    pub child_vault_token_account: UncheckedAccount<'info>,
+
+    /// CHECK: validated by the child's deposit / withdraw
+    #[account(mut)]
+    pub child_share_mint: UncheckedAccount<'info>,
+
+    #[account(mut, address = child_allocation.share_account)]
+    pub share_account: Box<Account<'info, TokenAccount>>,
+
+    /// CHECK: the parent's position in the child, validated by the child
+    #[account(mut)]
+    pub child_position: UncheckedAccount<'info>,
+
+    /// CHECK: the parent's entry on the child's allowlist, if it has one
+    pub child_allowlist_entry: Option<UncheckedAccount<'info>>,
+
+    pub aggregator_program: Program<'info, SolanaAiDefiAggregator>,
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> ChildVaultOperation<'info> {
+    /// Invoke one of this program's instructions with the parent vault signing
+    fn invoke_child(&self, ix: Instruction) -> Result<()> {
+        let vault_id_bytes = self.vault.vault_id.to_le_bytes();
+        let seeds = &[
+            b"vault".as_ref(),
+            vault_id_bytes.as_ref(),
+            &[self.vault.bump],
+        ];
+        invoke_signed(&ix, &self.to_account_infos(), &[&seeds[..]])?;
+        Ok(())
+    }
+
+    fn deposit_into_child(&self, amount: u64, proof: Vec<[u8; 32]>) -> Result<()> {
+        let accounts = crate::accounts::Deposit {
+            user: self.vault.key(),
+            vault: self.child_vault.key(),
+            user_token_account: self.vault_token_account.key(),
+            vault_token_account: self.child_vault_token_account.key(),
+            user_share_account: self.share_account.key(),
+            share_mint: self.child_share_mint.key(),
+            position: self.child_position.key(),
+            allowlist_entry: self.child_allowlist_entry.as_ref().map(|entry| entry.key()),
+            locked_share_account: None,
+            token_program: self.token_program.key(),
+            associated_token_program: self.associated_token_program.key(),
+            system_program: self.system_program.key(),
+        };
+        self.invoke_child(Instruction {
+            program_id: crate::ID,
+            accounts: accounts.to_account_metas(None),
+            data: crate::instruction::Deposit { amount, proof }.data(),
+        })
+    }
+
+    fn withdraw_from_child(&self, shares: u64) -> Result<()> {
+        let accounts = crate::accounts::Withdraw {
+            user: self.vault.key(),
+            vault: self.child_vault.key(),
+            user_token_account: self.vault_token_account.key(),
+            vault_token_account: self.child_vault_token_account.key(),
+            user_share_account: self.share_account.key(),
+            share_mint: self.child_share_mint.key(),
+            position: self.child_position.key(),
+            token_program: self.token_program.key(),
+            system_program: self.system_program.key(),
+        };
+        self.invoke_child(Instruction {
+            program_id: crate::ID,
+            accounts: accounts.to_account_metas(None),
+            data: crate::instruction::Withdraw { shares }.data(),
+        })
+    }
+
+    /// Refresh the accounts the child instruction changed. The child vault
+    /// must be reloaded: its stale copy would be written back on exit.
+    fn reload(&mut self) -> Result<()> {
+        self.vault_token_account.reload()?;
+        self.share_account.reload()?;
+        self.child_vault.reload()
+    }
+}
+
+/// Deposit `amount` idle base tokens into the child vault.
+/// `proof` is forwarded to merkle-gated children; pass it empty otherwise.
+pub fn allocate_to_child(
+    ctx: Context<ChildVaultOperation>,
+    amount: u64,
+    proof: Vec<[u8; 32]>,
+) -> Result<()> {
+    let accounts = ctx.accounts;
+    require!(
+        accounts.vault.is_operator(&accounts.authority.key()),
+        VaultError::Unauthorized
+    );
+    require!(accounts.vault.is_active(), VaultError::StrategyDisabled);
+    require!(amount > 0, VaultError::InvalidAmount);
+    require!(
+        accounts.vault.total_assets >= amount && accounts.vault_token_account.amount >= amount,
+        VaultError::InsufficientAssets
+    );
+
+    let mark = NavMark::of(&accounts.vault)?;
+    let balance_before = accounts.vault_token_account.amount;
+    let shares_before = accounts.share_account.amount;
+
+    accounts.deposit_into_child(amount, proof)?;
+    accounts.reload()?;
+
+    let spent = balance_before
+        .checked_sub(accounts.vault_token_account.amount)
+        .ok_or(VaultError::MathOverflow)?;
+    let shares = accounts
+        .share_account
+        .amount
+        .checked_sub(shares_before)
+        .ok_or(VaultError::MathOverflow)?;
+    let value = accounts.child_vault.value_of_shares(shares)?;
+
+    let vault = &mut accounts.vault;
+    vault.total_assets = vault
+        .total_assets
+        .checked_sub(spent)
+        .ok_or(VaultError::MathOverflow)?;
+    vault.child_assets = vault
+        .child_assets
+        .checked_add(value)
+        .ok_or(VaultError::MathOverflow)?;
+
+    msg!(
+        "Allocated {} to child {} for {} shares worth {}",
+        spent,
+        accounts.child_vault.key(),
+        shares,
+        value
+    );
+
+    emit!(ChildVaultAllocated {
+        vault: vault.key(),
+        child_vault: accounts.child_vault.key(),
+        amount: spent,
+        shares,
+        child_assets: vault.child_assets,
+        total_assets: vault.total_assets,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    mark.recognize_loss(vault, LossSource::ChildVault)
+}
+
+/// Redeem `shares` of the child vault back into base tokens
+pub fn withdraw_from_child(ctx: Context<ChildVaultOperation>, shares: u64) -> Result<()> {
+    let accounts = ctx.accounts;
+    require!(
+        accounts.vault.is_operator(&accounts.authority.key()) || accounts.vault.is_shutdown(),
+        VaultError::Unauthorized
+    );
+    require!(shares > 0, VaultError::ZeroShares);
+    require!(
+        accounts.share_account.amount >= shares,
+        VaultError::InsufficientShares
+    );
+
+    let mark = NavMark::of(&accounts.vault)?;
+    let balance_before = accounts.vault_token_account.amount;
+    // Release the shares at the value they carry now; rounding in the child
+    // shows up as a loss
+    let value = accounts.child_vault.value_of_shares(shares)?;
+
+    accounts.withdraw_from_child(shares)?;
+    accounts.reload()?;
+
+    let received = accounts
+        .vault_token_account
+        .amount
+        .checked_sub(balance_before)
+        .ok_or(VaultError::MathOverflow)?;
+
+    let vault = &mut accounts.vault;
+    vault.total_assets = vault
+        .total_assets
+        .checked_add(received)
+        .ok_or(VaultError::MathOverflow)?;
+    vault.child_assets = vault.child_assets.saturating_sub(value);
+
+    msg!(
+        "Redeemed {} shares of child {} for {}",
+        shares,
+        accounts.child_vault.key(),
+        received
+    );
+
+    emit!(ChildVaultWithdrawn {
+        vault: vault.key(),
+        child_vault: accounts.child_vault.key(),
+        shares,
+        received,
+        child_assets: vault.child_assets,
+        total_assets: vault.total_assets,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    mark.recognize_loss(vault, LossSource::ChildVault)
+}
+
+#[derive(Accounts)]
+pub struct SyncChildVaults<'info> {
+    #[account(
+        mut,
+        seeds = [b"vault", vault.vault_id.to_le_bytes().as_ref()],
+        bump = vault.bump,
+    )]
+    pub vault: Box<Account<'info, Vault>>,
+}
+
+/// Re-mark `vault.child_assets` at every child's share price. Permissionless.
+///
+/// Remaining accounts: (child allocation, child vault, share account) for
+/// every child of the vault, each exactly once.
+pub fn sync_child_vaults<'info>(
+    ctx: Context<'_, '_, 'info, 'info, SyncChildVaults<'info>>,
+) -> Result<()> {
+    let vault_key = ctx.accounts.vault.key();
+    require!(
+        ctx.remaining_accounts.len() == ctx.accounts.vault.child_count as usize * 3,
+        VaultError::ChildVaultsMissing
+    );
+
+    let mut seen: Vec<Pubkey> = Vec::with_capacity(ctx.accounts.vault.child_count as usize);
+    let mut child_assets: u64 = 0;
+
+    for triple in ctx.remaining_accounts.chunks(3) {
+        let allocation = Account::<ChildAllocation>::try_from(&triple[0])?;
+        let child_vault = Account::<Vault>::try_from(&triple[1])?;
+        let share_account = Account::<TokenAccount>::try_from(&triple[2])?;
+
+        require_keys_eq!(allocation.parent, vault_key, VaultError::ChildVaultsMissing);
+        require!(
+            !seen.contains(&allocation.key()),
+            VaultError::ChildVaultsMissing
+        );
+        seen.push(allocation.key());
+        require_keys_eq!(
+            child_vault.key(),
+            allocation.child,
+            VaultError::InvalidChildVault
+        );
+        require_keys_eq!(
+            share_account.key(),
+            allocation.share_account,
+            VaultError::InvalidOwner
+        );
+
+        let value = child_vault.value_of_shares(share_account.amount)?;
+        child_assets = child_assets
+            .checked_add(value)
+            .ok_or(VaultError::MathOverflow)?;
+    }
+
+    let mark = NavMark::of(&ctx.accounts.vault)?;
+    let vault = &mut ctx.accounts.vault;
+    let previous_child_assets = vault.child_assets;
+    vault.child_assets = child_assets;
+
+    msg!(
+        "Child assets: {} -> {}",
+        previous_child_assets,
+        child_assets
+    );
+
+    emit!(ChildVaultsSynced {
+        vault: vault_key,
+        previous_child_assets,
+        child_assets,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    mark.recognize_loss(vault, LossSource::ChildVault)
+}
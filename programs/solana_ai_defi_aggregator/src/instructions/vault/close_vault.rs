@@ -0,0 +1,227 @@
+use crate::error::vault_error::VaultError;
+use crate::events::vault_events::VaultClosed;
+use crate::state::{NavHistory, RiskPolicy, Vault, VaultRegistryEntry, VaultStatus, YieldAdapter};
+use anchor_lang::prelude::*;
+use anchor_spl::token::spl_token::instruction::AuthorityType;
+use anchor_spl::token::{self, Mint, Token, TokenAccount};
+
+// ===========================================
+// Vault closure
+// ===========================================
+//
+// A vault with no shares outstanding and no withdrawal left to claim can be
+// retired. Whatever its token accounts still hold belongs to no depositor (the
+// virtual-share offset and floor rounding leave dust behind the last exit), so
+// it is swept to the fee recipient and the booked value is written off. Every
+// account the vault pays rent for is closed back to the manager, the share
+// mint loses its mint authority so no share can ever be issued again, and the
+// registry entry is marked Closed (ids are never reused).
+//
+// Depositor positions stay open: they are the depositors' accounts, hold no
+// value once the shares are gone, and are not enumerable here. Lockup escrows
+// (`locked_shares`) are vault token accounts and are closed when passed with
+// the remaining accounts; any left out stay open, empty.
+
+#[derive(Accounts)]
+pub struct CloseVault<'info> {
+    /// Receives the rent of every closed account
+    #[account(
+        mut,
+        constraint = manager.key() == vault.manager @ VaultError::Unauthorized
+    )]
+    pub manager: Signer<'info>,
+
+    #[account(
+        mut,
+        close = manager,
+        seeds = [b"vault", vault.vault_id.to_le_bytes().as_ref()],
+        bump = vault.bump,
+    )]
+    pub vault: Box<Account<'info, Vault>>,
+
+    #[account(
+        mut,
+        seeds = [b"vault_registry_entry", vault.vault_id.to_le_bytes().as_ref()],
+        bump = registry_entry.bump,
+        constraint = registry_entry.vault == vault.key() @ VaultError::VaultNotRegistered,
+    )]
+    pub registry_entry: Box<Account<'info, VaultRegistryEntry>>,
+
+    #[account(
+        mut,
+        constraint = share_mint.key() == vault.share_mint @ VaultError::MintMismatch,
+    )]
+    pub share_mint: Box<Account<'info, Mint>>,
+
+    #[account(
+        mut,
+        seeds = [b"vault_token_account", vault.key().as_ref()],
+        bump,
+    )]
+    pub vault_token_account: Box<Account<'info, TokenAccount>>,
+
+    /// CHECK: queued-withdrawal share escrow; closed if it was ever created
+    #[account(mut, seeds = [b"withdraw_escrow", vault.key().as_ref()], bump)]
+    pub share_escrow: UncheckedAccount<'info>,
+
+    /// CHECK: settled-withdrawal token escrow; closed if it was ever created
+    #[account(mut, seeds = [b"claim_escrow", vault.key().as_ref()], bump)]
+    pub claim_escrow: UncheckedAccount<'info>,
+
+    /// CHECK: yield adapter collateral; closed if it was ever created
+    #[account(mut, seeds = [b"adapter_collateral", vault.key().as_ref()], bump)]
+    pub adapter_collateral: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        close = manager,
+        seeds = [b"nav_history", vault.key().as_ref()],
+        bump = nav_history.bump,
+    )]
+    pub nav_history: Option<Box<Account<'info, NavHistory>>>,
+
+    #[account(
+        mut,
+        close = manager,
+        seeds = [b"risk_policy", vault.key().as_ref()],
+        bump = risk_policy.bump,
+    )]
+    pub risk_policy: Option<Box<Account<'info, RiskPolicy>>>,
+
+    #[account(
+        mut,
+        close = manager,
+        seeds = [b"yield_adapter", vault.key().as_ref()],
+        bump = yield_adapter.bump,
+    )]
+    pub yield_adapter: Option<Box<Account<'info, YieldAdapter>>>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+/// Retire a vault nobody holds shares of and reclaim its rent.
+///
+/// Remaining accounts, in any order:
+/// - any other token accounts owned by the vault (e.g. strategy output mints
+///   or lockup escrows), closed to the manager like the base and escrow
+///   accounts;
+/// - token accounts of the fee recipient, one per mint the vault accounts
+///   still hold a balance of, which receive that balance.
+pub fn close_vault<'info>(ctx: Context<'_, '_, 'info, 'info, CloseVault<'info>>) -> Result<()> {
+    let vault = &ctx.accounts.vault;
+    let vault_key = vault.key();
+    require!(
+        vault.total_shares == 0 && ctx.accounts.share_mint.supply == 0,
+        VaultError::VaultNotEmpty
+    );
+    require!(vault.child_count == 0, VaultError::VaultHasChildren);
+    // Its parents' allocations would point at a closed account
+    require!(vault.parent_count == 0, VaultError::VaultIsChild);
+    // Settled withdrawals are paid from the claim escrow swept below
+    require!(vault.pending_claims == 0, VaultError::ClaimsPending);
+
+    let vault_id_bytes = vault.vault_id.to_le_bytes();
+    let seeds = &[b"vault".as_ref(), vault_id_bytes.as_ref(), &[vault.bump]];
+    let signer_seeds = &[&seeds[..]];
+
+    let mut token_accounts = vec![ctx.accounts.vault_token_account.to_account_info()];
+    for escrow in [
+        &ctx.accounts.share_escrow,
+        &ctx.accounts.claim_escrow,
+        &ctx.accounts.adapter_collateral,
+    ] {
+        // Escrows are created lazily; skip the ones that never were
+        if escrow.owner == &token::ID {
+            token_accounts.push(escrow.to_account_info());
+        }
+    }
+
+    let mut sweep_accounts: Vec<(AccountInfo<'info>, Pubkey)> = Vec::new();
+    for info in ctx.remaining_accounts {
+        require_keys_eq!(*info.owner, token::ID, VaultError::InvalidOwner);
+        let account = TokenAccount::try_deserialize(&mut &info.try_borrow_data()?[..])?;
+        if account.owner == vault_key {
+            token_accounts.push(info.clone());
+        } else {
+            require_keys_eq!(account.owner, vault.fee_recipient, VaultError::InvalidOwner);
+            sweep_accounts.push((info.clone(), account.mint));
+        }
+    }
+
+    let mut seen: Vec<Pubkey> = Vec::with_capacity(token_accounts.len());
+    for info in &token_accounts {
+        require_keys_eq!(*info.owner, token::ID, VaultError::InvalidOwner);
+        let account = TokenAccount::try_deserialize(&mut &info.try_borrow_data()?[..])?;
+        require_keys_eq!(account.owner, vault_key, VaultError::InvalidOwner);
+        require!(
+            !seen.contains(&info.key()),
+            VaultError::DuplicateTokenAccount
+        );
+        seen.push(info.key());
+
+        if account.amount > 0 {
+            let (destination, _) = sweep_accounts
+                .iter()
+                .find(|(_, mint)| *mint == account.mint)
+                .ok_or(VaultError::SweepAccountRequired)?;
+            token::transfer(
+                CpiContext::new_with_signer(
+                    ctx.accounts.token_program.to_account_info(),
+                    token::Transfer {
+                        from: info.clone(),
+                        to: destination.clone(),
+                        authority: vault.to_account_info(),
+                    },
+                    signer_seeds,
+                ),
+                account.amount,
+            )?;
+        }
+
+        token::close_account(CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            token::CloseAccount {
+                account: info.clone(),
+                destination: ctx.accounts.manager.to_account_info(),
+                authority: vault.to_account_info(),
+            },
+            signer_seeds,
+        ))?;
+    }
+
+    // Mints cannot be closed: drop the authority so the mint is inert
+    token::set_authority(
+        CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            token::SetAuthority {
+                current_authority: vault.to_account_info(),
+                account_or_mint: ctx.accounts.share_mint.to_account_info(),
+            },
+            signer_seeds,
+        ),
+        AuthorityType::MintTokens,
+        None,
+    )?;
+
+    ctx.accounts.registry_entry.status = VaultStatus::Closed;
+
+    // No shares are left to claim what is still booked
+    let nav_written_off = vault.nav();
+
+    msg!(
+        "Vault {} closed, {} token accounts reclaimed, {} written off",
+        vault_key,
+        seen.len(),
+        nav_written_off
+    );
+
+    emit!(VaultClosed {
+        vault: vault_key,
+        vault_id: vault.vault_id,
+        closed_by: ctx.accounts.manager.key(),
+        token_accounts_closed: seen.len() as u8,
+        nav_written_off,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+    Ok(())
+}
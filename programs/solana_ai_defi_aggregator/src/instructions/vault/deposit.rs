@@ -1,6 +1,6 @@
 use crate::error::vault_error::VaultError;
 use crate::events::vault_events::DepositEvent;
-use crate::state::Vault;
+use crate::state::{AllowlistEntry, DepositorPosition, Vault};
 use anchor_lang::prelude::*;
 use anchor_spl::associated_token::AssociatedToken;
 use anchor_spl::token::{self, Mint, MintTo, Token, TokenAccount, Transfer};
@@ -12,7 +12,7 @@ pub struct Deposit<'info> {
 
     #[account(
          mut,
-         seeds = [b"vault", vault.token_mint.as_ref()],
+         seeds = [b"vault", vault.vault_id.to_le_bytes().as_ref()],
          bump = vault.bump
     )]
     pub vault: Account<'info, Vault>,
@@ -47,16 +47,84 @@ pub struct Deposit<'info> {
     )]
     pub share_mint: Account<'info, Mint>,
 
+    /// Depositor position (tracks net deposits for per-user caps)
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = 8 + DepositorPosition::INIT_SPACE,
+        seeds = [b"position", vault.key().as_ref(), user.key().as_ref()],
+        bump
+    )]
+    pub position: Account<'info, DepositorPosition>,
+
+    /// Depositor's approval, required when the vault is allowlist-gated
+    #[account(
+        seeds = [b"allowlist", vault.key().as_ref(), user.key().as_ref()],
+        bump = allowlist_entry.bump,
+    )]
+    pub allowlist_entry: Option<Account<'info, AllowlistEntry>>,
+
+    /// Vault-owned escrow for the position's locked shares, required while
+    /// the vault has a lockup (see `release_locked_shares`)
+    #[account(
+        init_if_needed,
+        payer = user,
+        seeds = [b"locked_shares", position.key().as_ref()],
+        bump,
+        token::mint = share_mint,
+        token::authority = vault,
+    )]
+    pub locked_share_account: Option<Box<Account<'info, TokenAccount>>>,
+
     pub token_program: Program<'info, Token>,
     pub associated_token_program: Program<'info, AssociatedToken>,
     pub system_program: Program<'info, System>,
 }
 
-pub fn deposit(ctx: Context<Deposit>, amount: u64) -> Result<()> {
+/// `proof` is only read when the vault is merkle-gated; pass it empty otherwise
+pub fn deposit(ctx: Context<Deposit>, amount: u64, proof: Vec<[u8; 32]>) -> Result<()> {
     require!(amount > 0, VaultError::ZeroAmount);
 
+    // Shares are rounded down, in favor of the vault
+    let shares_to_mint = ctx.accounts.vault.preview_deposit(amount)?;
+
+    process_deposit(ctx, amount, shares_to_mint, &proof)
+}
+
+/// Mint exactly `shares`, pulling at most `max_assets_in` tokens from the user
+pub fn mint(
+    ctx: Context<Deposit>,
+    shares: u64,
+    max_assets_in: u64,
+    proof: Vec<[u8; 32]>,
+) -> Result<()> {
+    require!(shares > 0, VaultError::ZeroShares);
+
+    // Assets are rounded up, in favor of the vault
+    let assets_in = ctx.accounts.vault.preview_mint(shares)?;
+    require!(assets_in > 0, VaultError::ZeroAmount);
+    require!(assets_in <= max_assets_in, VaultError::SlippageExceeded);
+
+    process_deposit(ctx, assets_in, shares, &proof)
+}
+
+fn process_deposit(
+    ctx: Context<Deposit>,
+    amount: u64,
+    shares_to_mint: u64,
+    proof: &[[u8; 32]],
+) -> Result<()> {
     let vault = &mut ctx.accounts.vault;
-    let shares_to_mint = vault.assets_to_shares(amount)?;
+    let position = &mut ctx.accounts.position;
+
+    vault.check_deposit_gate(
+        &ctx.accounts.user.key(),
+        ctx.accounts.allowlist_entry.is_some(),
+        proof,
+    )?;
+
+    position.ensure_initialized(vault.key(), ctx.accounts.user.key(), ctx.bumps.position);
+    vault.check_deposit_limits(amount, position.deposited)?;
 
     msg!("Depositing {} tokens", amount);
     msg!("Minting {} shares", shares_to_mint);
@@ -74,16 +142,26 @@ pub fn deposit(ctx: Context<Deposit>, amount: u64) -> Result<()> {
         amount,
     )?;
 
-    // Mint Shares
-    //
-    let seeds = &[b"vault", vault.token_mint.as_ref(), &[vault.bump]];
+    // Mint Shares, into escrow while the vault has a lockup so they cannot
+    // leave the position before their lock expires
+    let share_destination = if vault.lock_period > 0 {
+        ctx.accounts
+            .locked_share_account
+            .as_ref()
+            .ok_or(VaultError::LockedShareAccountRequired)?
+            .to_account_info()
+    } else {
+        ctx.accounts.user_share_account.to_account_info()
+    };
+    let vault_id_bytes = vault.vault_id.to_le_bytes();
+    let seeds = &[b"vault".as_ref(), vault_id_bytes.as_ref(), &[vault.bump]];
     let signer_seeds = &[&seeds[..]];
     token::mint_to(
         CpiContext::new_with_signer(
             ctx.accounts.token_program.to_account_info(),
             MintTo {
                 mint: ctx.accounts.share_mint.to_account_info(),
-                to: ctx.accounts.user_share_account.to_account_info(),
+                to: share_destination,
                 authority: vault.to_account_info(),
             },
             signer_seeds,
@@ -100,6 +178,16 @@ pub fn deposit(ctx: Context<Deposit>, amount: u64) -> Result<()> {
         .total_shares
         .checked_add(shares_to_mint)
         .ok_or(VaultError::MathOverflow)?;
+    position.deposited = position
+        .deposited
+        .checked_add(amount)
+        .ok_or(VaultError::MathOverflow)?;
+
+    let now = Clock::get()?.unix_timestamp;
+    // Only the shares minted here are locked; earlier ones keep their lock
+    if vault.lock_period > 0 {
+        position.lock_shares(shares_to_mint, now, vault.lock_period)?;
+    }
 
     emit!(DepositEvent {
         user: ctx.accounts.user.key(),
@@ -107,7 +195,8 @@ pub fn deposit(ctx: Context<Deposit>, amount: u64) -> Result<()> {
         shares: shares_to_mint,
         total_assets: vault.total_assets,
         total_shares: vault.total_shares,
-        timestamp: Clock::get()?.unix_timestamp,
+        lock_until: position.lock_until,
+        timestamp: now,
     });
     Ok(())
 }
@@ -1,25 +1,28 @@
 use crate::error::vault_error::VaultError;
-use crate::events::vault_events::StrategyExecutedEvent;
-use crate::instructions::vault::JupiterStrategyType;
+use crate::events::risk_events::LossLimitTriggered;
+use crate::events::vault_events::{LossSource, StrategyExecutedEvent};
+#[cfg(not(feature = "test"))]
+use crate::instructions::jupiter_swap::Jupiter;
+use crate::instructions::vault::loss::NavMark;
+use crate::instructions::vault::{invoke_jupiter_swap, JupiterStrategyType};
 use crate::state::*;
 use crate::utils::fee::calculate_fee;
 use anchor_lang::prelude::*;
-use anchor_lang::solana_program::instruction::{AccountMeta, Instruction as SolInstruction};
 use anchor_spl::associated_token::AssociatedToken;
 use anchor_spl::token::{self, Mint, Token, TokenAccount};
 #[derive(Accounts)]
 pub struct ExecuteJupiterStrategy<'info> {
-    /// Only authority can execute strategies
+    /// Only the keeper (or manager) can execute strategies
     #[account(
-        constraint = authority.key() == vault.authority @ VaultError::Unauthorized
+        constraint = vault.is_operator(&keeper.key()) @ VaultError::Unauthorized
     )]
-    pub authority: Signer<'info>,
+    pub keeper: Signer<'info>,
 
     #[account(
         mut,
-        seeds = [b"vault", vault.token_mint.as_ref()],
+        seeds = [b"vault", vault.vault_id.to_le_bytes().as_ref()],
         bump = vault.bump,
-        constraint = vault.strategy_enabled @ VaultError::StrategyDisabled,
+        constraint = vault.is_active() @ VaultError::StrategyDisabled,
     )]
     pub vault: Account<'info, Vault>,
 
@@ -69,6 +72,31 @@ pub struct ExecuteJupiterStrategy<'info> {
     pub input_mint: Box<Account<'info, Mint>>,
     pub output_mint: Box<Account<'info, Mint>>,
 
+    // ===========================
+    // Risk limits
+    // ===========================
+    /// Vault's risk policy (enforced on every execution)
+    #[account(
+        mut,
+        seeds = [b"risk_policy", vault.key().as_ref()],
+        bump = risk_policy.bump,
+    )]
+    pub risk_policy: Box<Account<'info, RiskPolicy>>,
+
+    /// Oracle price of the input mint
+    #[account(
+        seeds = [b"price_feed", input_mint.key().as_ref()],
+        bump = input_price_feed.bump,
+    )]
+    pub input_price_feed: Box<Account<'info, PriceFeed>>,
+
+    /// Oracle price of the output mint (values the output in input terms)
+    #[account(
+        seeds = [b"price_feed", output_mint.key().as_ref()],
+        bump = output_price_feed.bump,
+    )]
+    pub output_price_feed: Box<Account<'info, PriceFeed>>,
+
     /// Jupiter program
     #[cfg(feature = "test")]
     /// CHECK: Test - not validated
@@ -112,12 +140,14 @@ pub fn execute_jupiter_strategy(
             execute_jupiter_swap_inline(ctx, amount, min_output, swap_ix_data, accounts_meta)
         }
         JupiterStrategyType::Rebalance => {
-            msg!("Rebalance strategy - Coming soon");
-            Ok(())
+            // Rebalancing needs the whole portfolio priced; it has its own instruction
+            msg!("Use the rebalance instruction for Rebalance strategies");
+            err!(VaultError::StrategyNotImplemented)
         }
         JupiterStrategyType::Yield => {
-            msg!("Yield strategy - Coming soon");
-            Ok(())
+            // Yield positions live in the vault's lending adapter
+            msg!("Use deploy_to_adapter / recall_from_adapter for Yield strategies");
+            err!(VaultError::StrategyNotImplemented)
         }
     }
 }
@@ -152,6 +182,25 @@ pub fn execute_jupiter_swap_inline(
     msg!("Vault input balance: {}", vault_input_balance);
     msg!("Amount in: {}", amount);
 
+    // ============================================
+    // 1b. ENFORCE RISK POLICY
+    // ============================================
+
+    let now = Clock::get()?.unix_timestamp;
+    ctx.accounts.input_price_feed.ensure_fresh(now)?;
+    ctx.accounts.output_price_feed.ensure_fresh(now)?;
+
+    let risk_policy = &mut ctx.accounts.risk_policy;
+    risk_policy.roll_window(now);
+    risk_policy.check_execution(
+        now,
+        &ctx.accounts.input_mint.key(),
+        &ctx.accounts.output_mint.key(),
+        amount,
+    )?;
+
+    msg!("Risk checks passed");
+
     // ============================================
     // 2. RECORD PRE-SWAP BALANCES
     // ============================================
@@ -177,7 +226,8 @@ pub fn execute_jupiter_swap_inline(
     msg!("Swap amount (after fee): {}", swap_amount);
 
     // Create vault signer seeds
-    let vault_seeds = &[b"vault", vault.token_mint.as_ref(), &[vault.bump]];
+    let vault_id_bytes = vault.vault_id.to_le_bytes();
+    let vault_seeds = &[b"vault".as_ref(), vault_id_bytes.as_ref(), &[vault.bump]];
     let signer_seeds = &[&vault_seeds[..]];
 
     // Transfer fee to fee vault (vault signs)
@@ -222,55 +272,21 @@ pub fn execute_jupiter_swap_inline(
     msg!("Protected accounts validated");
 
     // ============================================
-    // 5. BUILD AND VALIDATE JUPITER INSTRUCTION
+    // 5-6. INVOKE JUPITER CPI (vault signs)
     // ============================================
 
-    // Convert Pubkeys to AccountMeta
-    let accounts: Vec<AccountMeta> = accounts_meta
-        .into_iter()
-        .map(|pubkey| AccountMeta::new(pubkey, false))
-        .collect();
-
-    // Build Jupiter instruction
-    let jupiter_ix = SolInstruction {
-        program_id: ctx.accounts.jupiter_program.key(),
-        accounts,
-        data: swap_ix_data,
-    };
-
-    // Verify it's actually calling Jupiter
-    #[cfg(not(feature = "test"))]
-    {
-        require_keys_eq!(
-            jupiter_ix.program_id,
-            ctx.accounts.jupiter_program.key(),
-            VaultError::InvalidJupiterProgram
-        );
-        msg!("Jupiter instruction validated");
-    }
-
     #[cfg(feature = "test")]
     {
         msg!("TEST MODE: Skipping Jupiter program validation");
     }
 
-    // ============================================
-    // 6. INVOKE JUPITER CPI (vault signs)
-    // ============================================
-
-    msg!("Invoking Jupiter CPI...");
-
-    #[cfg(not(feature = "test"))]
-    {
-        invoke_signed(&jupiter_ix, ctx.remaining_accounts, signer_seeds)?;
-        msg!("Jupiter CPI completed successfully");
-    }
-
-    #[cfg(feature = "test")]
-    {
-        msg!("TEST MODE: Skipping actual Jupiter CPI");
-        msg!("TEST MODE: Swap simulation complete");
-    }
+    invoke_jupiter_swap(
+        ctx.accounts.jupiter_program.key(),
+        swap_ix_data,
+        accounts_meta,
+        ctx.remaining_accounts,
+        signer_seeds,
+    )?;
 
     // ============================================
     // 7. VERIFY POST-SWAP BALANCES AND SLIPPAGE
@@ -357,13 +373,47 @@ pub fn execute_jupiter_swap_inline(
         .checked_sub(output_balance_before)
         .ok_or(VaultError::MathOverflow)?;
 
-    update_vault_accounting(
+    // The input is always the base token, so this is the output's value in base terms
+    let output_value = ctx
+        .accounts
+        .output_price_feed
+        .quote(actual_output_received, &ctx.accounts.input_price_feed)?;
+
+    let mark = NavMark::of(&ctx.accounts.vault)?;
+    let output_mint = ctx.accounts.vault_output_ata.mint;
+    let realized_pnl = update_vault_accounting(
         &mut ctx.accounts.vault,
-        &ctx.accounts.input_mint,
-        &ctx.accounts.output_mint,
+        output_mint,
         actual_input_used,
-        actual_output_received,
+        output_value,
     )?;
+    mark.recognize_loss(&ctx.accounts.vault, LossSource::Strategy)?;
+
+    // ============================================
+    // 9b. BOOK REALIZED LOSS AGAINST THE RISK POLICY
+    // ============================================
+
+    // Loss = input spent - oracle value of what came back (fees included)
+    let realized_loss = actual_input_used.saturating_sub(output_value);
+
+    msg!("Output value in input terms: {}", output_value);
+    msg!("Realized loss: {}", realized_loss);
+
+    let risk_policy = &mut ctx.accounts.risk_policy;
+    risk_policy.record_execution(now, amount, realized_loss)?;
+
+    if risk_policy.loss_limit_hit() {
+        // The trade stands; further executions are blocked until the manager resumes
+        ctx.accounts.vault.status = VaultLifecycle::Paused;
+        msg!("Daily loss limit hit - strategies disabled");
+
+        emit!(LossLimitTriggered {
+            vault: ctx.accounts.vault.key(),
+            window_loss: risk_policy.window_loss(),
+            max_daily_loss: risk_policy.max_daily_loss,
+            timestamp: now,
+        });
+    }
 
     // ============================================
     // 10. EMIT EVENT
@@ -375,7 +425,8 @@ pub fn execute_jupiter_swap_inline(
         amount,
         input_used: actual_input_used,
         output_received: actual_output_received,
-        timestamp: Clock::get()?.unix_timestamp,
+        realized_pnl,
+        timestamp: now,
     });
 
     msg!("=== Jupiter Swap Executed Successfully ===");
@@ -383,38 +434,39 @@ pub fn execute_jupiter_swap_inline(
     Ok(())
 }
 
-/// Updates vault's total_assets after a swap
+/// Moves the spent base tokens into `strategy_assets` at the output's oracle
+/// value, booked as the cost of `output_mint`
 ///
-/// SIMPLE VERSION: Just deduct what was spent
-/// The vault's token accounts track actual balances
-/// Users get proportional share of ALL tokens on withdrawal
+/// The vault's token accounts track actual balances; users get a proportional
+/// share of ALL tokens through `withdraw_in_kind`. Returns the realized PnL of
+/// the swap in base tokens.
 fn update_vault_accounting(
     vault: &mut Vault,
-    _input_mint: &Account<Mint>,
-    _output_mint: &Account<Mint>,
+    output_mint: Pubkey,
     input_used: u64,
-    _output_received: u64,
-) -> Result<()> {
+    output_value: u64,
+) -> Result<i64> {
     msg!("Updating vault accounting...");
 
-    // Simple accounting: Just deduct what was spent
-    // The vault now holds less input token, but gained output tokens
-    // The actual balances are tracked in the token accounts
     vault.total_assets = vault
         .total_assets
         .checked_sub(input_used)
         .ok_or(VaultError::MathOverflow)?;
+    vault.book_strategy_cost(output_mint, output_value)?;
+
+    let pnl = (output_value as i128 - input_used as i128)
+        .try_into()
+        .map_err(|_| VaultError::MathOverflow)?;
+    vault.record_pnl(pnl)?;
 
     msg!("Deducted {} from total_assets", input_used);
     msg!("New total_assets: {}", vault.total_assets);
+    msg!("Booked {} to strategy_assets", output_value);
+    msg!(
+        "Realized PnL: {} (cumulative {})",
+        pnl,
+        vault.cumulative_pnl
+    );
 
-    // NOTE: In production, you'd want to:
-    // 1. Track multiple token balances
-    // 2. Get prices from oracle (Pyth)
-    // 3. Calculate total USD value
-    // 4. Update share price accordingly
-    //
-    // For now, this simple version works fine for MVP
-
-    Ok(())
+    Ok(pnl)
 }
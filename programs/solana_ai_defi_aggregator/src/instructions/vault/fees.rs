@@ -0,0 +1,96 @@
+use crate::error::vault_error::VaultError;
+use crate::events::vault_events::FeesCrystallized;
+use crate::state::Vault;
+use anchor_lang::prelude::*;
+use anchor_spl::token::{self, Mint, MintTo, Token, TokenAccount};
+
+#[derive(Accounts)]
+pub struct CrystallizeFees<'info> {
+    /// Manager or keeper: decides when gains are marked
+    #[account(
+        constraint = vault.is_operator(&authority.key()) @ VaultError::Unauthorized
+    )]
+    pub authority: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"vault", vault.vault_id.to_le_bytes().as_ref()],
+        bump = vault.bump,
+    )]
+    pub vault: Account<'info, Vault>,
+
+    #[account(
+        mut,
+        constraint = share_mint.key() == vault.share_mint @ VaultError::MintMismatch,
+    )]
+    pub share_mint: Account<'info, Mint>,
+
+    /// Share account of the vault's fee recipient
+    #[account(
+        mut,
+        constraint = fee_recipient_share_account.mint == vault.share_mint @ VaultError::MintMismatch,
+        constraint = fee_recipient_share_account.owner == vault.fee_recipient @ VaultError::InvalidOwner,
+    )]
+    pub fee_recipient_share_account: Account<'info, TokenAccount>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+/// Take the performance fee on gains above the high-water mark.
+///
+/// The fee is paid in newly minted shares, so no tokens leave the vault:
+/// the other holders are diluted by exactly the fee. The mark then moves to
+/// the share price after the fee, and a price still below it pays nothing.
+pub fn crystallize_fees(ctx: Context<CrystallizeFees>) -> Result<()> {
+    let vault = &mut ctx.accounts.vault;
+    require!(!vault.is_shutdown(), VaultError::VaultShutdown);
+
+    let previous_high_water_mark = vault.high_water_mark;
+    let (fee_assets, fee_shares) = vault.performance_fee()?;
+
+    if fee_shares > 0 {
+        let vault_id_bytes = vault.vault_id.to_le_bytes();
+        let seeds = &[b"vault".as_ref(), vault_id_bytes.as_ref(), &[vault.bump]];
+        let signer_seeds = &[&seeds[..]];
+        token::mint_to(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                MintTo {
+                    mint: ctx.accounts.share_mint.to_account_info(),
+                    to: ctx.accounts.fee_recipient_share_account.to_account_info(),
+                    authority: vault.to_account_info(),
+                },
+                signer_seeds,
+            ),
+            fee_shares,
+        )?;
+
+        vault.total_shares = vault
+            .total_shares
+            .checked_add(fee_shares)
+            .ok_or(VaultError::MathOverflow)?;
+    }
+
+    let share_price = vault.share_price()?;
+    vault.high_water_mark = vault.high_water_mark.max(share_price);
+
+    msg!(
+        "Performance fee: {} base tokens as {} shares to {}, high-water mark {} -> {}",
+        fee_assets,
+        fee_shares,
+        vault.fee_recipient,
+        previous_high_water_mark,
+        vault.high_water_mark
+    );
+
+    emit!(FeesCrystallized {
+        vault: vault.key(),
+        fee_recipient: vault.fee_recipient,
+        fee_assets,
+        fee_shares,
+        previous_high_water_mark,
+        high_water_mark: vault.high_water_mark,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+    Ok(())
+}
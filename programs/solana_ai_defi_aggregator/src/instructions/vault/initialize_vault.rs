@@ -1,5 +1,8 @@
 use crate::error::vault_error::VaultError;
-use crate::state::Vault;
+use crate::events::vault_events::VaultRegistered;
+use crate::state::{
+    Vault, VaultLifecycle, VaultRegistry, VaultRegistryEntry, VaultStatus, Versioned,
+};
 use anchor_lang::prelude::*;
 use anchor_spl::token::{Mint, Token, TokenAccount};
 
@@ -8,24 +11,46 @@ pub struct InitializeVault<'info> {
     #[account(mut)]
     pub authority: Signer<'info>,
 
+    /// Vault registry (assigns the vault id)
+    #[account(
+        mut,
+        seeds = [b"vault_registry"],
+        bump = registry.bump,
+    )]
+    pub registry: Account<'info, VaultRegistry>,
+
+    /// Registry entry listing the new vault
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + VaultRegistryEntry::INIT_SPACE,
+        seeds = [b"vault_registry_entry", registry.vault_count.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub registry_entry: Account<'info, VaultRegistryEntry>,
+
     /// The vault state PDA
     #[account(
         init,
         payer = authority,
         space = 8 + Vault::INIT_SPACE,
-        seeds = [b"vault", token_mint.key().as_ref()],
+        seeds = [b"vault", registry.vault_count.to_le_bytes().as_ref()],
         bump
     )]
     pub vault: Account<'info, Vault>,
 
     /// The underlying token mint (e.g, USDC)
+    #[account(
+        constraint = token_mint.decimals.checked_add(Vault::DECIMALS_OFFSET).is_some()
+            @ VaultError::InvalidMintDecimals,
+    )]
     pub token_mint: Account<'info, Mint>,
 
     /// Share token mint (created by vault)
     #[account(
         init,
         payer = authority,
-        mint::decimals = token_mint.decimals,
+        mint::decimals = token_mint.decimals.saturating_add(Vault::DECIMALS_OFFSET),
         mint::authority = vault,
         seeds = [b"share_mint", vault.key().as_ref()],
         bump
@@ -55,20 +80,58 @@ pub fn initialize_vault(ctx: Context<InitializeVault>, performance_fee_bps: u16)
     );
 
     let vault = &mut ctx.accounts.vault;
-    vault.authority = ctx.accounts.authority.key();
+    vault.manager = ctx.accounts.authority.key();
+    // Every role starts with the manager until it is rotated out
+    vault.keeper = vault.manager;
+    vault.guardian = vault.manager;
+    vault.fee_recipient = vault.manager;
     vault.token_mint = ctx.accounts.token_mint.key();
     vault.share_mint = ctx.accounts.share_mint.key();
     vault.total_assets = 0;
     vault.total_shares = 0;
     vault.bump = ctx.bumps.vault;
-    vault.strategy_enabled = false;
+    vault.status = VaultLifecycle::Paused;
     vault.performance_fee_bps = performance_fee_bps;
+    vault.deposit_cap = 0;
+    vault.user_deposit_cap = 0;
+    vault.min_deposit = 0;
+    vault.withdraw_epoch = 0;
+    vault.target_weights = Vec::new();
+    vault.rebalance_threshold_bps = 0;
+    vault.rebalance_max_slippage_bps = 0;
+    vault.adapter_assets = 0;
+    vault.version = Vault::CURRENT_VERSION;
+    vault.high_water_mark = vault.share_price()?;
+
+    let registry = &mut ctx.accounts.registry;
+    vault.vault_id = registry.vault_count;
+    registry.vault_count = registry
+        .vault_count
+        .checked_add(1)
+        .ok_or(VaultError::MathOverflow)?;
+
+    let entry = &mut ctx.accounts.registry_entry;
+    entry.vault = vault.key();
+    entry.vault_id = vault.vault_id;
+    entry.token_mint = vault.token_mint;
+    entry.manager = vault.manager;
+    entry.status = VaultStatus::Active;
+    entry.bump = ctx.bumps.registry_entry;
 
     msg!(
         "Vault initialized for mint: {}",
         ctx.accounts.token_mint.key()
     );
+    msg!("Vault id: {}", vault.vault_id);
     msg!("Performance fee: {} bps", performance_fee_bps);
 
+    emit!(VaultRegistered {
+        vault: vault.key(),
+        vault_id: vault.vault_id,
+        token_mint: vault.token_mint,
+        manager: vault.manager,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
     Ok(())
 }
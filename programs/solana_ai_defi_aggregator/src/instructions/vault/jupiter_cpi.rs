@@ -0,0 +1,100 @@
+use crate::error::vault_error::VaultError;
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::instruction::{AccountMeta, Instruction as SolInstruction};
+use anchor_lang::solana_program::program::invoke_signed;
+
+/// Build the client-provided Jupiter instruction and invoke it with the vault signing.
+///
+/// `accounts` are the accounts Jupiter expects, forwarded as-is. In test mode the
+/// CPI is skipped and the test mints the swap output itself.
+pub fn invoke_jupiter_swap(
+    jupiter_program: Pubkey,
+    swap_ix_data: Vec<u8>,
+    accounts_meta: Vec<Pubkey>,
+    accounts: &[AccountInfo],
+    signer_seeds: &[&[&[u8]]],
+) -> Result<()> {
+    // Convert Pubkeys to AccountMeta
+    let metas: Vec<AccountMeta> = accounts_meta
+        .into_iter()
+        .map(|pubkey| AccountMeta::new(pubkey, false))
+        .collect();
+
+    // Build Jupiter instruction
+    let jupiter_ix = SolInstruction {
+        program_id: jupiter_program,
+        accounts: metas,
+        data: swap_ix_data,
+    };
+
+    msg!("Invoking Jupiter CPI...");
+
+    #[cfg(not(feature = "test"))]
+    {
+        invoke_signed(&jupiter_ix, accounts, signer_seeds)?;
+        msg!("Jupiter CPI completed successfully");
+    }
+
+    #[cfg(feature = "test")]
+    {
+        msg!(
+            "TEST MODE: Skipping actual Jupiter CPI ({} accounts, {} forwarded, {} signer seeds)",
+            jupiter_ix.accounts.len(),
+            accounts.len(),
+            signer_seeds.len()
+        );
+        msg!("TEST MODE: Swap simulation complete");
+    }
+
+    Ok(())
+}
+
+/// Invoke a client-provided Jupiter route with the vault as its transfer
+/// authority.
+///
+/// `accounts_meta` lists the route's accounts in order. The vault signs
+/// wherever it appears; every other account has to be among `accounts` and
+/// is forwarded with the access it was passed with. Unlike
+/// `invoke_jupiter_swap` the route also runs in test mode, where tests route
+/// through mock_swap, so callers always measure the fill from balances.
+pub fn invoke_jupiter_route<'info>(
+    jupiter_program: Pubkey,
+    swap_ix_data: Vec<u8>,
+    accounts_meta: Vec<Pubkey>,
+    vault: &AccountInfo<'info>,
+    accounts: &[AccountInfo<'info>],
+    signer_seeds: &[&[&[u8]]],
+) -> Result<()> {
+    let metas = accounts_meta
+        .into_iter()
+        .map(|pubkey| {
+            if pubkey == vault.key() {
+                return Ok(AccountMeta::new_readonly(pubkey, true));
+            }
+            let info = accounts
+                .iter()
+                .find(|info| info.key() == pubkey)
+                .ok_or(VaultError::InvalidRemainingAccounts)?;
+            Ok(if info.is_writable {
+                AccountMeta::new(pubkey, false)
+            } else {
+                AccountMeta::new_readonly(pubkey, false)
+            })
+        })
+        .collect::<Result<Vec<AccountMeta>>>()?;
+
+    let jupiter_ix = SolInstruction {
+        program_id: jupiter_program,
+        accounts: metas,
+        data: swap_ix_data,
+    };
+
+    let mut infos = accounts.to_vec();
+    infos.push(vault.clone());
+
+    msg!("Invoking Jupiter CPI...");
+    invoke_signed(&jupiter_ix, &infos, signer_seeds)?;
+    msg!("Jupiter CPI completed successfully");
+
+    Ok(())
+}
@@ -0,0 +1,118 @@
+use crate::error::vault_error::VaultError;
+use crate::events::vault_events::LockedSharesReleased;
+use crate::state::{DepositorPosition, Vault};
+use anchor_lang::prelude::*;
+use anchor_spl::token::{self, Mint, Token, TokenAccount};
+
+#[derive(Accounts)]
+pub struct ReleaseLockedShares<'info> {
+    pub owner: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"vault", vault.vault_id.to_le_bytes().as_ref()],
+        bump = vault.bump
+    )]
+    pub vault: Account<'info, Vault>,
+
+    #[account(
+        mut,
+        seeds = [b"position", vault.key().as_ref(), owner.key().as_ref()],
+        bump = position.bump,
+    )]
+    pub position: Account<'info, DepositorPosition>,
+
+    /// Escrow the position's locked shares were minted into
+    #[account(
+        mut,
+        seeds = [b"locked_shares", position.key().as_ref()],
+        bump,
+    )]
+    pub locked_share_account: Account<'info, TokenAccount>,
+
+    /// Owner's share token account (receives the released shares)
+    #[account(
+        mut,
+        constraint = owner_share_account.mint == vault.share_mint @ VaultError::MintMismatch,
+        constraint = owner_share_account.owner == owner.key() @ VaultError::InvalidOwner,
+    )]
+    pub owner_share_account: Account<'info, TokenAccount>,
+
+    /// Mutable: early-release penalty shares are burned
+    #[account(
+        mut,
+        constraint = share_mint.key() == vault.share_mint @ VaultError::MintMismatch,
+    )]
+    pub share_mint: Account<'info, Mint>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+/// Move the position's escrowed shares to the owner's wallet. The early-exit
+/// penalty is burned out of the shares whose lock has not expired, so its
+/// value stays with the remaining holders; the rest (or everything once the
+/// vault is shut down) is released in full.
+pub fn release_locked_shares(ctx: Context<ReleaseLockedShares>) -> Result<()> {
+    let locked = ctx.accounts.locked_share_account.amount;
+    require!(locked > 0, VaultError::ZeroShares);
+
+    let vault = &mut ctx.accounts.vault;
+    let now = Clock::get()?.unix_timestamp;
+    let penalty_bps = vault.exit_penalty_bps(&ctx.accounts.position, now);
+    let still_locked = locked.min(ctx.accounts.position.locked_shares(now));
+    let penalty_shares = Vault::exit_penalty(still_locked, penalty_bps)?;
+    let released = locked - penalty_shares;
+
+    let vault_id_bytes = vault.vault_id.to_le_bytes();
+    let seeds = &[b"vault".as_ref(), vault_id_bytes.as_ref(), &[vault.bump]];
+    let signer_seeds = &[&seeds[..]];
+
+    if penalty_shares > 0 {
+        token::burn(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                token::Burn {
+                    mint: ctx.accounts.share_mint.to_account_info(),
+                    from: ctx.accounts.locked_share_account.to_account_info(),
+                    authority: vault.to_account_info(),
+                },
+                signer_seeds,
+            ),
+            penalty_shares,
+        )?;
+        vault.total_shares = vault
+            .total_shares
+            .checked_sub(penalty_shares)
+            .ok_or(VaultError::MathOverflow)?;
+        msg!("Early-release penalty: burned {} shares", penalty_shares);
+    }
+
+    if released > 0 {
+        token::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                token::Transfer {
+                    from: ctx.accounts.locked_share_account.to_account_info(),
+                    to: ctx.accounts.owner_share_account.to_account_info(),
+                    authority: vault.to_account_info(),
+                },
+                signer_seeds,
+            ),
+            released,
+        )?;
+    }
+
+    msg!("Released {} locked shares", released);
+
+    let position = &mut ctx.accounts.position;
+    emit!(LockedSharesReleased {
+        vault: vault.key(),
+        owner: ctx.accounts.owner.key(),
+        shares: released,
+        penalty_shares,
+        lock_until: position.lock_until,
+        timestamp: now,
+    });
+    position.clear_locks();
+    Ok(())
+}
@@ -0,0 +1,46 @@
+use crate::events::vault_events::{LossSource, VaultLossRecognized};
+use crate::state::Vault;
+use anchor_lang::prelude::*;
+
+/// NAV and share price taken before an operation that re-values the vault
+pub(crate) struct NavMark {
+    nav: u64,
+    share_price: u64,
+}
+
+impl NavMark {
+    pub(crate) fn of(vault: &Vault) -> Result<Self> {
+        Ok(Self {
+            nav: vault.nav(),
+            share_price: vault.share_price()?,
+        })
+    }
+
+    /// Emit `VaultLossRecognized` if the share price fell since the mark
+    pub(crate) fn recognize_loss(&self, vault: &Account<Vault>, source: LossSource) -> Result<()> {
+        let share_price = vault.share_price()?;
+        if share_price >= self.share_price {
+            return Ok(());
+        }
+
+        let loss = self.nav.saturating_sub(vault.nav());
+        msg!(
+            "Loss recognized ({:?}): {} base tokens, share price {} -> {}",
+            source,
+            loss,
+            self.share_price,
+            share_price
+        );
+
+        emit!(VaultLossRecognized {
+            vault: vault.key(),
+            source,
+            loss,
+            share_price_before: self.share_price,
+            share_price_after: share_price,
+            cumulative_pnl: vault.cumulative_pnl,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        Ok(())
+    }
+}
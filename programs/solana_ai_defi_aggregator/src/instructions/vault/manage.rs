@@ -1,26 +1,228 @@
 use crate::error::vault_error::VaultError;
-use crate::state::vault::Vault;
+use crate::events::vault_events::{
+    DepositGateUpdated, DepositLimitsUpdated, FeeRecipientUpdated, LockupUpdated,
+    MerkleRootUpdated, PerformanceFeeUpdated, VaultStatusUpdated,
+};
+use crate::state::vault::{DepositGate, Vault, VaultLifecycle, MAX_EARLY_EXIT_PENALTY_BPS};
 use anchor_lang::prelude::*;
 
 #[derive(Accounts)]
-pub struct SetStrategyEnabled<'info> {
+pub struct SetVaultStatus<'info> {
+    /// Manager, or guardian when pausing
     #[account(
-        constraint = authority.key() == vault.authority @ VaultError::Unauthorized
+        constraint = vault.can_pause(&authority.key()) @ VaultError::Unauthorized
     )]
     pub authority: Signer<'info>,
 
     #[account(
         mut,
-        seeds = [b"vault", vault.token_mint.as_ref()],
+        seeds = [b"vault", vault.vault_id.to_le_bytes().as_ref()],
         bump = vault.bump,
     )]
     pub vault: Account<'info, Vault>,
 }
 
-pub fn set_strategy_enabled(ctx: Context<SetStrategyEnabled>, enabled: bool) -> Result<()> {
+/// Switch between Active and Paused. Shutdown goes through `emergency_shutdown`
+/// and is final.
+pub fn set_vault_status(ctx: Context<SetVaultStatus>, status: VaultLifecycle) -> Result<()> {
     let vault = &mut ctx.accounts.vault;
-    vault.strategy_enabled = enabled;
+    require!(!vault.is_shutdown(), VaultError::VaultShutdown);
+    require!(
+        status != VaultLifecycle::Shutdown,
+        VaultError::InvalidVaultStatus
+    );
 
-    msg!("Strategy enabled: {}", enabled);
+    // The guardian can only pull the brake, never release it
+    if status == VaultLifecycle::Active {
+        require_keys_eq!(
+            ctx.accounts.authority.key(),
+            vault.manager,
+            VaultError::Unauthorized
+        );
+    }
+
+    let old_status = vault.status;
+    vault.status = status;
+
+    msg!("Vault status: {:?} -> {:?}", old_status, status);
+
+    emit!(VaultStatusUpdated {
+        vault: vault.key(),
+        old_status,
+        new_status: status,
+        updated_by: ctx.accounts.authority.key(),
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct SetDepositLimits<'info> {
+    #[account(
+        constraint = authority.key() == vault.manager @ VaultError::Unauthorized
+    )]
+    pub authority: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"vault", vault.vault_id.to_le_bytes().as_ref()],
+        bump = vault.bump,
+    )]
+    pub vault: Account<'info, Vault>,
+}
+
+/// Configure the total deposit cap, per-depositor cap and minimum deposit.
+/// A value of 0 disables the corresponding limit.
+pub fn set_deposit_limits(
+    ctx: Context<SetDepositLimits>,
+    deposit_cap: u64,
+    user_deposit_cap: u64,
+    min_deposit: u64,
+) -> Result<()> {
+    let vault = &mut ctx.accounts.vault;
+    vault.deposit_cap = deposit_cap;
+    vault.user_deposit_cap = user_deposit_cap;
+    vault.min_deposit = min_deposit;
+
+    msg!(
+        "Deposit limits updated: cap={}, user_cap={}, min={}",
+        deposit_cap,
+        user_deposit_cap,
+        min_deposit
+    );
+
+    emit!(DepositLimitsUpdated {
+        vault: vault.key(),
+        deposit_cap,
+        user_deposit_cap,
+        min_deposit,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+    Ok(())
+}
+
+/// Configure the deposit lockup. While a lock period is set, deposits mint
+/// their shares into the position's vault-owned escrow, so they cannot be
+/// transferred or redeemed until released. Releasing shares within
+/// `lock_period` seconds of the deposit that minted them burns
+/// `early_exit_penalty_bps` of them, leaving their value with the other
+/// holders. A lock period of 0 disables the lockup for new deposits.
+pub fn set_lockup(
+    ctx: Context<SetDepositLimits>,
+    lock_period: i64,
+    early_exit_penalty_bps: u16,
+) -> Result<()> {
+    require!(
+        lock_period >= 0 && early_exit_penalty_bps <= MAX_EARLY_EXIT_PENALTY_BPS,
+        VaultError::InvalidLockup
+    );
+
+    let vault = &mut ctx.accounts.vault;
+    vault.lock_period = lock_period;
+    vault.early_exit_penalty_bps = early_exit_penalty_bps;
+
+    msg!(
+        "Lockup updated: period={}s, penalty={}bps",
+        lock_period,
+        early_exit_penalty_bps
+    );
+
+    emit!(LockupUpdated {
+        vault: vault.key(),
+        lock_period,
+        early_exit_penalty_bps,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+    Ok(())
+}
+
+/// Choose who may deposit. Switching gates keeps the allowlist PDAs and
+/// the merkle root, so switching back needs no re-approval.
+pub fn set_deposit_gate(ctx: Context<SetDepositLimits>, deposit_gate: DepositGate) -> Result<()> {
+    let vault = &mut ctx.accounts.vault;
+    vault.deposit_gate = deposit_gate;
+
+    msg!("Deposit gate: {:?}", deposit_gate);
+
+    emit!(DepositGateUpdated {
+        vault: vault.key(),
+        deposit_gate,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+    Ok(())
+}
+
+/// Replace the root of the approved-depositor merkle tree.
+/// Leaves are sha256(depositor pubkey), pairs hashed in sorted order.
+pub fn set_merkle_root(ctx: Context<SetDepositLimits>, merkle_root: [u8; 32]) -> Result<()> {
+    let vault = &mut ctx.accounts.vault;
+    let old_root = vault.merkle_root;
+    vault.merkle_root = merkle_root;
+
+    msg!("Deposit merkle root updated");
+
+    emit!(MerkleRootUpdated {
+        vault: vault.key(),
+        old_root,
+        new_root: merkle_root,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct SetVaultFees<'info> {
+    /// Only the manager touches fees; keeper keys never can
+    #[account(
+        constraint = manager.key() == vault.manager @ VaultError::Unauthorized
+    )]
+    pub manager: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"vault", vault.vault_id.to_le_bytes().as_ref()],
+        bump = vault.bump,
+    )]
+    pub vault: Account<'info, Vault>,
+}
+
+pub fn set_performance_fee(ctx: Context<SetVaultFees>, performance_fee_bps: u16) -> Result<()> {
+    require!(
+        performance_fee_bps <= 5000, // Max 50%
+        VaultError::InvalidFee
+    );
+
+    let vault = &mut ctx.accounts.vault;
+    let old_fee_bps = vault.performance_fee_bps;
+    vault.performance_fee_bps = performance_fee_bps;
+
+    msg!(
+        "Performance fee: {} -> {} bps",
+        old_fee_bps,
+        performance_fee_bps
+    );
+
+    emit!(PerformanceFeeUpdated {
+        vault: vault.key(),
+        old_fee_bps,
+        new_fee_bps: performance_fee_bps,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+    Ok(())
+}
+
+pub fn set_fee_recipient(ctx: Context<SetVaultFees>, fee_recipient: Pubkey) -> Result<()> {
+    let vault = &mut ctx.accounts.vault;
+    let old_recipient = vault.fee_recipient;
+    vault.fee_recipient = fee_recipient;
+
+    msg!("Fee recipient: {} -> {}", old_recipient, fee_recipient);
+
+    emit!(FeeRecipientUpdated {
+        vault: vault.key(),
+        old_recipient,
+        new_recipient: fee_recipient,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
     Ok(())
 }
@@ -1,15 +1,50 @@
 use anchor_lang::prelude::*;
+pub mod allowlist;
+pub mod child_vaults;
+pub mod close_vault;
 pub mod deposit;
 pub mod execute_strategy;
+pub mod fees;
 pub mod initialize_vault;
+pub mod jupiter_cpi;
+pub mod lockup;
+pub mod loss;
 pub mod manage;
+pub mod nav_history;
+pub mod queued_withdraw;
+pub mod rebalance;
+pub mod registry;
+pub mod risk;
+pub mod roles;
+pub mod shutdown;
+pub mod sync;
+pub mod views;
 pub mod withdraw;
+pub mod withdraw_in_kind;
+pub mod yield_adapter;
 
+pub use allowlist::*;
+pub use child_vaults::*;
+pub use close_vault::*;
 pub use deposit::*;
 pub use execute_strategy::*;
+pub use fees::*;
 pub use initialize_vault::*;
+pub use jupiter_cpi::*;
+pub use lockup::*;
 pub use manage::*;
+pub use nav_history::*;
+pub use queued_withdraw::*;
+pub use rebalance::*;
+pub use registry::*;
+pub use risk::*;
+pub use roles::*;
+pub use shutdown::*;
+pub use sync::*;
+pub use views::*;
 pub use withdraw::*;
+pub use withdraw_in_kind::*;
+pub use yield_adapter::*;
 
 // Re-export strategy type
 
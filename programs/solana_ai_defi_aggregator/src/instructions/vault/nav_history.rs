@@ -0,0 +1,97 @@
+use crate::error::vault_error::VaultError;
+use crate::events::vault_events::{NavHistoryConfigured, NavSnapshotRecorded};
+use crate::state::{NavHistory, NavSnapshot, Vault};
+use anchor_lang::prelude::*;
+
+#[derive(Accounts)]
+pub struct ConfigureNavHistory<'info> {
+    #[account(
+        mut,
+        constraint = manager.key() == vault.manager @ VaultError::Unauthorized
+    )]
+    pub manager: Signer<'info>,
+
+    #[account(
+        seeds = [b"vault", vault.vault_id.to_le_bytes().as_ref()],
+        bump = vault.bump,
+    )]
+    pub vault: Account<'info, Vault>,
+
+    #[account(
+        init_if_needed,
+        payer = manager,
+        space = 8 + NavHistory::INIT_SPACE,
+        seeds = [b"nav_history", vault.key().as_ref()],
+        bump
+    )]
+    pub nav_history: Box<Account<'info, NavHistory>>,
+
+    pub system_program: Program<'info, System>,
+}
+
+/// Create the vault's NAV history or change its snapshot interval.
+/// Existing snapshots are kept.
+pub fn configure_nav_history(ctx: Context<ConfigureNavHistory>, interval_secs: i64) -> Result<()> {
+    require!(interval_secs > 0, VaultError::InvalidSnapshotInterval);
+
+    let nav_history = &mut ctx.accounts.nav_history;
+    nav_history.vault = ctx.accounts.vault.key();
+    nav_history.interval_secs = interval_secs;
+    nav_history.bump = ctx.bumps.nav_history;
+
+    msg!("NAV snapshots every {}s", interval_secs);
+
+    emit!(NavHistoryConfigured {
+        vault: nav_history.vault,
+        interval_secs,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct RecordNavSnapshot<'info> {
+    #[account(
+        seeds = [b"vault", vault.vault_id.to_le_bytes().as_ref()],
+        bump = vault.bump,
+    )]
+    pub vault: Account<'info, Vault>,
+
+    #[account(
+        mut,
+        seeds = [b"nav_history", vault.key().as_ref()],
+        bump = nav_history.bump,
+        has_one = vault,
+    )]
+    pub nav_history: Box<Account<'info, NavHistory>>,
+}
+
+/// Crank: append the vault's current share price and NAV. Permissionless,
+/// at most once per configured interval.
+pub fn record_nav_snapshot(ctx: Context<RecordNavSnapshot>) -> Result<()> {
+    let now = Clock::get()?.unix_timestamp;
+    let nav_history = &mut ctx.accounts.nav_history;
+    require!(nav_history.is_due(now), VaultError::SnapshotNotDue);
+
+    let vault = &ctx.accounts.vault;
+    let snapshot = NavSnapshot {
+        timestamp: now,
+        share_price: vault.share_price()?,
+        total_assets: vault.nav(),
+    };
+    nav_history.push(snapshot);
+
+    msg!(
+        "NAV snapshot: share price {}, total assets {}",
+        snapshot.share_price,
+        snapshot.total_assets
+    );
+
+    emit!(NavSnapshotRecorded {
+        vault: vault.key(),
+        share_price: snapshot.share_price,
+        total_assets: snapshot.total_assets,
+        timestamp: now,
+    });
+    Ok(())
+}
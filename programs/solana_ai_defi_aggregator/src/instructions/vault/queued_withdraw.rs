@@ -0,0 +1,528 @@
+use crate::error::vault_error::VaultError;
+use crate::events::vault_events::{
+    WithdrawEpochSettledEvent, WithdrawRequestCancelledEvent, WithdrawRequestedEvent,
+    WithdrawalClaimedEvent,
+};
+use crate::state::{DepositorPosition, Vault, WithdrawEpoch, WithdrawalRequest};
+use anchor_lang::prelude::*;
+use anchor_spl::token::{self, Mint, Token, TokenAccount};
+
+// ===========================================
+// Queued withdrawals
+// ===========================================
+//
+// When the strategy has deployed the base token, `withdraw` cannot be served
+// instantly. Users lock their shares in escrow and join the open epoch; the
+// keeper frees up liquidity and settles the whole epoch at one share price;
+// users then claim their tokens.
+//
+// A shutdown closes the queue: an unsettled epoch may never find the base
+// liquidity to settle, so its requests can be cancelled and the shares
+// taken out in kind instead.
+
+#[derive(Accounts)]
+pub struct RequestWithdraw<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"vault", vault.vault_id.to_le_bytes().as_ref()],
+        bump = vault.bump
+    )]
+    pub vault: Account<'info, Vault>,
+
+    /// User's share token account (shares to lock)
+    #[account(
+        mut,
+        constraint = user_share_account.mint == vault.share_mint @ VaultError::MintMismatch,
+        constraint = user_share_account.owner == user.key() @ VaultError::InvalidOwner,
+    )]
+    pub user_share_account: Box<Account<'info, TokenAccount>>,
+
+    /// Vault-owned escrow holding queued shares until settlement
+    #[account(
+        init_if_needed,
+        payer = user,
+        seeds = [b"withdraw_escrow", vault.key().as_ref()],
+        bump,
+        token::mint = share_mint,
+        token::authority = vault,
+    )]
+    pub share_escrow: Box<Account<'info, TokenAccount>>,
+
+    #[account(
+        constraint = share_mint.key() == vault.share_mint @ VaultError::MintMismatch,
+    )]
+    pub share_mint: Box<Account<'info, Mint>>,
+
+    /// The open withdrawal epoch
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = 8 + WithdrawEpoch::INIT_SPACE,
+        seeds = [b"withdraw_epoch", vault.key().as_ref(), vault.withdraw_epoch.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub withdraw_epoch: Box<Account<'info, WithdrawEpoch>>,
+
+    /// User's request in the open epoch
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = 8 + WithdrawalRequest::INIT_SPACE,
+        seeds = [
+            b"withdrawal_request",
+            vault.key().as_ref(),
+            user.key().as_ref(),
+            vault.withdraw_epoch.to_le_bytes().as_ref()
+        ],
+        bump
+    )]
+    pub withdrawal_request: Box<Account<'info, WithdrawalRequest>>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+/// Queue `shares` for the open epoch
+pub fn request_withdraw(ctx: Context<RequestWithdraw>, shares: u64) -> Result<()> {
+    require!(shares > 0, VaultError::ZeroShares);
+
+    let vault = &mut ctx.accounts.vault;
+    require!(!vault.is_shutdown(), VaultError::WithdrawInKindOnly);
+    let epoch = vault.withdraw_epoch;
+
+    // Lock shares in escrow
+    token::transfer(
+        CpiContext::new(
+            ctx.accounts.token_program.to_account_info(),
+            token::Transfer {
+                from: ctx.accounts.user_share_account.to_account_info(),
+                to: ctx.accounts.share_escrow.to_account_info(),
+                authority: ctx.accounts.user.to_account_info(),
+            },
+        ),
+        shares,
+    )?;
+
+    let withdraw_epoch = &mut ctx.accounts.withdraw_epoch;
+    if withdraw_epoch.vault == Pubkey::default() {
+        withdraw_epoch.vault = vault.key();
+        withdraw_epoch.epoch = epoch;
+        withdraw_epoch.bump = ctx.bumps.withdraw_epoch;
+    }
+    withdraw_epoch.total_shares = withdraw_epoch
+        .total_shares
+        .checked_add(shares)
+        .ok_or(VaultError::MathOverflow)?;
+
+    let request = &mut ctx.accounts.withdrawal_request;
+    if request.owner == Pubkey::default() {
+        request.vault = vault.key();
+        request.owner = ctx.accounts.user.key();
+        request.epoch = epoch;
+        request.bump = ctx.bumps.withdrawal_request;
+    }
+    request.shares = request
+        .shares
+        .checked_add(shares)
+        .ok_or(VaultError::MathOverflow)?;
+
+    msg!("Queued {} shares for withdrawal epoch {}", shares, epoch);
+
+    emit!(WithdrawRequestedEvent {
+        vault: vault.key(),
+        user: ctx.accounts.user.key(),
+        epoch,
+        shares,
+        epoch_shares: withdraw_epoch.total_shares,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct SettleWithdrawEpoch<'info> {
+    #[account(
+        mut,
+        constraint = vault.is_operator(&authority.key()) @ VaultError::Unauthorized
+    )]
+    pub authority: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"vault", vault.vault_id.to_le_bytes().as_ref()],
+        bump = vault.bump
+    )]
+    pub vault: Account<'info, Vault>,
+
+    #[account(
+        mut,
+        seeds = [b"withdraw_epoch", vault.key().as_ref(), vault.withdraw_epoch.to_le_bytes().as_ref()],
+        bump = withdraw_epoch.bump
+    )]
+    pub withdraw_epoch: Box<Account<'info, WithdrawEpoch>>,
+
+    /// Vault's token account (source of the payout)
+    #[account(
+        mut,
+        seeds = [b"vault_token_account", vault.key().as_ref()],
+        bump,
+        constraint = vault_token_account.mint == vault.token_mint @ VaultError::MintMismatch,
+    )]
+    pub vault_token_account: Box<Account<'info, TokenAccount>>,
+
+    /// Escrow of queued shares (burned at settlement)
+    #[account(
+        mut,
+        seeds = [b"withdraw_escrow", vault.key().as_ref()],
+        bump,
+    )]
+    pub share_escrow: Box<Account<'info, TokenAccount>>,
+
+    /// Vault-owned account holding settled tokens until they are claimed
+    #[account(
+        init_if_needed,
+        payer = authority,
+        seeds = [b"claim_escrow", vault.key().as_ref()],
+        bump,
+        token::mint = token_mint,
+        token::authority = vault,
+    )]
+    pub claim_escrow: Box<Account<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        constraint = share_mint.key() == vault.share_mint @ VaultError::MintMismatch,
+    )]
+    pub share_mint: Box<Account<'info, Mint>>,
+
+    #[account(
+        constraint = token_mint.key() == vault.token_mint @ VaultError::MintMismatch,
+    )]
+    pub token_mint: Box<Account<'info, Mint>>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+/// Settle the open epoch: every queued share is redeemed at the same price
+pub fn settle_withdraw_epoch(ctx: Context<SettleWithdrawEpoch>) -> Result<()> {
+    let vault = &mut ctx.accounts.vault;
+    let withdraw_epoch = &mut ctx.accounts.withdraw_epoch;
+
+    let shares = withdraw_epoch.total_shares;
+    require!(shares > 0, VaultError::EmptyWithdrawEpoch);
+
+    // One share price for the whole epoch (rounded down, in favor of the vault)
+    let share_price = vault.share_price()?;
+    let assets = vault.preview_redeem(shares)?;
+
+    // The keeper must have freed up enough liquidity first
+    require!(
+        vault.total_assets >= assets && ctx.accounts.vault_token_account.amount >= assets,
+        VaultError::InsufficientAssets
+    );
+
+    msg!("Settling epoch {}", withdraw_epoch.epoch);
+    msg!("Burning {} queued shares for {} tokens", shares, assets);
+
+    let vault_id_bytes = vault.vault_id.to_le_bytes();
+    let seeds = &[b"vault".as_ref(), vault_id_bytes.as_ref(), &[vault.bump]];
+    let signer_seeds = &[&seeds[..]];
+
+    token::burn(
+        CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            token::Burn {
+                mint: ctx.accounts.share_mint.to_account_info(),
+                from: ctx.accounts.share_escrow.to_account_info(),
+                authority: vault.to_account_info(),
+            },
+            signer_seeds,
+        ),
+        shares,
+    )?;
+
+    token::transfer(
+        CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            token::Transfer {
+                from: ctx.accounts.vault_token_account.to_account_info(),
+                to: ctx.accounts.claim_escrow.to_account_info(),
+                authority: vault.to_account_info(),
+            },
+            signer_seeds,
+        ),
+        assets,
+    )?;
+
+    vault.total_assets = vault
+        .total_assets
+        .checked_sub(assets)
+        .ok_or(VaultError::MathOverflow)?;
+    vault.total_shares = vault
+        .total_shares
+        .checked_sub(shares)
+        .ok_or(VaultError::MathOverflow)?;
+    vault.withdraw_epoch = vault
+        .withdraw_epoch
+        .checked_add(1)
+        .ok_or(VaultError::MathOverflow)?;
+    vault.pending_claims = vault
+        .pending_claims
+        .checked_add(assets)
+        .ok_or(VaultError::MathOverflow)?;
+
+    let clock = Clock::get()?;
+    withdraw_epoch.total_assets = assets;
+    withdraw_epoch.settled = true;
+    withdraw_epoch.settled_at = clock.unix_timestamp;
+
+    emit!(WithdrawEpochSettledEvent {
+        vault: vault.key(),
+        epoch: withdraw_epoch.epoch,
+        shares,
+        assets,
+        share_price,
+        timestamp: clock.unix_timestamp,
+    });
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct CancelWithdrawRequest<'info> {
+    /// Anyone may crank the refund once the vault is shut down
+    pub authority: Signer<'info>,
+
+    #[account(
+        seeds = [b"vault", vault.vault_id.to_le_bytes().as_ref()],
+        bump = vault.bump
+    )]
+    pub vault: Account<'info, Vault>,
+
+    #[account(
+        mut,
+        seeds = [
+            b"withdraw_epoch",
+            vault.key().as_ref(),
+            withdrawal_request.epoch.to_le_bytes().as_ref()
+        ],
+        bump = withdraw_epoch.bump,
+        constraint = !withdraw_epoch.settled @ VaultError::EpochAlreadySettled,
+    )]
+    pub withdraw_epoch: Box<Account<'info, WithdrawEpoch>>,
+
+    #[account(
+        mut,
+        close = owner,
+        seeds = [
+            b"withdrawal_request",
+            vault.key().as_ref(),
+            withdrawal_request.owner.as_ref(),
+            withdrawal_request.epoch.to_le_bytes().as_ref()
+        ],
+        bump = withdrawal_request.bump,
+    )]
+    pub withdrawal_request: Box<Account<'info, WithdrawalRequest>>,
+
+    /// CHECK: receives the request's rent
+    #[account(mut, address = withdrawal_request.owner @ VaultError::InvalidOwner)]
+    pub owner: UncheckedAccount<'info>,
+
+    /// Escrow of queued shares
+    #[account(
+        mut,
+        seeds = [b"withdraw_escrow", vault.key().as_ref()],
+        bump,
+    )]
+    pub share_escrow: Box<Account<'info, TokenAccount>>,
+
+    /// Owner's share account (receives the escrowed shares)
+    #[account(
+        mut,
+        constraint = owner_share_account.mint == vault.share_mint @ VaultError::MintMismatch,
+        constraint = owner_share_account.owner == withdrawal_request.owner @ VaultError::InvalidOwner,
+    )]
+    pub owner_share_account: Box<Account<'info, TokenAccount>>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+/// Return the escrowed shares of an unsettled request after a shutdown, so
+/// the owner can exit through `withdraw_in_kind`
+pub fn cancel_withdraw_request(ctx: Context<CancelWithdrawRequest>) -> Result<()> {
+    let vault = &ctx.accounts.vault;
+    require!(vault.is_shutdown(), VaultError::VaultNotShutdown);
+
+    let request = &ctx.accounts.withdrawal_request;
+    let shares = request.shares;
+
+    let vault_id_bytes = vault.vault_id.to_le_bytes();
+    let seeds = &[b"vault".as_ref(), vault_id_bytes.as_ref(), &[vault.bump]];
+    let signer_seeds = &[&seeds[..]];
+
+    token::transfer(
+        CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            token::Transfer {
+                from: ctx.accounts.share_escrow.to_account_info(),
+                to: ctx.accounts.owner_share_account.to_account_info(),
+                authority: vault.to_account_info(),
+            },
+            signer_seeds,
+        ),
+        shares,
+    )?;
+
+    let withdraw_epoch = &mut ctx.accounts.withdraw_epoch;
+    withdraw_epoch.total_shares = withdraw_epoch
+        .total_shares
+        .checked_sub(shares)
+        .ok_or(VaultError::MathOverflow)?;
+
+    msg!(
+        "Cancelled request of {} for {} shares (epoch {})",
+        request.owner,
+        shares,
+        request.epoch
+    );
+
+    emit!(WithdrawRequestCancelledEvent {
+        vault: vault.key(),
+        user: request.owner,
+        epoch: request.epoch,
+        shares,
+        epoch_shares: withdraw_epoch.total_shares,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+    Ok(())
+}
+
+#[derive(Accounts)]
+#[instruction(epoch: u64)]
+pub struct ClaimWithdrawal<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"vault", vault.vault_id.to_le_bytes().as_ref()],
+        bump = vault.bump
+    )]
+    pub vault: Account<'info, Vault>,
+
+    #[account(
+        mut,
+        seeds = [b"withdraw_epoch", vault.key().as_ref(), epoch.to_le_bytes().as_ref()],
+        bump = withdraw_epoch.bump,
+        constraint = withdraw_epoch.settled @ VaultError::EpochNotSettled,
+    )]
+    pub withdraw_epoch: Box<Account<'info, WithdrawEpoch>>,
+
+    #[account(
+        mut,
+        close = user,
+        seeds = [
+            b"withdrawal_request",
+            vault.key().as_ref(),
+            user.key().as_ref(),
+            epoch.to_le_bytes().as_ref()
+        ],
+        bump = withdrawal_request.bump,
+    )]
+    pub withdrawal_request: Box<Account<'info, WithdrawalRequest>>,
+
+    /// Escrow holding settled tokens
+    #[account(
+        mut,
+        seeds = [b"claim_escrow", vault.key().as_ref()],
+        bump,
+    )]
+    pub claim_escrow: Box<Account<'info, TokenAccount>>,
+
+    /// User's token account (receives the claim)
+    #[account(
+        mut,
+        constraint = user_token_account.mint == vault.token_mint @ VaultError::MintMismatch,
+        constraint = user_token_account.owner == user.key() @ VaultError::InvalidOwner,
+    )]
+    pub user_token_account: Box<Account<'info, TokenAccount>>,
+
+    /// Depositor position (net deposits shrink on withdrawal)
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = 8 + DepositorPosition::INIT_SPACE,
+        seeds = [b"position", vault.key().as_ref(), user.key().as_ref()],
+        bump
+    )]
+    pub position: Box<Account<'info, DepositorPosition>>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+pub fn claim_withdrawal(ctx: Context<ClaimWithdrawal>, epoch: u64) -> Result<()> {
+    let vault = &mut ctx.accounts.vault;
+    let shares = ctx.accounts.withdrawal_request.shares;
+
+    // Pro-rata share of what the epoch still holds (rounded down). The claim
+    // leaves the epoch with it, so the last claimant takes the remainder and
+    // no rounding dust is left in the escrow.
+    let withdraw_epoch = &mut ctx.accounts.withdraw_epoch;
+    let amount = withdraw_epoch
+        .assets_for(shares)
+        .ok_or(VaultError::MathOverflow)?;
+    withdraw_epoch.total_shares = withdraw_epoch
+        .total_shares
+        .checked_sub(shares)
+        .ok_or(VaultError::MathOverflow)?;
+    withdraw_epoch.total_assets = withdraw_epoch
+        .total_assets
+        .checked_sub(amount)
+        .ok_or(VaultError::MathOverflow)?;
+    // Saturating: a vault migrated to v8 booked its escrow as it stood
+    vault.pending_claims = vault.pending_claims.saturating_sub(amount);
+
+    msg!(
+        "Claiming {} tokens for {} shares (epoch {})",
+        amount,
+        shares,
+        epoch
+    );
+
+    if amount > 0 {
+        let vault_id_bytes = vault.vault_id.to_le_bytes();
+        let seeds = &[b"vault".as_ref(), vault_id_bytes.as_ref(), &[vault.bump]];
+        let signer_seeds = &[&seeds[..]];
+
+        token::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                token::Transfer {
+                    from: ctx.accounts.claim_escrow.to_account_info(),
+                    to: ctx.accounts.user_token_account.to_account_info(),
+                    authority: vault.to_account_info(),
+                },
+                signer_seeds,
+            ),
+            amount,
+        )?;
+    }
+
+    let position = &mut ctx.accounts.position;
+    position.ensure_initialized(vault.key(), ctx.accounts.user.key(), ctx.bumps.position);
+    position.deposited = position.deposited.saturating_sub(amount);
+
+    emit!(WithdrawalClaimedEvent {
+        vault: vault.key(),
+        user: ctx.accounts.user.key(),
+        epoch,
+        shares,
+        amount,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+    Ok(())
+}
@@ -0,0 +1,558 @@
+use crate::error::vault_error::VaultError;
+use crate::events::risk_events::LossLimitTriggered;
+use crate::events::vault_events::{
+    LossSource, MintWeight, RebalanceExecuted, TargetWeightsUpdated,
+};
+#[cfg(not(feature = "test"))]
+use crate::instructions::jupiter_swap::Jupiter;
+use crate::instructions::vault::invoke_jupiter_route;
+use crate::instructions::vault::loss::NavMark;
+use crate::state::{
+    PriceFeed, RiskPolicy, TargetWeight, Vault, VaultLifecycle, MAX_TARGET_WEIGHTS,
+};
+use anchor_lang::prelude::*;
+use anchor_spl::token::{Token, TokenAccount};
+
+// ===========================================
+// Rebalance
+// ===========================================
+//
+// The manager sets a target allocation (mint -> weight bps). A keeper may then
+// swap one overweight mint into one underweight mint, and only when:
+//   - some mint has drifted past the threshold,
+//   - the total deviation from target goes down after the swap,
+//   - the output clears both the keeper's min_output and the oracle quote
+//     minus the manager's max slippage.
+
+const BPS: u128 = 10_000;
+
+#[derive(Accounts)]
+pub struct SetTargetWeights<'info> {
+    #[account(
+        constraint = manager.key() == vault.manager @ VaultError::Unauthorized
+    )]
+    pub manager: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"vault", vault.vault_id.to_le_bytes().as_ref()],
+        bump = vault.bump,
+    )]
+    pub vault: Account<'info, Vault>,
+}
+
+pub fn set_target_weights(
+    ctx: Context<SetTargetWeights>,
+    target_weights: Vec<TargetWeight>,
+    threshold_bps: u16,
+    max_slippage_bps: u16,
+) -> Result<()> {
+    require!(
+        target_weights.len() <= MAX_TARGET_WEIGHTS,
+        VaultError::InvalidTargetWeights
+    );
+    require!(
+        threshold_bps <= 10_000 && max_slippage_bps <= 10_000,
+        VaultError::InvalidTargetWeights
+    );
+
+    let mut total_bps: u32 = 0;
+    for (i, target) in target_weights.iter().enumerate() {
+        require!(
+            !target_weights[..i].iter().any(|t| t.mint == target.mint),
+            VaultError::InvalidTargetWeights
+        );
+        total_bps += target.weight_bps as u32;
+    }
+    // An empty allocation switches rebalancing off
+    require!(
+        target_weights.is_empty() || total_bps == 10_000,
+        VaultError::InvalidTargetWeights
+    );
+
+    let vault = &mut ctx.accounts.vault;
+    vault.target_weights = target_weights;
+    vault.rebalance_threshold_bps = threshold_bps;
+    vault.rebalance_max_slippage_bps = max_slippage_bps;
+
+    msg!(
+        "Target weights set: {} mints, threshold {} bps, max slippage {} bps",
+        vault.target_weights.len(),
+        threshold_bps,
+        max_slippage_bps
+    );
+
+    emit!(TargetWeightsUpdated {
+        vault: vault.key(),
+        target_weights: vault.target_weights.clone(),
+        threshold_bps,
+        max_slippage_bps,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct Rebalance<'info> {
+    /// Only the keeper (or manager) can rebalance
+    #[account(
+        constraint = vault.is_operator(&keeper.key()) @ VaultError::Unauthorized
+    )]
+    pub keeper: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"vault", vault.vault_id.to_le_bytes().as_ref()],
+        bump = vault.bump,
+        constraint = vault.is_active() @ VaultError::StrategyDisabled,
+    )]
+    pub vault: Box<Account<'info, Vault>>,
+
+    /// Vault account of the overweight mint being sold
+    #[account(
+        mut,
+        constraint = vault_input_ata.owner == vault.key() @ VaultError::InvalidOwner,
+    )]
+    pub vault_input_ata: Box<Account<'info, TokenAccount>>,
+
+    /// Vault account of the underweight mint being bought
+    #[account(
+        mut,
+        constraint = vault_output_ata.owner == vault.key() @ VaultError::InvalidOwner,
+        constraint = vault_output_ata.mint != vault_input_ata.mint @ VaultError::MintMismatch,
+    )]
+    pub vault_output_ata: Box<Account<'info, TokenAccount>>,
+
+    #[account(
+        seeds = [b"price_feed", vault_input_ata.mint.as_ref()],
+        bump = input_price_feed.bump,
+    )]
+    pub input_price_feed: Box<Account<'info, PriceFeed>>,
+
+    #[account(
+        seeds = [b"price_feed", vault_output_ata.mint.as_ref()],
+        bump = output_price_feed.bump,
+    )]
+    pub output_price_feed: Box<Account<'info, PriceFeed>>,
+
+    /// Prices the leg in base-token terms for the risk policy
+    #[account(
+        seeds = [b"price_feed", vault.token_mint.as_ref()],
+        bump = base_price_feed.bump,
+    )]
+    pub base_price_feed: Box<Account<'info, PriceFeed>>,
+
+    #[account(
+        mut,
+        seeds = [b"risk_policy", vault.key().as_ref()],
+        bump = risk_policy.bump,
+    )]
+    pub risk_policy: Box<Account<'info, RiskPolicy>>,
+
+    /// Jupiter program
+    #[cfg(feature = "test")]
+    /// CHECK: Test - not validated
+    pub jupiter_program: UncheckedAccount<'info>,
+
+    #[cfg(not(feature = "test"))]
+    pub jupiter_program: Program<'info, Jupiter>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+/// One priced vault holding
+struct Holding {
+    token_account: Pubkey,
+    mint: Pubkey,
+    amount: u64,
+    price_feed: PriceFeed,
+}
+
+/// Swap an overweight mint into an underweight one.
+///
+/// Remaining accounts: the first `holdings` pairs are (vault token account,
+/// price feed of its mint) covering every vault holding: the base token, both
+/// legs, every target mint and every mint in `strategy_mints`. The rest are
+/// forwarded to Jupiter, and `accounts_meta` orders them (and the vault) for
+/// the route.
+///
+/// A sold strategy mint leaves `strategy_assets` at its booked cost, so the
+/// realized PnL is what the sale fetched over what the holding was booked at.
+pub fn rebalance<'info>(
+    ctx: Context<'_, '_, 'info, 'info, Rebalance<'info>>,
+    amount: u64,
+    min_output: u64,
+    holdings: u8,
+    swap_ix_data: Vec<u8>,
+    accounts_meta: Vec<Pubkey>,
+) -> Result<()> {
+    msg!("=== Rebalance ===");
+    msg!("Amount: {}", amount);
+    msg!("Min output: {}", min_output);
+
+    require!(amount > 0, VaultError::InvalidAmount);
+    require!(
+        !ctx.accounts.vault.target_weights.is_empty(),
+        VaultError::InvalidTargetWeights
+    );
+
+    let vault_key = ctx.accounts.vault.key();
+    let input_mint = ctx.accounts.vault_input_ata.mint;
+    let output_mint = ctx.accounts.vault_output_ata.mint;
+    let now = Clock::get()?.unix_timestamp;
+
+    let holdings_len = holdings as usize * 2;
+    require!(
+        ctx.remaining_accounts.len() >= holdings_len,
+        VaultError::InvalidRemainingAccounts
+    );
+    let (holding_accounts, jupiter_accounts) = ctx.remaining_accounts.split_at(holdings_len);
+
+    // ============================================
+    // 1. PRICE THE PORTFOLIO
+    // ============================================
+
+    let mut portfolio = load_holdings(holding_accounts, &vault_key, now)?;
+
+    for leg in [
+        ctx.accounts.vault_input_ata.key(),
+        ctx.accounts.vault_output_ata.key(),
+    ] {
+        require!(
+            portfolio.iter().any(|h| h.token_account == leg),
+            VaultError::MissingHolding
+        );
+    }
+    // Every mint the vault is known to hold or wants to hold must be priced,
+    // or leaving one out would skew the weights
+    let vault = &ctx.accounts.vault;
+    let required_mints = std::iter::once(&vault.token_mint)
+        .chain(vault.strategy_mints.iter())
+        .chain(vault.target_weights.iter().map(|target| &target.mint));
+    for mint in required_mints {
+        require!(
+            portfolio.iter().any(|h| h.mint == *mint),
+            VaultError::MissingHolding
+        );
+    }
+
+    let weights_before = current_weights(&portfolio)?;
+    let (max_drift, deviation_before) = drift(&ctx.accounts.vault, &weights_before);
+
+    msg!(
+        "Max drift: {} bps, total deviation: {} bps",
+        max_drift,
+        deviation_before
+    );
+
+    require!(
+        max_drift >= ctx.accounts.vault.rebalance_threshold_bps as u64,
+        VaultError::RebalanceNotNeeded
+    );
+
+    // Sell overweight, buy underweight
+    require!(
+        weight_of(&weights_before, &input_mint) > ctx.accounts.vault.target_weight(&input_mint),
+        VaultError::InvalidRebalanceDirection
+    );
+    require!(
+        weight_of(&weights_before, &output_mint) < ctx.accounts.vault.target_weight(&output_mint),
+        VaultError::InvalidRebalanceDirection
+    );
+
+    // ============================================
+    // 2. ENFORCE RISK POLICY
+    // ============================================
+
+    ctx.accounts.input_price_feed.ensure_fresh(now)?;
+    ctx.accounts.output_price_feed.ensure_fresh(now)?;
+    ctx.accounts.base_price_feed.ensure_fresh(now)?;
+
+    let notional = ctx
+        .accounts
+        .input_price_feed
+        .quote(amount, &ctx.accounts.base_price_feed)?;
+
+    let risk_policy = &mut ctx.accounts.risk_policy;
+    risk_policy.roll_window(now);
+    risk_policy.check_execution(now, &input_mint, &output_mint, notional)?;
+
+    // ============================================
+    // 3. SWAP (vault signs)
+    // ============================================
+
+    require!(
+        ctx.accounts.vault_input_ata.amount >= amount,
+        VaultError::InsufficientAssets
+    );
+
+    let protected_accounts = [vault_key, ctx.accounts.risk_policy.key()];
+    for remaining_acc in jupiter_accounts.iter() {
+        require!(
+            !protected_accounts.contains(&remaining_acc.key()),
+            VaultError::ProtectedAccountInRemainingAccounts
+        );
+    }
+
+    let input_balance_before = ctx.accounts.vault_input_ata.amount;
+    let output_balance_before = ctx.accounts.vault_output_ata.amount;
+
+    let vault_id_bytes = ctx.accounts.vault.vault_id.to_le_bytes();
+    let vault_bump = ctx.accounts.vault.bump;
+    let vault_seeds = &[b"vault".as_ref(), vault_id_bytes.as_ref(), &[vault_bump]];
+    let signer_seeds = &[&vault_seeds[..]];
+
+    invoke_jupiter_route(
+        ctx.accounts.jupiter_program.key(),
+        swap_ix_data,
+        accounts_meta,
+        &ctx.accounts.vault.to_account_info(),
+        jupiter_accounts,
+        signer_seeds,
+    )?;
+
+    ctx.accounts.vault_input_ata.reload()?;
+    ctx.accounts.vault_output_ata.reload()?;
+
+    let fair_output = ctx
+        .accounts
+        .input_price_feed
+        .quote(amount, &ctx.accounts.output_price_feed)?;
+
+    let input_used = input_balance_before
+        .checked_sub(ctx.accounts.vault_input_ata.amount)
+        .ok_or(VaultError::MathOverflow)?;
+    let output_received = ctx
+        .accounts
+        .vault_output_ata
+        .amount
+        .checked_sub(output_balance_before)
+        .ok_or(VaultError::MathOverflow)?;
+
+    msg!("Input used: {}", input_used);
+    msg!("Output received: {}", output_received);
+
+    // ============================================
+    // 4. SLIPPAGE BOUNDS
+    // ============================================
+
+    require!(input_used <= amount, VaultError::UnexpectedInputAmount);
+    require_gte!(output_received, min_output, VaultError::SlippageExceeded);
+
+    // Oracle bound, scaled to what was actually sold
+    let oracle_min_output = (fair_output as u128)
+        .checked_mul(input_used as u128)
+        .ok_or(VaultError::MathOverflow)?
+        .checked_div(amount as u128)
+        .ok_or(VaultError::MathOverflow)?
+        .checked_mul(BPS - ctx.accounts.vault.rebalance_max_slippage_bps as u128)
+        .ok_or(VaultError::MathOverflow)?
+        / BPS;
+    require!(
+        output_received as u128 >= oracle_min_output,
+        VaultError::SlippageExceeded
+    );
+
+    msg!("Slippage check passed (oracle min: {})", oracle_min_output);
+
+    // ============================================
+    // 5. VERIFY THE VAULT MOVED TOWARD TARGET
+    // ============================================
+
+    for holding in portfolio.iter_mut() {
+        if holding.token_account == ctx.accounts.vault_input_ata.key() {
+            holding.amount = holding
+                .amount
+                .checked_sub(input_used)
+                .ok_or(VaultError::MathOverflow)?;
+        } else if holding.token_account == ctx.accounts.vault_output_ata.key() {
+            holding.amount = holding
+                .amount
+                .checked_add(output_received)
+                .ok_or(VaultError::MathOverflow)?;
+        }
+    }
+
+    let weights_after = current_weights(&portfolio)?;
+    let (_, deviation_after) = drift(&ctx.accounts.vault, &weights_after);
+
+    msg!("Total deviation after: {} bps", deviation_after);
+
+    require!(
+        deviation_after < deviation_before,
+        VaultError::DeviationNotReduced
+    );
+
+    // ============================================
+    // 6. UPDATE VAULT ACCOUNTING
+    // ============================================
+
+    let input_value = ctx
+        .accounts
+        .input_price_feed
+        .quote(input_used, &ctx.accounts.base_price_feed)?;
+    let output_value = ctx
+        .accounts
+        .output_price_feed
+        .quote(output_received, &ctx.accounts.base_price_feed)?;
+
+    // total_assets tracks the base token; other mints live in strategy_assets
+    // at the cost they were booked at
+    let mark = NavMark::of(&ctx.accounts.vault)?;
+    let vault = &mut ctx.accounts.vault;
+    let input_cost = if input_mint == vault.token_mint {
+        vault.total_assets = vault
+            .total_assets
+            .checked_sub(input_used)
+            .ok_or(VaultError::MathOverflow)?;
+        input_used
+    } else {
+        match vault.release_strategy_cost(&input_mint, input_used, input_balance_before)? {
+            Some(cost) => cost,
+            // Not tracked (bought before v7): released at today's value
+            None => {
+                let released = input_value.min(vault.strategy_assets);
+                vault.strategy_assets -= released;
+                released
+            }
+        }
+    };
+    if output_mint == vault.token_mint {
+        vault.total_assets = vault
+            .total_assets
+            .checked_add(output_received)
+            .ok_or(VaultError::MathOverflow)?;
+    } else {
+        vault.book_strategy_cost(output_mint, output_value)?;
+    }
+
+    let realized_pnl = (output_value as i128 - input_cost as i128)
+        .try_into()
+        .map_err(|_| VaultError::MathOverflow)?;
+    vault.record_pnl(realized_pnl)?;
+    mark.recognize_loss(&ctx.accounts.vault, LossSource::Rebalance)?;
+
+    // ============================================
+    // 7. BOOK REALIZED LOSS AGAINST THE RISK POLICY
+    // ============================================
+
+    let realized_loss = input_value.saturating_sub(output_value);
+
+    let risk_policy = &mut ctx.accounts.risk_policy;
+    risk_policy.record_execution(now, notional, realized_loss)?;
+
+    if risk_policy.loss_limit_hit() {
+        ctx.accounts.vault.status = VaultLifecycle::Paused;
+        msg!("Daily loss limit hit - strategies disabled");
+
+        emit!(LossLimitTriggered {
+            vault: vault_key,
+            window_loss: risk_policy.window_loss(),
+            max_daily_loss: risk_policy.max_daily_loss,
+            timestamp: now,
+        });
+    }
+
+    emit!(RebalanceExecuted {
+        vault: vault_key,
+        input_mint,
+        output_mint,
+        input_used,
+        output_received,
+        realized_pnl,
+        weights_before,
+        weights_after,
+        deviation_before_bps: deviation_before,
+        deviation_after_bps: deviation_after,
+        timestamp: now,
+    });
+
+    msg!("=== Rebalance Executed Successfully ===");
+    Ok(())
+}
+
+/// Read (vault token account, price feed) pairs into priced holdings
+fn load_holdings<'info>(
+    accounts: &'info [AccountInfo<'info>],
+    vault_key: &Pubkey,
+    now: i64,
+) -> Result<Vec<Holding>> {
+    let mut portfolio: Vec<Holding> = Vec::with_capacity(accounts.len() / 2);
+
+    // Escrowed tokens belong to queued withdrawals, not to the portfolio
+    let (share_escrow, _) =
+        Pubkey::find_program_address(&[b"withdraw_escrow", vault_key.as_ref()], &crate::ID);
+    let (claim_escrow, _) =
+        Pubkey::find_program_address(&[b"claim_escrow", vault_key.as_ref()], &crate::ID);
+
+    for pair in accounts.chunks(2) {
+        let token_account = Account::<TokenAccount>::try_from(&pair[0])?;
+        let price_feed = Account::<PriceFeed>::try_from(&pair[1])?;
+
+        require_keys_eq!(token_account.owner, *vault_key, VaultError::InvalidOwner);
+        require!(
+            token_account.key() != share_escrow && token_account.key() != claim_escrow,
+            VaultError::ProtectedAccountInRemainingAccounts
+        );
+        // Feeds only exist at their mint's PDA, so matching the mint is enough
+        require_keys_eq!(
+            price_feed.mint,
+            token_account.mint,
+            VaultError::MintMismatch
+        );
+        require!(
+            !portfolio.iter().any(|h| h.mint == token_account.mint),
+            VaultError::DuplicateTokenAccount
+        );
+        price_feed.ensure_fresh(now)?;
+
+        portfolio.push(Holding {
+            token_account: token_account.key(),
+            mint: token_account.mint,
+            amount: token_account.amount,
+            price_feed: price_feed.into_inner(),
+        });
+    }
+
+    Ok(portfolio)
+}
+
+/// Each holding's share of total USD value, in bps (rounded down)
+fn current_weights(portfolio: &[Holding]) -> Result<Vec<MintWeight>> {
+    let mut values: Vec<u128> = Vec::with_capacity(portfolio.len());
+    let mut total: u128 = 0;
+    for holding in portfolio {
+        let value = holding.price_feed.value_of(holding.amount)?;
+        total = total.checked_add(value).ok_or(VaultError::MathOverflow)?;
+        values.push(value);
+    }
+    require!(total > 0, VaultError::InsufficientAssets);
+
+    portfolio
+        .iter()
+        .zip(values)
+        .map(|(holding, value)| {
+            let weight_bps = value.checked_mul(BPS).ok_or(VaultError::MathOverflow)? / total;
+            Ok(MintWeight {
+                mint: holding.mint,
+                weight_bps: weight_bps as u16,
+            })
+        })
+        .collect()
+}
+
+fn weight_of(weights: &[MintWeight], mint: &Pubkey) -> u16 {
+    weights
+        .iter()
+        .find(|w| w.mint == *mint)
+        .map(|w| w.weight_bps)
+        .unwrap_or(0)
+}
+
+/// (largest single-mint drift, sum of drifts) against the vault's targets, in bps
+fn drift(vault: &Vault, weights: &[MintWeight]) -> (u64, u64) {
+    weights.iter().fold((0, 0), |(max, total), w| {
+        let diff = (w.weight_bps as i64 - vault.target_weight(&w.mint) as i64).unsigned_abs();
+        (max.max(diff), total + diff)
+    })
+}
@@ -0,0 +1,28 @@
+use crate::state::VaultRegistry;
+use anchor_lang::prelude::*;
+
+#[derive(Accounts)]
+pub struct InitializeVaultRegistry<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + VaultRegistry::INIT_SPACE,
+        seeds = [b"vault_registry"],
+        bump
+    )]
+    pub registry: Account<'info, VaultRegistry>,
+
+    pub system_program: Program<'info, System>,
+}
+
+pub fn initialize_vault_registry(ctx: Context<InitializeVaultRegistry>) -> Result<()> {
+    let registry = &mut ctx.accounts.registry;
+    registry.vault_count = 0;
+    registry.bump = ctx.bumps.registry;
+
+    msg!("Vault registry initialized");
+    Ok(())
+}
@@ -0,0 +1,57 @@
+use crate::error::vault_error::VaultError;
+use crate::events::risk_events::RiskPolicyUpdated;
+use crate::state::{RiskLimits, RiskPolicy, Vault};
+use anchor_lang::prelude::*;
+
+#[derive(Accounts)]
+pub struct SetRiskPolicy<'info> {
+    #[account(
+        mut,
+        constraint = manager.key() == vault.manager @ VaultError::Unauthorized
+    )]
+    pub manager: Signer<'info>,
+
+    #[account(
+        seeds = [b"vault", vault.vault_id.to_le_bytes().as_ref()],
+        bump = vault.bump,
+    )]
+    pub vault: Account<'info, Vault>,
+
+    #[account(
+        init_if_needed,
+        payer = manager,
+        space = 8 + RiskPolicy::INIT_SPACE,
+        seeds = [b"risk_policy", vault.key().as_ref()],
+        bump
+    )]
+    pub risk_policy: Box<Account<'info, RiskPolicy>>,
+
+    pub system_program: Program<'info, System>,
+}
+
+/// Create or update the limits enforced on `execute_jupiter_strategy`.
+/// Usage counters (24h window, last execution) are kept across updates.
+pub fn set_risk_policy(ctx: Context<SetRiskPolicy>, limits: RiskLimits) -> Result<()> {
+    let risk_policy = &mut ctx.accounts.risk_policy;
+    risk_policy.vault = ctx.accounts.vault.key();
+    risk_policy.bump = ctx.bumps.risk_policy;
+    risk_policy.apply_limits(limits)?;
+
+    msg!(
+        "Risk policy: max trade {}, max 24h {}, max 24h loss {}, cooldown {}s",
+        risk_policy.max_trade_notional,
+        risk_policy.max_daily_notional,
+        risk_policy.max_daily_loss,
+        risk_policy.min_execution_interval
+    );
+
+    emit!(RiskPolicyUpdated {
+        vault: risk_policy.vault,
+        max_trade_notional: risk_policy.max_trade_notional,
+        max_daily_notional: risk_policy.max_daily_notional,
+        max_daily_loss: risk_policy.max_daily_loss,
+        min_execution_interval: risk_policy.min_execution_interval,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+    Ok(())
+}
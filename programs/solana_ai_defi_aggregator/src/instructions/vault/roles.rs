@@ -0,0 +1,104 @@
+use crate::error::vault_error::VaultError;
+use crate::events::vault_events::{GuardianUpdated, KeeperUpdated, ManagerUpdated};
+use crate::state::{Vault, VaultRegistryEntry};
+use anchor_lang::prelude::*;
+
+// ===========================================
+// Role rotation
+// ===========================================
+//
+// manager  - sets parameters and fees, rotates every role
+// keeper   - executes strategies and settles withdrawal epochs
+// guardian - can only pause / disable strategies
+//
+// Only the manager can rotate roles, so a leaked keeper or guardian key can be
+// replaced without redeploying the vault.
+
+#[derive(Accounts)]
+pub struct SetManager<'info> {
+    #[account(
+        constraint = manager.key() == vault.manager @ VaultError::Unauthorized
+    )]
+    pub manager: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"vault", vault.vault_id.to_le_bytes().as_ref()],
+        bump = vault.bump,
+    )]
+    pub vault: Account<'info, Vault>,
+
+    /// Registry entry mirrors the manager
+    #[account(
+        mut,
+        seeds = [b"vault_registry_entry", vault.vault_id.to_le_bytes().as_ref()],
+        bump = registry_entry.bump,
+        constraint = registry_entry.vault == vault.key() @ VaultError::VaultNotRegistered,
+    )]
+    pub registry_entry: Account<'info, VaultRegistryEntry>,
+}
+
+pub fn set_manager(ctx: Context<SetManager>, new_manager: Pubkey) -> Result<()> {
+    let vault = &mut ctx.accounts.vault;
+    let old_manager = vault.manager;
+    vault.manager = new_manager;
+
+    ctx.accounts.registry_entry.manager = new_manager;
+
+    msg!("Manager: {} -> {}", old_manager, new_manager);
+
+    emit!(ManagerUpdated {
+        vault: vault.key(),
+        old_manager,
+        new_manager,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct SetVaultRole<'info> {
+    #[account(
+        constraint = manager.key() == vault.manager @ VaultError::Unauthorized
+    )]
+    pub manager: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"vault", vault.vault_id.to_le_bytes().as_ref()],
+        bump = vault.bump,
+    )]
+    pub vault: Account<'info, Vault>,
+}
+
+pub fn set_keeper(ctx: Context<SetVaultRole>, new_keeper: Pubkey) -> Result<()> {
+    let vault = &mut ctx.accounts.vault;
+    let old_keeper = vault.keeper;
+    vault.keeper = new_keeper;
+
+    msg!("Keeper: {} -> {}", old_keeper, new_keeper);
+
+    emit!(KeeperUpdated {
+        vault: vault.key(),
+        old_keeper,
+        new_keeper,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+    Ok(())
+}
+
+pub fn set_guardian(ctx: Context<SetVaultRole>, new_guardian: Pubkey) -> Result<()> {
+    let vault = &mut ctx.accounts.vault;
+    let old_guardian = vault.guardian;
+    vault.guardian = new_guardian;
+
+    msg!("Guardian: {} -> {}", old_guardian, new_guardian);
+
+    emit!(GuardianUpdated {
+        vault: vault.key(),
+        old_guardian,
+        new_guardian,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+    Ok(())
+}
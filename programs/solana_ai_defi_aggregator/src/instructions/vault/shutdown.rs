@@ -0,0 +1,81 @@
+use crate::adapters::LendingAdapter;
+use crate::error::vault_error::VaultError;
+use crate::events::vault_events::{AdapterUnwound, VaultShutdownTriggered};
+use crate::instructions::vault::yield_adapter::{recall, AdapterOperation};
+use crate::state::{Vault, VaultLifecycle, VaultRegistryEntry, VaultStatus};
+use anchor_lang::prelude::*;
+
+// ===========================================
+// Emergency shutdown
+// ===========================================
+//
+// Shutdown is one-way. Strategies and deposits stop for good, adapter
+// positions are pulled back by a permissionless crank, and depositors exit
+// through `withdraw_in_kind` so nobody depends on a swap or on liquidity
+// the keeper would have to free up.
+
+#[derive(Accounts)]
+pub struct EmergencyShutdown<'info> {
+    /// Guardian (or manager)
+    #[account(
+        constraint = vault.can_pause(&guardian.key()) @ VaultError::Unauthorized
+    )]
+    pub guardian: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"vault", vault.vault_id.to_le_bytes().as_ref()],
+        bump = vault.bump,
+    )]
+    pub vault: Account<'info, Vault>,
+
+    /// Registry entry mirrors the shutdown
+    #[account(
+        mut,
+        seeds = [b"vault_registry_entry", vault.vault_id.to_le_bytes().as_ref()],
+        bump = registry_entry.bump,
+        constraint = registry_entry.vault == vault.key() @ VaultError::VaultNotRegistered,
+    )]
+    pub registry_entry: Account<'info, VaultRegistryEntry>,
+}
+
+pub fn emergency_shutdown(ctx: Context<EmergencyShutdown>) -> Result<()> {
+    let vault = &mut ctx.accounts.vault;
+    require!(!vault.is_shutdown(), VaultError::VaultShutdown);
+
+    vault.status = VaultLifecycle::Shutdown;
+    ctx.accounts.registry_entry.status = VaultStatus::Shutdown;
+
+    msg!("Vault shut down by {}", ctx.accounts.guardian.key());
+
+    emit!(VaultShutdownTriggered {
+        vault: vault.key(),
+        triggered_by: ctx.accounts.guardian.key(),
+        total_assets: vault.total_assets,
+        adapter_assets: vault.adapter_assets,
+        total_shares: vault.total_shares,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+    Ok(())
+}
+
+/// Crank: redeem the whole adapter position back into the vault token account.
+/// Permissionless once the vault is shut down.
+pub fn unwind_adapter(ctx: Context<AdapterOperation>) -> Result<()> {
+    let accounts = ctx.accounts;
+    require!(accounts.vault.is_shutdown(), VaultError::VaultNotShutdown);
+
+    let position = accounts.adapter().balance()?;
+    require!(position > 0, VaultError::InsufficientAssets);
+
+    let received = recall(accounts, position)?;
+    msg!("Unwound adapter position: {} received", received);
+
+    emit!(AdapterUnwound {
+        vault: accounts.vault.key(),
+        received,
+        adapter_assets: accounts.vault.adapter_assets,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+    Ok(())
+}
@@ -0,0 +1,64 @@
+use crate::error::vault_error::VaultError;
+use crate::events::vault_events::{LossSource, VaultSynced};
+use crate::instructions::vault::loss::NavMark;
+use crate::state::Vault;
+use anchor_lang::prelude::*;
+use anchor_spl::token::TokenAccount;
+
+#[derive(Accounts)]
+pub struct SyncVault<'info> {
+    /// Manager or keeper: a sync moves the share price either way
+    #[account(
+        constraint = vault.is_operator(&authority.key()) @ VaultError::Unauthorized
+    )]
+    pub authority: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"vault", vault.vault_id.to_le_bytes().as_ref()],
+        bump = vault.bump
+    )]
+    pub vault: Account<'info, Vault>,
+
+    #[account(
+        seeds = [b"vault_token_account", vault.key().as_ref()],
+        bump,
+        constraint = vault_token_account.mint == vault.token_mint @ VaultError::MintMismatch,
+    )]
+    pub vault_token_account: Account<'info, TokenAccount>,
+}
+
+/// Reset `total_assets` to the base tokens the vault actually holds.
+///
+/// Bookkeeping drifts from the balance through direct donations, swaps that
+/// pay base tokens back in, or test helpers. A surplus is credited to every
+/// holder; a shortfall is recognized as a loss.
+pub fn sync_vault(ctx: Context<SyncVault>) -> Result<()> {
+    let mark = NavMark::of(&ctx.accounts.vault)?;
+    let vault = &mut ctx.accounts.vault;
+    let previous_total_assets = vault.total_assets;
+    let balance = ctx.accounts.vault_token_account.amount;
+
+    let difference = i64::try_from(balance as i128 - previous_total_assets as i128)
+        .map_err(|_| VaultError::MathOverflow)?;
+    vault.total_assets = balance;
+
+    msg!(
+        "Vault synced: total_assets {} -> {} ({:+})",
+        previous_total_assets,
+        balance,
+        difference
+    );
+
+    emit!(VaultSynced {
+        vault: vault.key(),
+        previous_total_assets,
+        total_assets: balance,
+        difference,
+        synced_by: ctx.accounts.authority.key(),
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    mark.recognize_loss(vault, LossSource::Sync)?;
+    Ok(())
+}
@@ -0,0 +1,161 @@
+use crate::error::vault_error::VaultError;
+use crate::state::{DepositorPosition, NavHistory, Vault, SECONDS_PER_DAY};
+use anchor_lang::prelude::*;
+use anchor_spl::token::{Mint, TokenAccount};
+
+#[derive(Accounts)]
+pub struct MaxDeposit<'info> {
+    #[account(
+        seeds = [b"vault", vault.vault_id.to_le_bytes().as_ref()],
+        bump = vault.bump
+    )]
+    pub vault: Account<'info, Vault>,
+
+    /// CHECK: Depositor being queried, only used to derive the position PDA
+    pub owner: UncheckedAccount<'info>,
+
+    /// Depositor position, omitted if the owner never deposited
+    #[account(
+        seeds = [b"position", vault.key().as_ref(), owner.key().as_ref()],
+        bump = position.bump
+    )]
+    pub position: Option<Account<'info, DepositorPosition>>,
+}
+
+#[derive(Accounts)]
+pub struct MaxWithdraw<'info> {
+    #[account(
+        seeds = [b"vault", vault.vault_id.to_le_bytes().as_ref()],
+        bump = vault.bump
+    )]
+    pub vault: Account<'info, Vault>,
+
+    /// Vault's token account (available liquidity)
+    #[account(
+        seeds = [b"vault_token_account", vault.key().as_ref()],
+        bump,
+        constraint = vault_token_account.mint == vault.token_mint @ VaultError::MintMismatch,
+    )]
+    pub vault_token_account: Account<'info, TokenAccount>,
+
+    /// Share token account of the owner being queried
+    #[account(
+        constraint = owner_share_account.mint == vault.share_mint @ VaultError::MintMismatch,
+    )]
+    pub owner_share_account: Account<'info, TokenAccount>,
+}
+
+#[derive(Accounts)]
+pub struct VaultApy<'info> {
+    #[account(
+        seeds = [b"vault", vault.vault_id.to_le_bytes().as_ref()],
+        bump = vault.bump
+    )]
+    pub vault: Account<'info, Vault>,
+
+    #[account(
+        seeds = [b"nav_history", vault.key().as_ref()],
+        bump = nav_history.bump,
+        has_one = vault,
+    )]
+    pub nav_history: Box<Account<'info, NavHistory>>,
+}
+
+#[derive(Accounts)]
+pub struct VaultHealth<'info> {
+    #[account(
+        seeds = [b"vault", vault.vault_id.to_le_bytes().as_ref()],
+        bump = vault.bump
+    )]
+    pub vault: Account<'info, Vault>,
+
+    #[account(
+        constraint = share_mint.key() == vault.share_mint @ VaultError::MintMismatch,
+    )]
+    pub share_mint: Account<'info, Mint>,
+
+    #[account(
+        seeds = [b"vault_token_account", vault.key().as_ref()],
+        bump,
+        constraint = vault_token_account.mint == vault.token_mint @ VaultError::MintMismatch,
+    )]
+    pub vault_token_account: Account<'info, TokenAccount>,
+}
+
+/// Bookkeeping next to the on-chain balances it should agree with
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+pub struct VaultHealthView {
+    pub total_shares: u64,
+    pub share_supply: u64,
+    pub total_assets: u64,
+    pub token_balance: u64,
+    /// Tokens held beyond `total_assets` (credited on the next sync)
+    pub unbooked_assets: u64,
+}
+
+/// Annualized share-price returns; None until the history covers the window
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+pub struct ApyView {
+    pub share_price: u64,
+    pub apy_7d_bps: Option<i64>,
+    pub apy_30d_bps: Option<i64>,
+    pub snapshots: u16,
+}
+
+/// Returns the maximum amount of tokens the owner can deposit
+pub fn max_deposit(ctx: Context<MaxDeposit>) -> Result<u64> {
+    let deposited = ctx
+        .accounts
+        .position
+        .as_ref()
+        .map_or(0, |position| position.deposited);
+
+    Ok(ctx.accounts.vault.max_deposit(deposited))
+}
+
+/// Returns the maximum amount of tokens the share account owner can withdraw
+pub fn max_withdraw(ctx: Context<MaxWithdraw>) -> Result<u64> {
+    ctx.accounts.vault.max_withdraw(
+        ctx.accounts.owner_share_account.amount,
+        ctx.accounts.vault_token_account.amount,
+    )
+}
+
+/// Returns the vault's 7-day and 30-day annualized returns from its NAV history
+pub fn vault_apy(ctx: Context<VaultApy>) -> Result<ApyView> {
+    let now = Clock::get()?.unix_timestamp;
+    let share_price = ctx.accounts.vault.share_price()?;
+    let nav_history = &ctx.accounts.nav_history;
+
+    Ok(ApyView {
+        share_price,
+        apy_7d_bps: nav_history.annualized_return_bps(7 * SECONDS_PER_DAY, now, share_price)?,
+        apy_30d_bps: nav_history.annualized_return_bps(30 * SECONDS_PER_DAY, now, share_price)?,
+        snapshots: nav_history.len,
+    })
+}
+
+/// Fails unless `total_shares` equals the share mint supply and `total_assets`
+/// is covered by the vault's token balance; returns the figures otherwise
+pub fn vault_health(ctx: Context<VaultHealth>) -> Result<VaultHealthView> {
+    let vault = &ctx.accounts.vault;
+    let share_supply = ctx.accounts.share_mint.supply;
+    let token_balance = ctx.accounts.vault_token_account.amount;
+
+    require!(
+        vault.total_shares == share_supply,
+        VaultError::ShareSupplyMismatch
+    );
+    require!(
+        vault.total_assets <= token_balance,
+        VaultError::AssetsExceedBalance
+    );
+
+    Ok(VaultHealthView {
+        total_shares: vault.total_shares,
+        share_supply,
+        total_assets: vault.total_assets,
+        token_balance,
+        unbooked_assets: token_balance - vault.total_assets,
+    })
+}
@@ -1,6 +1,6 @@
 use crate::error::vault_error::VaultError;
 use crate::events::vault_events::WithdrawEvent;
-use crate::state::vault::Vault;
+use crate::state::{DepositorPosition, Vault};
 use anchor_lang::prelude::*;
 use anchor_spl::token::{self, Mint, Token, TokenAccount};
 
@@ -11,7 +11,7 @@ pub struct Withdraw<'info> {
 
     #[account(
         mut,
-        seeds = [b"vault", vault.token_mint.as_ref()],
+        seeds = [b"vault", vault.vault_id.to_le_bytes().as_ref()],
         bump = vault.bump
     )]
     pub vault: Account<'info, Vault>,
@@ -47,25 +47,60 @@ pub struct Withdraw<'info> {
     )]
     pub share_mint: Account<'info, Mint>,
 
+    /// Depositor position (net deposits shrink on withdrawal)
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = 8 + DepositorPosition::INIT_SPACE,
+        seeds = [b"position", vault.key().as_ref(), user.key().as_ref()],
+        bump
+    )]
+    pub position: Account<'info, DepositorPosition>,
+
     pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
 }
 
 pub fn withdraw(ctx: Context<Withdraw>, shares: u64) -> Result<()> {
     require!(shares > 0, VaultError::ZeroShares);
+    require!(
+        !ctx.accounts.vault.is_shutdown(),
+        VaultError::WithdrawInKindOnly
+    );
 
-    let vault = &mut ctx.accounts.vault;
+    // calculate assets to return (rounded down, in favor of the vault)
+    let assets = ctx.accounts.vault.preview_redeem(shares)?;
 
-    // calculate assets to return
-    let assets_to_return = vault.shares_to_assets(shares)?;
+    process_withdraw(ctx, shares, assets)
+}
 
-    // verify vault has enough assets
+/// Withdraw exactly `amount` tokens, burning at most `max_shares_in` shares
+pub fn redeem_assets(ctx: Context<Withdraw>, amount: u64, max_shares_in: u64) -> Result<()> {
+    require!(amount > 0, VaultError::ZeroAmount);
     require!(
-        vault.total_assets >= assets_to_return,
-        VaultError::InsufficientAssets
+        !ctx.accounts.vault.is_shutdown(),
+        VaultError::WithdrawInKindOnly
     );
 
+    // Shares to burn are rounded up, in favor of the vault
+    let shares = ctx.accounts.vault.preview_withdraw(amount)?;
+    require!(shares <= max_shares_in, VaultError::SlippageExceeded);
+
+    process_withdraw(ctx, shares, amount)
+}
+
+/// Burn `shares` and pay out the `assets` they are worth
+fn process_withdraw(ctx: Context<Withdraw>, shares: u64, assets: u64) -> Result<()> {
+    let vault = &mut ctx.accounts.vault;
+    let position = &mut ctx.accounts.position;
+
+    position.ensure_initialized(vault.key(), ctx.accounts.user.key(), ctx.bumps.position);
+
+    // verify vault has enough assets
+    require!(vault.total_assets >= assets, VaultError::InsufficientAssets);
+
     msg!("Burning {} shares", shares);
-    msg!("Returning {} tokens", assets_to_return);
+    msg!("Returning {} tokens", assets);
     msg!("Share price: {}", vault.share_price()?);
 
     // Burn user's shares
@@ -83,7 +118,8 @@ pub fn withdraw(ctx: Context<Withdraw>, shares: u64) -> Result<()> {
 
     // Transfer tokens from vault to user
     // let vault_key = vault.key();
-    let seed = &[b"vault", vault.token_mint.as_ref(), &[vault.bump]];
+    let vault_id_bytes = vault.vault_id.to_le_bytes();
+    let seed = &[b"vault".as_ref(), vault_id_bytes.as_ref(), &[vault.bump]];
 
     let signer_seeds = &[&seed[..]];
 
@@ -97,24 +133,25 @@ pub fn withdraw(ctx: Context<Withdraw>, shares: u64) -> Result<()> {
             },
             signer_seeds,
         ),
-        assets_to_return,
+        assets,
     )?;
 
     // Update vault state
 
     vault.total_assets = vault
         .total_assets
-        .checked_sub(assets_to_return)
+        .checked_sub(assets)
         .ok_or(VaultError::MathOverflow)?;
     vault.total_shares = vault
         .total_shares
         .checked_sub(shares)
         .ok_or(VaultError::MathOverflow)?;
+    position.deposited = position.deposited.saturating_sub(assets);
 
     emit!(WithdrawEvent {
         user: ctx.accounts.user.key(),
         shares,
-        amount: assets_to_return,
+        amount: assets,
         total_assets: vault.total_assets,
         total_shares: vault.total_shares,
         timestamp: Clock::get()?.unix_timestamp,
@@ -0,0 +1,260 @@
+use crate::error::vault_error::VaultError;
+use crate::events::vault_events::WithdrawInKindEvent;
+use crate::state::{DepositorPosition, Vault};
+use anchor_lang::prelude::*;
+use anchor_spl::token::{self, Mint, Token, TokenAccount};
+
+#[derive(Accounts)]
+pub struct WithdrawInKind<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"vault", vault.vault_id.to_le_bytes().as_ref()],
+        bump = vault.bump
+    )]
+    pub vault: Account<'info, Vault>,
+
+    /// Vault's base token account (always paid out)
+    #[account(
+        mut,
+        seeds = [b"vault_token_account", vault.key().as_ref()],
+        bump,
+        constraint = vault_token_account.mint == vault.token_mint @ VaultError::MintMismatch,
+    )]
+    pub vault_token_account: Box<Account<'info, TokenAccount>>,
+
+    /// User's base token account
+    #[account(
+        mut,
+        constraint = user_token_account.mint == vault.token_mint @ VaultError::MintMismatch,
+        constraint = user_token_account.owner == user.key() @ VaultError::InvalidOwner,
+    )]
+    pub user_token_account: Box<Account<'info, TokenAccount>>,
+
+    /// User's share token account (shares to burn)
+    #[account(
+        mut,
+        constraint = user_share_account.mint == vault.share_mint @ VaultError::MintMismatch,
+        constraint = user_share_account.owner == user.key() @ VaultError::InvalidOwner,
+    )]
+    pub user_share_account: Box<Account<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        constraint = share_mint.key() == vault.share_mint @ VaultError::MintMismatch,
+    )]
+    pub share_mint: Box<Account<'info, Mint>>,
+
+    /// Depositor position (net deposits shrink on withdrawal)
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = 8 + DepositorPosition::INIT_SPACE,
+        seeds = [b"position", vault.key().as_ref(), user.key().as_ref()],
+        bump
+    )]
+    pub position: Box<Account<'info, DepositorPosition>>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+/// Burn `shares` and pay the user a pro-rata slice of every vault token account.
+///
+/// Remaining accounts come in pairs: a token account owned by the vault,
+/// followed by the user's token account of the same mint. No swap is involved,
+/// so this exit works whatever the strategy has deployed into.
+pub fn withdraw_in_kind<'info>(
+    ctx: Context<'_, '_, 'info, 'info, WithdrawInKind<'info>>,
+    shares: u64,
+) -> Result<()> {
+    require!(shares > 0, VaultError::ZeroShares);
+    require!(
+        ctx.remaining_accounts.len() % 2 == 0,
+        VaultError::InvalidRemainingAccounts
+    );
+
+    let vault = &mut ctx.accounts.vault;
+    let vault_key = vault.key();
+    let total_shares = vault.total_shares;
+    require!(total_shares > 0, VaultError::NoShares);
+    require!(shares <= total_shares, VaultError::InsufficientShares);
+    // The virtual shares own their slice too, so an in-kind exit never pays
+    // more than the same shares would redeem for
+    let share_supply = total_shares
+        .checked_add(Vault::VIRTUAL_SHARES)
+        .ok_or(VaultError::MathOverflow)?;
+
+    let share_balance = ctx.accounts.user_share_account.amount;
+
+    let position = &mut ctx.accounts.position;
+    position.ensure_initialized(vault_key, ctx.accounts.user.key(), ctx.bumps.position);
+
+    // Escrows belong to queued withdrawals, never to in-kind payouts
+    let (share_escrow, _) =
+        Pubkey::find_program_address(&[b"withdraw_escrow", vault_key.as_ref()], &crate::ID);
+    let (claim_escrow, _) =
+        Pubkey::find_program_address(&[b"claim_escrow", vault_key.as_ref()], &crate::ID);
+    // Lending receipts carry their share of the adapter position with them
+    let (adapter_collateral, _) =
+        Pubkey::find_program_address(&[b"adapter_collateral", vault_key.as_ref()], &crate::ID);
+    let mut adapter_paid_out = false;
+
+    msg!("Burning {} of {} shares in kind", shares, total_shares);
+
+    // Burn user's shares
+    token::burn(
+        CpiContext::new(
+            ctx.accounts.token_program.to_account_info(),
+            token::Burn {
+                mint: ctx.accounts.share_mint.to_account_info(),
+                from: ctx.accounts.user_share_account.to_account_info(),
+                authority: ctx.accounts.user.to_account_info(),
+            },
+        ),
+        shares,
+    )?;
+
+    let vault_id_bytes = vault.vault_id.to_le_bytes();
+    let seeds = &[b"vault".as_ref(), vault_id_bytes.as_ref(), &[vault.bump]];
+    let signer_seeds = &[&seeds[..]];
+
+    // Base token leg
+    let base_amount = pro_rata(
+        ctx.accounts.vault_token_account.amount,
+        shares,
+        share_supply,
+    )?;
+    if base_amount > 0 {
+        token::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                token::Transfer {
+                    from: ctx.accounts.vault_token_account.to_account_info(),
+                    to: ctx.accounts.user_token_account.to_account_info(),
+                    authority: vault.to_account_info(),
+                },
+                signer_seeds,
+            ),
+            base_amount,
+        )?;
+    }
+
+    // Every other token the vault holds
+    let mut seen = vec![ctx.accounts.vault_token_account.key()];
+    let mut other_accounts: u8 = 0;
+
+    for pair in ctx.remaining_accounts.chunks(2) {
+        let source_info = &pair[0];
+        let destination_info = &pair[1];
+
+        let source = Account::<TokenAccount>::try_from(source_info)?;
+        let destination = Account::<TokenAccount>::try_from(destination_info)?;
+
+        require_keys_eq!(source.owner, vault_key, VaultError::InvalidOwner);
+        require_keys_eq!(
+            destination.owner,
+            ctx.accounts.user.key(),
+            VaultError::InvalidOwner
+        );
+        require_keys_eq!(source.mint, destination.mint, VaultError::MintMismatch);
+        // Shares the vault holds (escrowed or locked) are not vault assets
+        require!(
+            source.mint != vault.share_mint,
+            VaultError::ProtectedAccountInRemainingAccounts
+        );
+        require!(
+            source.key() != share_escrow && source.key() != claim_escrow,
+            VaultError::ProtectedAccountInRemainingAccounts
+        );
+        require!(
+            !seen.contains(&source.key()),
+            VaultError::DuplicateTokenAccount
+        );
+        seen.push(source.key());
+        adapter_paid_out |= source.key() == adapter_collateral;
+
+        let amount = pro_rata(source.amount, shares, share_supply)?;
+        if amount > 0 {
+            token::transfer(
+                CpiContext::new_with_signer(
+                    ctx.accounts.token_program.to_account_info(),
+                    token::Transfer {
+                        from: source_info.clone(),
+                        to: destination_info.clone(),
+                        authority: vault.to_account_info(),
+                    },
+                    signer_seeds,
+                ),
+                amount,
+            )?;
+        }
+
+        msg!("Paid {} of mint {}", amount, source.mint);
+        other_accounts = other_accounts
+            .checked_add(1)
+            .ok_or(VaultError::MathOverflow)?;
+    }
+
+    // Update vault state: the burned shares take their slice of the bookkeeping
+    let assets_released = pro_rata(vault.total_assets, shares, share_supply)?;
+    vault.total_assets = vault
+        .total_assets
+        .checked_sub(assets_released)
+        .ok_or(VaultError::MathOverflow)?;
+    if adapter_paid_out {
+        let adapter_released = pro_rata(vault.adapter_assets, shares, share_supply)?;
+        vault.adapter_assets = vault
+            .adapter_assets
+            .checked_sub(adapter_released)
+            .ok_or(VaultError::MathOverflow)?;
+    }
+    // Strategy holdings leave at their booked value whether or not every
+    // mint was passed in; anything left behind accrues to the other holders
+    let strategy_released = pro_rata(vault.strategy_assets, shares, share_supply)?;
+    vault.strategy_assets = vault
+        .strategy_assets
+        .checked_sub(strategy_released)
+        .ok_or(VaultError::MathOverflow)?;
+    vault.release_strategy_costs_pro_rata(shares, share_supply)?;
+    // Same for child vault shares, re-marked by the next sync
+    let child_released = pro_rata(vault.child_assets, shares, share_supply)?;
+    vault.child_assets = vault
+        .child_assets
+        .checked_sub(child_released)
+        .ok_or(VaultError::MathOverflow)?;
+    vault.total_shares = vault
+        .total_shares
+        .checked_sub(shares)
+        .ok_or(VaultError::MathOverflow)?;
+
+    let position = &mut ctx.accounts.position;
+    let principal_released = pro_rata(position.deposited, shares, share_balance.max(shares))?;
+    position.deposited = position.deposited.saturating_sub(principal_released);
+
+    emit!(WithdrawInKindEvent {
+        user: ctx.accounts.user.key(),
+        shares,
+        base_amount,
+        other_accounts,
+        total_assets: vault.total_assets,
+        total_shares: vault.total_shares,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+    Ok(())
+}
+
+/// amount * shares / supply, rounded down (in favor of the vault)
+fn pro_rata(amount: u64, shares: u64, supply: u64) -> Result<u64> {
+    let value = (amount as u128)
+        .checked_mul(shares as u128)
+        .ok_or(VaultError::MathOverflow)?
+        .checked_div(supply as u128)
+        .ok_or(VaultError::MathOverflow)?;
+
+    require!(value <= u64::MAX as u128, VaultError::MathOverflow);
+
+    Ok(value as u64)
+}
@@ -0,0 +1,456 @@
+use crate::adapters::{reserve_accounts, LendingAdapter, TokenLendingAdapter};
+use crate::error::vault_error::VaultError;
+use crate::events::vault_events::{
+    AdapterDeployed, AdapterRecalled, AdapterSynced, LendingProgramUpdated, LossSource,
+    YieldAdapterRegistered,
+};
+use crate::instructions::vault::loss::NavMark;
+use crate::state::{AdapterKind, ApprovedLendingProgram, GlobalState, Vault, YieldAdapter};
+use anchor_lang::prelude::*;
+use anchor_spl::token::{Mint, Token, TokenAccount};
+
+// ===========================================
+// Yield strategy
+// ===========================================
+//
+// The manager points the vault at one lending reserve. The keeper deploys
+// idle base tokens into it and recalls them when withdrawals need liquidity.
+// Deployed tokens count toward NAV through `vault.adapter_assets`, which is
+// re-marked from the reserve's exchange rate on every deploy, recall or sync.
+//
+// The vault signs every CPI into the lending program, so the program must be
+// approved by the protocol admin first. Revoking an approval stops new
+// registrations and deploys; positions already deployed can still be
+// recalled or unwound.
+
+#[derive(Accounts)]
+#[instruction(program: Pubkey)]
+pub struct ApproveLendingProgram<'info> {
+    #[account(
+        mut,
+        constraint = admin.key() == global_state.admin @ VaultError::Unauthorized
+    )]
+    pub admin: Signer<'info>,
+
+    #[account(
+        seeds = [b"global_state"],
+        bump = global_state.bump
+    )]
+    pub global_state: Account<'info, GlobalState>,
+
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + ApprovedLendingProgram::INIT_SPACE,
+        seeds = [b"lending_program", program.as_ref()],
+        bump
+    )]
+    pub approved_lending_program: Account<'info, ApprovedLendingProgram>,
+
+    pub system_program: Program<'info, System>,
+}
+
+/// Let vault managers register adapters on `program`
+pub fn approve_lending_program(ctx: Context<ApproveLendingProgram>, program: Pubkey) -> Result<()> {
+    let now = Clock::get()?.unix_timestamp;
+    let approval = &mut ctx.accounts.approved_lending_program;
+    approval.program = program;
+    approval.approved_at = now;
+    approval.bump = ctx.bumps.approved_lending_program;
+
+    msg!("Lending program approved: {}", program);
+
+    emit!(LendingProgramUpdated {
+        program,
+        approved: true,
+        timestamp: now,
+    });
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct RevokeLendingProgram<'info> {
+    #[account(
+        mut,
+        constraint = admin.key() == global_state.admin @ VaultError::Unauthorized
+    )]
+    pub admin: Signer<'info>,
+
+    #[account(
+        seeds = [b"global_state"],
+        bump = global_state.bump
+    )]
+    pub global_state: Account<'info, GlobalState>,
+
+    #[account(
+        mut,
+        close = admin,
+        seeds = [b"lending_program", approved_lending_program.program.as_ref()],
+        bump = approved_lending_program.bump,
+    )]
+    pub approved_lending_program: Account<'info, ApprovedLendingProgram>,
+}
+
+/// Withdraw an approval. Adapters already on the program can only recall.
+pub fn revoke_lending_program(ctx: Context<RevokeLendingProgram>) -> Result<()> {
+    let program = ctx.accounts.approved_lending_program.program;
+
+    msg!("Lending program revoked: {}", program);
+
+    emit!(LendingProgramUpdated {
+        program,
+        approved: false,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct RegisterYieldAdapter<'info> {
+    #[account(
+        mut,
+        constraint = manager.key() == vault.manager @ VaultError::Unauthorized
+    )]
+    pub manager: Signer<'info>,
+
+    #[account(
+        seeds = [b"vault", vault.vault_id.to_le_bytes().as_ref()],
+        bump = vault.bump,
+    )]
+    pub vault: Box<Account<'info, Vault>>,
+
+    #[account(
+        init,
+        payer = manager,
+        space = 8 + YieldAdapter::INIT_SPACE,
+        seeds = [b"yield_adapter", vault.key().as_ref()],
+        bump
+    )]
+    pub yield_adapter: Box<Account<'info, YieldAdapter>>,
+
+    /// CHECK: an approved program exposing the token-lending interface
+    #[account(executable)]
+    pub lending_program: UncheckedAccount<'info>,
+
+    #[account(
+        seeds = [b"lending_program", lending_program.key().as_ref()],
+        bump = approved_lending_program.bump,
+    )]
+    pub approved_lending_program: Box<Account<'info, ApprovedLendingProgram>>,
+
+    /// CHECK: must belong to the lending program
+    #[account(owner = lending_program.key() @ VaultError::InvalidOwner)]
+    pub reserve: UncheckedAccount<'info>,
+
+    #[account(
+        constraint = reserve_liquidity_supply.mint == vault.token_mint @ VaultError::MintMismatch,
+    )]
+    pub reserve_liquidity_supply: Box<Account<'info, TokenAccount>>,
+
+    pub reserve_collateral_mint: Box<Account<'info, Mint>>,
+
+    /// CHECK: identity only, the lending program validates it against the reserve
+    pub lending_market: UncheckedAccount<'info>,
+
+    /// Vault-owned account receiving the collateral
+    #[account(
+        init,
+        payer = manager,
+        seeds = [b"adapter_collateral", vault.key().as_ref()],
+        bump,
+        token::mint = reserve_collateral_mint,
+        token::authority = vault,
+    )]
+    pub collateral_account: Box<Account<'info, TokenAccount>>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+pub fn register_yield_adapter(ctx: Context<RegisterYieldAdapter>) -> Result<()> {
+    let lending_program = ctx.accounts.lending_program.key();
+    let lending_market = ctx.accounts.lending_market.key();
+
+    // The supply and collateral mint are pinned, so they must be the reserve's
+    let (liquidity_supply, collateral_mint) =
+        reserve_accounts(&ctx.accounts.reserve.try_borrow_data()?)?;
+    require_keys_eq!(
+        liquidity_supply,
+        ctx.accounts.reserve_liquidity_supply.key(),
+        VaultError::ReserveMismatch
+    );
+    require_keys_eq!(
+        collateral_mint,
+        ctx.accounts.reserve_collateral_mint.key(),
+        VaultError::ReserveMismatch
+    );
+
+    // SPL token-lending derives the market authority from the market key
+    let (lending_market_authority, _) =
+        Pubkey::find_program_address(&[lending_market.as_ref()], &lending_program);
+
+    let yield_adapter = &mut ctx.accounts.yield_adapter;
+    yield_adapter.vault = ctx.accounts.vault.key();
+    yield_adapter.kind = AdapterKind::TokenLending;
+    yield_adapter.lending_program = lending_program;
+    yield_adapter.reserve = ctx.accounts.reserve.key();
+    yield_adapter.reserve_liquidity_supply = ctx.accounts.reserve_liquidity_supply.key();
+    yield_adapter.reserve_collateral_mint = ctx.accounts.reserve_collateral_mint.key();
+    yield_adapter.lending_market = lending_market;
+    yield_adapter.lending_market_authority = lending_market_authority;
+    yield_adapter.collateral_account = ctx.accounts.collateral_account.key();
+    yield_adapter.deployed = 0;
+    yield_adapter.last_synced_at = Clock::get()?.unix_timestamp;
+    yield_adapter.bump = ctx.bumps.yield_adapter;
+
+    msg!(
+        "Yield adapter registered: reserve {}",
+        yield_adapter.reserve
+    );
+
+    emit!(YieldAdapterRegistered {
+        vault: yield_adapter.vault,
+        lending_program,
+        reserve: yield_adapter.reserve,
+        timestamp: yield_adapter.last_synced_at,
+    });
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct AdapterOperation<'info> {
+    /// Keeper or manager for deploy / recall / sync; anyone may unwind after shutdown
+    pub authority: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"vault", vault.vault_id.to_le_bytes().as_ref()],
+        bump = vault.bump,
+    )]
+    pub vault: Box<Account<'info, Vault>>,
+
+    #[account(
+        mut,
+        seeds = [b"yield_adapter", vault.key().as_ref()],
+        bump = yield_adapter.bump,
+        has_one = vault,
+    )]
+    pub yield_adapter: Box<Account<'info, YieldAdapter>>,
+
+    #[account(
+        mut,
+        seeds = [b"vault_token_account", vault.key().as_ref()],
+        bump,
+    )]
+    pub vault_token_account: Box<Account<'info, TokenAccount>>,
+
+    #[account(mut, address = yield_adapter.collateral_account)]
+    pub collateral_account: Box<Account<'info, TokenAccount>>,
+
+    /// CHECK: pinned by the adapter
+    #[account(address = yield_adapter.lending_program)]
+    pub lending_program: UncheckedAccount<'info>,
+
+    /// Current approval of the lending program; required to deploy
+    #[account(
+        seeds = [b"lending_program", yield_adapter.lending_program.as_ref()],
+        bump = approved_lending_program.bump,
+    )]
+    pub approved_lending_program: Option<Box<Account<'info, ApprovedLendingProgram>>>,
+
+    /// CHECK: pinned by the adapter
+    #[account(mut, address = yield_adapter.reserve)]
+    pub reserve: UncheckedAccount<'info>,
+
+    /// CHECK: pinned by the adapter
+    #[account(mut, address = yield_adapter.reserve_liquidity_supply)]
+    pub reserve_liquidity_supply: UncheckedAccount<'info>,
+
+    /// CHECK: pinned by the adapter
+    #[account(mut, address = yield_adapter.reserve_collateral_mint)]
+    pub reserve_collateral_mint: UncheckedAccount<'info>,
+
+    /// CHECK: pinned by the adapter
+    #[account(address = yield_adapter.lending_market)]
+    pub lending_market: UncheckedAccount<'info>,
+
+    /// CHECK: pinned by the adapter
+    #[account(address = yield_adapter.lending_market_authority)]
+    pub lending_market_authority: UncheckedAccount<'info>,
+
+    pub clock: Sysvar<'info, Clock>,
+    pub token_program: Program<'info, Token>,
+}
+
+impl<'info> AdapterOperation<'info> {
+    pub(crate) fn adapter(&self) -> TokenLendingAdapter<'_, 'info> {
+        TokenLendingAdapter {
+            lending_program: self.lending_program.as_ref(),
+            reserve: self.reserve.as_ref(),
+            reserve_liquidity_supply: self.reserve_liquidity_supply.as_ref(),
+            reserve_collateral_mint: self.reserve_collateral_mint.as_ref(),
+            lending_market: self.lending_market.as_ref(),
+            lending_market_authority: self.lending_market_authority.as_ref(),
+            liquidity_account: self.vault_token_account.as_ref().as_ref(),
+            collateral_account: self.collateral_account.as_ref().as_ref(),
+            authority: self.vault.as_ref().as_ref(),
+            clock: self.clock.as_ref(),
+            token_program: self.token_program.as_ref(),
+        }
+    }
+}
+
+/// Deploy `amount` idle base tokens into the lending reserve
+pub fn deploy_to_adapter(ctx: Context<AdapterOperation>, amount: u64) -> Result<()> {
+    let accounts = ctx.accounts;
+    require!(
+        accounts.vault.is_operator(&accounts.authority.key()),
+        VaultError::Unauthorized
+    );
+    require!(accounts.vault.is_active(), VaultError::StrategyDisabled);
+    require!(
+        accounts.approved_lending_program.is_some(),
+        VaultError::LendingProgramNotApproved
+    );
+    require!(amount > 0, VaultError::InvalidAmount);
+    require!(
+        accounts.vault.total_assets >= amount && accounts.vault_token_account.amount >= amount,
+        VaultError::InsufficientAssets
+    );
+
+    let vault_id_bytes = accounts.vault.vault_id.to_le_bytes();
+    let seeds = &[
+        b"vault".as_ref(),
+        vault_id_bytes.as_ref(),
+        &[accounts.vault.bump],
+    ];
+    let signer_seeds = &[&seeds[..]];
+
+    let adapter = accounts.adapter();
+    adapter.deposit(amount, signer_seeds)?;
+    let adapter_assets = adapter.balance()?;
+
+    let now = Clock::get()?.unix_timestamp;
+    let vault = &mut accounts.vault;
+    vault.total_assets = vault
+        .total_assets
+        .checked_sub(amount)
+        .ok_or(VaultError::MathOverflow)?;
+    vault.adapter_assets = adapter_assets;
+
+    let yield_adapter = &mut accounts.yield_adapter;
+    yield_adapter.deployed = yield_adapter
+        .deployed
+        .checked_add(amount)
+        .ok_or(VaultError::MathOverflow)?;
+    yield_adapter.last_synced_at = now;
+
+    msg!(
+        "Deployed {} to adapter, position worth {}",
+        amount,
+        adapter_assets
+    );
+
+    emit!(AdapterDeployed {
+        vault: vault.key(),
+        amount,
+        adapter_assets,
+        total_assets: vault.total_assets,
+        timestamp: now,
+    });
+    Ok(())
+}
+
+/// Recall at least `amount` base tokens (or the whole position) back into the vault
+pub fn recall_from_adapter(ctx: Context<AdapterOperation>, amount: u64) -> Result<()> {
+    let accounts = ctx.accounts;
+    require!(
+        accounts.vault.is_operator(&accounts.authority.key()),
+        VaultError::Unauthorized
+    );
+    require!(amount > 0, VaultError::InvalidAmount);
+
+    let received = recall(accounts, amount)?;
+    msg!("Recalled {} from adapter", received);
+    Ok(())
+}
+
+/// Re-mark `vault.adapter_assets` from the reserve. Manager or keeper.
+///
+/// The mark is only as fresh as the reserve's last refresh, so operators
+/// refresh it in the same transaction; nobody else may take the mark.
+pub fn sync_adapter(ctx: Context<AdapterOperation>) -> Result<()> {
+    let accounts = ctx.accounts;
+    require!(
+        accounts.vault.is_operator(&accounts.authority.key()),
+        VaultError::Unauthorized
+    );
+    let adapter_assets = accounts.adapter().balance()?;
+    let now = Clock::get()?.unix_timestamp;
+    let mark = NavMark::of(&accounts.vault)?;
+
+    let vault = &mut accounts.vault;
+    let previous_assets = vault.adapter_assets;
+    vault.adapter_assets = adapter_assets;
+    accounts.yield_adapter.last_synced_at = now;
+
+    msg!("Adapter assets: {} -> {}", previous_assets, adapter_assets);
+
+    emit!(AdapterSynced {
+        vault: vault.key(),
+        previous_assets,
+        adapter_assets,
+        timestamp: now,
+    });
+
+    mark.recognize_loss(vault, LossSource::Adapter)?;
+    Ok(())
+}
+
+/// Redeem enough collateral for `amount` base tokens and book what came back
+pub(crate) fn recall(accounts: &mut AdapterOperation, amount: u64) -> Result<u64> {
+    let vault_id_bytes = accounts.vault.vault_id.to_le_bytes();
+    let seeds = &[
+        b"vault".as_ref(),
+        vault_id_bytes.as_ref(),
+        &[accounts.vault.bump],
+    ];
+    let signer_seeds = &[&seeds[..]];
+
+    let balance_before = accounts.vault_token_account.amount;
+
+    let adapter = accounts.adapter();
+    let collateral = adapter.collateral_for(amount)?;
+    require!(collateral > 0, VaultError::InsufficientAssets);
+    adapter.withdraw(collateral, signer_seeds)?;
+    let adapter_assets = adapter.balance()?;
+
+    accounts.vault_token_account.reload()?;
+    let received = accounts
+        .vault_token_account
+        .amount
+        .checked_sub(balance_before)
+        .ok_or(VaultError::MathOverflow)?;
+
+    let now = Clock::get()?.unix_timestamp;
+    let vault = &mut accounts.vault;
+    vault.total_assets = vault
+        .total_assets
+        .checked_add(received)
+        .ok_or(VaultError::MathOverflow)?;
+    vault.adapter_assets = adapter_assets;
+
+    let yield_adapter = &mut accounts.yield_adapter;
+    yield_adapter.deployed = yield_adapter.deployed.saturating_sub(received);
+    yield_adapter.last_synced_at = now;
+
+    emit!(AdapterRecalled {
+        vault: vault.key(),
+        collateral,
+        received,
+        adapter_assets,
+        total_assets: vault.total_assets,
+        timestamp: now,
+    });
+    Ok(received)
+}
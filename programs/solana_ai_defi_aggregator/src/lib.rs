@@ -1,5 +1,6 @@
 use anchor_lang::prelude::*;
 
+pub mod adapters;
 pub mod error;
 pub mod events;
 pub mod instructions;
@@ -44,6 +45,44 @@ pub mod solana_ai_defi_aggregator {
         )
     }
 
+    // ===========================================
+    // Account Migrations (permissionless, payer covers the extra rent)
+    // ===========================================
+
+    /// Remaining accounts: the vault's claim escrow
+    pub fn migrate_vault(ctx: Context<MigrateAccount>) -> Result<()> {
+        instructions::migrate::migrate_vault(ctx)
+    }
+
+    pub fn migrate_global_state(ctx: Context<MigrateAccount>) -> Result<()> {
+        instructions::migrate::migrate_global_state(ctx)
+    }
+
+    pub fn migrate_user_state(ctx: Context<MigrateAccount>) -> Result<()> {
+        instructions::migrate::migrate_user_state(ctx)
+    }
+
+    pub fn migrate_strategy(ctx: Context<MigrateAccount>) -> Result<()> {
+        instructions::migrate::migrate_strategy(ctx)
+    }
+
+    pub fn migrate_position(ctx: Context<MigrateAccount>) -> Result<()> {
+        instructions::migrate::migrate_position(ctx)
+    }
+
+    pub fn migrate_user_strategy(ctx: Context<MigrateAccount>) -> Result<()> {
+        instructions::migrate::migrate_user_strategy(ctx)
+    }
+
+    pub fn migrate_execution(ctx: Context<MigrateAccount>) -> Result<()> {
+        instructions::migrate::migrate_execution(ctx)
+    }
+
+    /// Push an oracle price (USD, 6 decimals) for a mint. Admin only.
+    pub fn update_price_feed(ctx: Context<UpdatePriceFeed>, price: u64) -> Result<()> {
+        instructions::update_price_feed::update_price_feed_handler(ctx, price)
+    }
+
     // ===========================================
     // Jupiter Swap Instruction
     // ===========================================
@@ -76,18 +115,101 @@ pub mod solana_ai_defi_aggregator {
     // ===========================================
     //
     //
+    pub fn initialize_vault_registry(ctx: Context<InitializeVaultRegistry>) -> Result<()> {
+        instructions::vault::initialize_vault_registry(ctx)
+    }
+
     pub fn initialize_vault(ctx: Context<InitializeVault>, performance_fee_bps: u16) -> Result<()> {
         instructions::vault::initialize_vault(ctx, performance_fee_bps)
     }
 
-    pub fn deposit(ctx: Context<Deposit>, amount: u64) -> Result<()> {
-        instructions::vault::deposit(ctx, amount)
+    /// Deposit tokens for shares; `proof` is only read by merkle-gated vaults
+    pub fn deposit(ctx: Context<Deposit>, amount: u64, proof: Vec<[u8; 32]>) -> Result<()> {
+        instructions::vault::deposit(ctx, amount, proof)
     }
 
     pub fn withdraw(ctx: Context<Withdraw>, shares: u64) -> Result<()> {
         instructions::vault::withdraw(ctx, shares)
     }
 
+    /// Mint an exact amount of shares, paying at most `max_assets_in`
+    pub fn mint(
+        ctx: Context<Deposit>,
+        shares: u64,
+        max_assets_in: u64,
+        proof: Vec<[u8; 32]>,
+    ) -> Result<()> {
+        instructions::vault::mint(ctx, shares, max_assets_in, proof)
+    }
+
+    /// Withdraw an exact amount of tokens, burning at most `max_shares_in`
+    pub fn redeem_assets(ctx: Context<Withdraw>, amount: u64, max_shares_in: u64) -> Result<()> {
+        instructions::vault::redeem_assets(ctx, amount, max_shares_in)
+    }
+
+    /// Burn shares for a pro-rata slice of every vault token account.
+    /// Remaining accounts: (vault token account, user token account) pairs.
+    pub fn withdraw_in_kind<'info>(
+        ctx: Context<'_, '_, 'info, 'info, WithdrawInKind<'info>>,
+        shares: u64,
+    ) -> Result<()> {
+        instructions::vault::withdraw_in_kind(ctx, shares)
+    }
+
+    /// Lock shares in escrow and join the open withdrawal epoch
+    pub fn request_withdraw(ctx: Context<RequestWithdraw>, shares: u64) -> Result<()> {
+        instructions::vault::request_withdraw(ctx, shares)
+    }
+
+    /// Settle the open withdrawal epoch at a single share price
+    pub fn settle_withdraw_epoch(ctx: Context<SettleWithdrawEpoch>) -> Result<()> {
+        instructions::vault::settle_withdraw_epoch(ctx)
+    }
+
+    /// Hand back the shares of an unsettled request once the vault is shut down.
+    /// Permissionless.
+    pub fn cancel_withdraw_request(ctx: Context<CancelWithdrawRequest>) -> Result<()> {
+        instructions::vault::cancel_withdraw_request(ctx)
+    }
+
+    pub fn claim_withdrawal(ctx: Context<ClaimWithdrawal>, epoch: u64) -> Result<()> {
+        instructions::vault::claim_withdrawal(ctx, epoch)
+    }
+
+    pub fn max_deposit(ctx: Context<MaxDeposit>) -> Result<u64> {
+        instructions::vault::max_deposit(ctx)
+    }
+
+    pub fn max_withdraw(ctx: Context<MaxWithdraw>) -> Result<u64> {
+        instructions::vault::max_withdraw(ctx)
+    }
+
+    pub fn vault_apy(ctx: Context<VaultApy>) -> Result<ApyView> {
+        instructions::vault::vault_apy(ctx)
+    }
+
+    /// Fails if bookkeeping disagrees with the share supply or token balance
+    pub fn vault_health(ctx: Context<VaultHealth>) -> Result<VaultHealthView> {
+        instructions::vault::vault_health(ctx)
+    }
+
+    /// Reconcile `total_assets` with the vault token balance. Manager or keeper.
+    pub fn sync_vault(ctx: Context<SyncVault>) -> Result<()> {
+        instructions::vault::sync_vault(ctx)
+    }
+
+    pub fn configure_nav_history(
+        ctx: Context<ConfigureNavHistory>,
+        interval_secs: i64,
+    ) -> Result<()> {
+        instructions::vault::configure_nav_history(ctx, interval_secs)
+    }
+
+    /// Crank: append a NAV snapshot once the interval has passed
+    pub fn record_nav_snapshot(ctx: Context<RecordNavSnapshot>) -> Result<()> {
+        instructions::vault::record_nav_snapshot(ctx)
+    }
+
     pub fn execute_jupiter_strategy(
         ctx: Context<ExecuteJupiterStrategy>,
         strategy_type: JupiterStrategyType,
@@ -106,6 +228,99 @@ pub mod solana_ai_defi_aggregator {
         )
     }
 
+    /// Swap an overweight mint into an underweight one, toward the target weights.
+    /// Remaining accounts: `holdings` (vault token account, price feed) pairs,
+    /// then the Jupiter swap accounts.
+    pub fn rebalance<'info>(
+        ctx: Context<'_, '_, 'info, 'info, Rebalance<'info>>,
+        amount: u64,
+        min_output: u64,
+        holdings: u8,
+        swap_ix_data: Vec<u8>,
+        accounts_meta: Vec<Pubkey>,
+    ) -> Result<()> {
+        instructions::vault::rebalance(
+            ctx,
+            amount,
+            min_output,
+            holdings,
+            swap_ix_data,
+            accounts_meta,
+        )
+    }
+
+    pub fn set_target_weights(
+        ctx: Context<SetTargetWeights>,
+        target_weights: Vec<state::TargetWeight>,
+        threshold_bps: u16,
+        max_slippage_bps: u16,
+    ) -> Result<()> {
+        instructions::vault::set_target_weights(
+            ctx,
+            target_weights,
+            threshold_bps,
+            max_slippage_bps,
+        )
+    }
+
+    /// Approve a lending program for yield adapters. Admin only.
+    pub fn approve_lending_program(
+        ctx: Context<ApproveLendingProgram>,
+        program: Pubkey,
+    ) -> Result<()> {
+        instructions::vault::approve_lending_program(ctx, program)
+    }
+
+    /// Revoke a lending program approval. Admin only.
+    pub fn revoke_lending_program(ctx: Context<RevokeLendingProgram>) -> Result<()> {
+        instructions::vault::revoke_lending_program(ctx)
+    }
+
+    pub fn register_yield_adapter(ctx: Context<RegisterYieldAdapter>) -> Result<()> {
+        instructions::vault::register_yield_adapter(ctx)
+    }
+
+    pub fn deploy_to_adapter(ctx: Context<AdapterOperation>, amount: u64) -> Result<()> {
+        instructions::vault::deploy_to_adapter(ctx, amount)
+    }
+
+    pub fn recall_from_adapter(ctx: Context<AdapterOperation>, amount: u64) -> Result<()> {
+        instructions::vault::recall_from_adapter(ctx, amount)
+    }
+
+    pub fn sync_adapter(ctx: Context<AdapterOperation>) -> Result<()> {
+        instructions::vault::sync_adapter(ctx)
+    }
+
+    pub fn add_child_vault(ctx: Context<AddChildVault>) -> Result<()> {
+        instructions::vault::add_child_vault(ctx)
+    }
+
+    pub fn remove_child_vault(ctx: Context<RemoveChildVault>) -> Result<()> {
+        instructions::vault::remove_child_vault(ctx)
+    }
+
+    /// Deposit idle base tokens into a child vault; `proof` is forwarded to merkle-gated children
+    pub fn allocate_to_child(
+        ctx: Context<ChildVaultOperation>,
+        amount: u64,
+        proof: Vec<[u8; 32]>,
+    ) -> Result<()> {
+        instructions::vault::allocate_to_child(ctx, amount, proof)
+    }
+
+    pub fn withdraw_from_child(ctx: Context<ChildVaultOperation>, shares: u64) -> Result<()> {
+        instructions::vault::withdraw_from_child(ctx, shares)
+    }
+
+    /// Re-mark child vault shares.
+    /// Remaining accounts: (child allocation, child vault, share account) per child.
+    pub fn sync_child_vaults<'info>(
+        ctx: Context<'_, '_, 'info, 'info, SyncChildVaults<'info>>,
+    ) -> Result<()> {
+        instructions::vault::sync_child_vaults(ctx)
+    }
+
     pub fn create_strategy(
         ctx: Context<CreateStrategy>,
         strategy_id: u64,
@@ -126,12 +341,38 @@ pub mod solana_ai_defi_aggregator {
         )
     }
 
-    pub fn buy_strategy(ctx: Context<BuyStrategy>) -> Result<()> {
-        instructions::marketplace::buy_strategy(ctx)
+    pub fn buy_strategy(
+        ctx: Context<BuyStrategy>,
+        max_price: u64,
+        expected_payment_mint: Pubkey,
+    ) -> Result<()> {
+        instructions::marketplace::buy_strategy(ctx, max_price, expected_payment_mint)
+    }
+
+    /// Pay for another subscription period
+    pub fn renew_subscription(
+        ctx: Context<RenewSubscription>,
+        max_price: u64,
+        expected_payment_mint: Pubkey,
+    ) -> Result<()> {
+        instructions::marketplace::renew_subscription(ctx, max_price, expected_payment_mint)
     }
 
-    pub fn execute_strategy(ctx: Context<ExecuteStrategy>, input_amount: u64) -> Result<()> {
-        instructions::marketplace::execute_strategy(ctx, input_amount)
+    /// Swap the executor's tokens through Jupiter with the pinned parameters
+    pub fn execute_strategy(
+        ctx: Context<ExecuteStrategy>,
+        input_amount: u64,
+        min_output: u64,
+        swap_ix_data: Vec<u8>,
+        accounts_meta: Vec<AccountMetaData>,
+    ) -> Result<()> {
+        instructions::marketplace::execute_strategy(
+            ctx,
+            input_amount,
+            min_output,
+            swap_ix_data,
+            accounts_meta,
+        )
     }
 
     pub fn update_strategy_status(
@@ -142,6 +383,54 @@ pub mod solana_ai_defi_aggregator {
         instructions::marketplace::update_strategy_status(ctx, is_active, new_price)
     }
 
+    /// Price per `subscription_period` seconds (0 = one-time purchase)
+    pub fn set_subscription_terms(
+        ctx: Context<UpdateStrategy>,
+        subscription_period: i64,
+        grace_period: i64,
+    ) -> Result<()> {
+        instructions::marketplace::set_subscription_terms(ctx, subscription_period, grace_period)
+    }
+
+    /// Creator's cut (bps) of realized profit on each execution
+    pub fn set_profit_share(ctx: Context<UpdateStrategy>, profit_share_bps: u16) -> Result<()> {
+        instructions::marketplace::set_profit_share(ctx, profit_share_bps)
+    }
+
+    pub fn set_strategy_payment_mint(ctx: Context<SetStrategyPaymentMint>) -> Result<()> {
+        instructions::marketplace::set_strategy_payment_mint(ctx)
+    }
+
+    /// Publish a new parameter version; buyers stay pinned to theirs
+    pub fn update_strategy_parameters(
+        ctx: Context<UpdateStrategyParameters>,
+        parameters: state::StrategyParameters,
+    ) -> Result<()> {
+        instructions::marketplace::update_strategy_parameters(ctx, parameters)
+    }
+
+    /// Move a purchase onto the strategy's latest parameters
+    pub fn adopt_latest_parameters(ctx: Context<AdoptStrategyVersion>) -> Result<()> {
+        instructions::marketplace::adopt_latest_parameters(ctx)
+    }
+
+    /// Protocol commission (bps) on strategy sales, paid to the fee treasury
+    pub fn set_marketplace_commission(
+        ctx: Context<SetMarketplaceCommission>,
+        commission_bps: u16,
+    ) -> Result<()> {
+        instructions::marketplace::set_marketplace_commission(ctx, commission_bps)
+    }
+
+    /// Oracle/agent allowed to record execution results
+    pub fn set_execution_attestor(
+        ctx: Context<SetExecutionAttestor>,
+        attestor: Pubkey,
+    ) -> Result<()> {
+        instructions::marketplace::set_execution_attestor(ctx, attestor)
+    }
+
+    /// Attestor-only: record the measured result of an execution
     pub fn record_execution_result(
         ctx: Context<RecordExecutionResult>,
         output_amount: u64,
@@ -151,8 +440,101 @@ pub mod solana_ai_defi_aggregator {
         instructions::marketplace::record_execution_result(ctx, output_amount, profit, success)
     }
 
-    pub fn set_strategy_enabled(ctx: Context<SetStrategyEnabled>, enabled: bool) -> Result<()> {
-        instructions::vault::set_strategy_enabled(ctx, enabled)
+    /// Pause or resume strategies (Active / Paused). The guardian may only pause.
+    pub fn set_vault_status(
+        ctx: Context<SetVaultStatus>,
+        status: state::VaultLifecycle,
+    ) -> Result<()> {
+        instructions::vault::set_vault_status(ctx, status)
+    }
+
+    /// Close a vault without shares, sweep its dust to the fee recipient and
+    /// return its rent to the manager. Remaining accounts: other vault-owned
+    /// token accounts to close, and fee recipient accounts receiving dust.
+    pub fn close_vault<'info>(ctx: Context<'_, '_, 'info, 'info, CloseVault<'info>>) -> Result<()> {
+        instructions::vault::close_vault(ctx)
+    }
+
+    /// Permanently stop strategies and deposits; exits switch to in-kind
+    pub fn emergency_shutdown(ctx: Context<EmergencyShutdown>) -> Result<()> {
+        instructions::vault::emergency_shutdown(ctx)
+    }
+
+    /// Crank: recall the whole adapter position of a shut-down vault
+    pub fn unwind_adapter(ctx: Context<AdapterOperation>) -> Result<()> {
+        instructions::vault::unwind_adapter(ctx)
+    }
+
+    pub fn set_deposit_limits(
+        ctx: Context<SetDepositLimits>,
+        deposit_cap: u64,
+        user_deposit_cap: u64,
+        min_deposit: u64,
+    ) -> Result<()> {
+        instructions::vault::set_deposit_limits(ctx, deposit_cap, user_deposit_cap, min_deposit)
+    }
+
+    pub fn set_lockup(
+        ctx: Context<SetDepositLimits>,
+        lock_period: i64,
+        early_exit_penalty_bps: u16,
+    ) -> Result<()> {
+        instructions::vault::set_lockup(ctx, lock_period, early_exit_penalty_bps)
+    }
+
+    /// Move a position's locked shares to its owner, less the early-release penalty
+    pub fn release_locked_shares(ctx: Context<ReleaseLockedShares>) -> Result<()> {
+        instructions::vault::release_locked_shares(ctx)
+    }
+
+    pub fn set_deposit_gate(
+        ctx: Context<SetDepositLimits>,
+        deposit_gate: state::DepositGate,
+    ) -> Result<()> {
+        instructions::vault::set_deposit_gate(ctx, deposit_gate)
+    }
+
+    pub fn set_merkle_root(ctx: Context<SetDepositLimits>, merkle_root: [u8; 32]) -> Result<()> {
+        instructions::vault::set_merkle_root(ctx, merkle_root)
+    }
+
+    pub fn add_to_allowlist(ctx: Context<AddToAllowlist>, depositor: Pubkey) -> Result<()> {
+        instructions::vault::add_to_allowlist(ctx, depositor)
+    }
+
+    pub fn remove_from_allowlist(ctx: Context<RemoveFromAllowlist>) -> Result<()> {
+        instructions::vault::remove_from_allowlist(ctx)
+    }
+
+    pub fn set_performance_fee(ctx: Context<SetVaultFees>, performance_fee_bps: u16) -> Result<()> {
+        instructions::vault::set_performance_fee(ctx, performance_fee_bps)
+    }
+
+    pub fn set_fee_recipient(ctx: Context<SetVaultFees>, fee_recipient: Pubkey) -> Result<()> {
+        instructions::vault::set_fee_recipient(ctx, fee_recipient)
+    }
+
+    /// Mint the performance fee above the high-water mark to the fee recipient.
+    /// Manager or keeper.
+    pub fn crystallize_fees(ctx: Context<CrystallizeFees>) -> Result<()> {
+        instructions::vault::crystallize_fees(ctx)
+    }
+
+    /// Set the risk limits enforced on strategy executions
+    pub fn set_risk_policy(ctx: Context<SetRiskPolicy>, limits: state::RiskLimits) -> Result<()> {
+        instructions::vault::set_risk_policy(ctx, limits)
+    }
+
+    pub fn set_manager(ctx: Context<SetManager>, new_manager: Pubkey) -> Result<()> {
+        instructions::vault::set_manager(ctx, new_manager)
+    }
+
+    pub fn set_keeper(ctx: Context<SetVaultRole>, new_keeper: Pubkey) -> Result<()> {
+        instructions::vault::set_keeper(ctx, new_keeper)
+    }
+
+    pub fn set_guardian(ctx: Context<SetVaultRole>, new_guardian: Pubkey) -> Result<()> {
+        instructions::vault::set_guardian(ctx, new_guardian)
     }
 
     #[cfg(feature = "test")]
@@ -0,0 +1,15 @@
+use anchor_lang::prelude::*;
+
+// Approval for one depositor of an allowlist-gated vault
+#[account]
+#[derive(InitSpace)]
+pub struct AllowlistEntry {
+    /// Vault the approval applies to
+    pub vault: Pubkey,
+
+    /// Approved depositor wallet
+    pub depositor: Pubkey,
+
+    /// Bump for PDA
+    pub bump: u8,
+}
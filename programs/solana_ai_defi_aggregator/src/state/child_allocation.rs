@@ -0,0 +1,21 @@
+use anchor_lang::prelude::*;
+
+// A child vault whose shares a parent (fund-of-funds) vault may hold
+#[account]
+#[derive(InitSpace)]
+pub struct ChildAllocation {
+    /// Parent vault holding the shares
+    pub parent: Pubkey,
+
+    /// Child vault the shares belong to
+    pub child: Pubkey,
+
+    /// Parent-owned associated account for the child's share mint
+    pub share_account: Pubkey,
+
+    /// Bump for PDA
+    pub bump: u8,
+}
+
+/// Children one parent may hold; `sync_child_vaults` marks them all in one transaction
+pub const MAX_CHILD_VAULTS: u8 = 8;
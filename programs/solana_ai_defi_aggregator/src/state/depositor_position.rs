@@ -0,0 +1,120 @@
+use crate::error::vault_error::VaultError;
+use crate::state::Versioned;
+use anchor_lang::prelude::*;
+
+// Per-depositor bookkeeping for a single vault
+#[account]
+#[derive(InitSpace)]
+pub struct DepositorPosition {
+    /// Vault the position belongs to
+    pub vault: Pubkey,
+
+    /// Depositor wallet
+    pub owner: Pubkey,
+
+    /// Net tokens deposited (deposits minus withdrawals), used for per-user caps
+    pub deposited: u64,
+
+    /// Bump for PDA
+    pub bump: u8,
+
+    /// Layout version (see `Versioned`); new fields go after it
+    pub version: u8,
+
+    /// When the last of `share_locks` expires
+    pub lock_until: i64,
+
+    /// Shares each deposit minted into escrow and when they unlock. Escrowed
+    /// shares no unexpired lock covers are free, so a deposit never re-locks
+    /// shares already in escrow, except that once every slot is taken a
+    /// deposit joins the newest lock and extends it.
+    #[max_len(MAX_SHARE_LOCKS)]
+    pub share_locks: Vec<ShareLock>,
+}
+
+/// Escrowed shares a deposit minted and when they unlock
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq, InitSpace)]
+pub struct ShareLock {
+    pub shares: u64,
+    pub until: i64,
+}
+
+/// Locks one position keeps open at once
+pub const MAX_SHARE_LOCKS: usize = 8;
+
+impl DepositorPosition {
+    /// Fill in the identity fields the first time the position is used
+    pub fn ensure_initialized(&mut self, vault: Pubkey, owner: Pubkey, bump: u8) {
+        if self.owner == Pubkey::default() {
+            self.vault = vault;
+            self.owner = owner;
+            self.deposited = 0;
+            self.bump = bump;
+            self.version = Self::CURRENT_VERSION;
+            self.lock_until = 0;
+            self.share_locks.clear();
+        }
+    }
+
+    pub fn is_locked(&self, now: i64) -> bool {
+        now < self.lock_until
+    }
+
+    /// Lock `shares` just minted into escrow until `now + lock_period`,
+    /// dropping the locks that have expired
+    pub fn lock_shares(&mut self, shares: u64, now: i64, lock_period: i64) -> Result<()> {
+        let until = now
+            .checked_add(lock_period)
+            .ok_or(VaultError::MathOverflow)?;
+        self.share_locks.retain(|lock| lock.until > now);
+        if self.share_locks.len() < MAX_SHARE_LOCKS {
+            self.share_locks.push(ShareLock { shares, until });
+        } else if let Some(newest) = self.share_locks.last_mut() {
+            newest.shares = newest
+                .shares
+                .checked_add(shares)
+                .ok_or(VaultError::MathOverflow)?;
+            newest.until = until;
+        }
+        self.lock_until = self.lock_until.max(until);
+        Ok(())
+    }
+
+    /// Escrowed shares still under a lock at `now`
+    pub fn locked_shares(&self, now: i64) -> u64 {
+        self.share_locks
+            .iter()
+            .filter(|lock| lock.until > now)
+            .fold(0, |total, lock| total.saturating_add(lock.shares))
+    }
+
+    /// Forget every lock once the escrow is emptied
+    pub fn clear_locks(&mut self) {
+        self.share_locks.clear();
+        self.lock_until = 0;
+    }
+}
+
+impl Versioned for DepositorPosition {
+    const CURRENT_VERSION: u8 = 2;
+
+    fn version(&self) -> u8 {
+        self.version
+    }
+
+    fn upgrade(&mut self) -> Result<()> {
+        // v1: version byte, lock_until (zero = unlocked)
+        if self.version < 2 {
+            // v2: share_locks. The old lock covered the whole escrow.
+            self.share_locks.clear();
+            if self.lock_until > 0 {
+                self.share_locks.push(ShareLock {
+                    shares: u64::MAX,
+                    until: self.lock_until,
+                });
+            }
+        }
+        self.version = Self::CURRENT_VERSION;
+        Ok(())
+    }
+}
@@ -1,12 +1,39 @@
+use crate::state::Versioned;
 use anchor_lang::prelude::*;
 
 #[account]
+#[derive(InitSpace)]
 pub struct GlobalState {
     pub admin: Pubkey,
     pub fee_rate: u16,
     pub bump: u8,
+
+    /// Layout version (see `Versioned`); new fields go after it
+    pub version: u8,
+
+    /// Protocol commission on marketplace sales, in bps, paid to the fee treasury
+    pub marketplace_commission_bps: u16,
+
+    /// Oracle/agent allowed to record strategy execution results
+    /// (default = nobody, results can't be recorded)
+    pub execution_attestor: Pubkey,
 }
 
-impl GlobalState {
-    pub const LEN: usize = 32 + 1 + 2 + 1;
+/// Upper bound on the marketplace commission (30%)
+pub const MAX_MARKETPLACE_COMMISSION_BPS: u16 = 3_000;
+
+impl Versioned for GlobalState {
+    const CURRENT_VERSION: u8 = 3;
+
+    fn version(&self) -> u8 {
+        self.version
+    }
+
+    fn upgrade(&mut self) -> Result<()> {
+        // v1: version byte only
+        // v2: marketplace_commission_bps (zero = no commission)
+        // v3: execution_attestor (default = recording disabled until the admin sets one)
+        self.version = Self::CURRENT_VERSION;
+        Ok(())
+    }
 }
@@ -1,15 +1,37 @@
+pub mod allowlist;
+pub mod child_allocation;
+pub mod depositor_position;
 pub mod global_state;
+pub mod nav_history;
+pub mod price_feed;
+pub mod risk_policy;
 pub mod strategy;
 pub mod strategy_execution;
+pub mod strategy_version;
 pub mod swap_context;
 pub mod user_state;
 pub mod user_strategy;
 pub mod vault;
+pub mod vault_registry;
+pub mod versioned;
+pub mod withdrawal;
+pub mod yield_adapter;
 
+pub use allowlist::*;
+pub use child_allocation::*;
+pub use depositor_position::*;
 pub use global_state::*;
+pub use nav_history::*;
+pub use price_feed::*;
+pub use risk_policy::*;
 pub use strategy::*;
 pub use strategy_execution::*;
+pub use strategy_version::*;
 pub use swap_context::*;
 pub use user_state::*;
 pub use user_strategy::*;
 pub use vault::*;
+pub use vault_registry::*;
+pub use versioned::*;
+pub use withdrawal::*;
+pub use yield_adapter::*;
@@ -0,0 +1,115 @@
+use crate::error::vault_error::VaultError;
+use anchor_lang::prelude::*;
+
+/// Snapshots kept per vault. At a daily interval that is three months of history;
+/// the interval must stay under NAV_HISTORY_LEN / 30 days for a 30d APY.
+pub const NAV_HISTORY_LEN: usize = 96;
+
+pub const SECONDS_PER_DAY: i64 = 86_400;
+pub const SECONDS_PER_YEAR: i64 = 365 * SECONDS_PER_DAY;
+
+#[derive(AnchorSerialize, AnchorDeserialize, InitSpace, Clone, Copy, Debug, Default)]
+pub struct NavSnapshot {
+    pub timestamp: i64,
+
+    /// Vault::share_price at the time (6 decimals)
+    pub share_price: u64,
+
+    /// Vault::nav at the time
+    pub total_assets: u64,
+}
+
+// Fixed-size ring buffer of vault NAV snapshots, written by a permissionless
+// crank at most once per `interval_secs`. Annualized returns are read from the
+// share price, so deposits and withdrawals don't show up as performance.
+#[account]
+#[derive(InitSpace)]
+pub struct NavHistory {
+    /// Vault this history belongs to
+    pub vault: Pubkey,
+
+    /// Minimum seconds between two snapshots
+    pub interval_secs: i64,
+
+    /// Slot the next snapshot is written to
+    pub head: u16,
+
+    /// Number of slots holding a snapshot
+    pub len: u16,
+
+    pub snapshots: [NavSnapshot; NAV_HISTORY_LEN],
+
+    /// Bump for PDA
+    pub bump: u8,
+}
+
+impl NavHistory {
+    pub fn latest(&self) -> Option<&NavSnapshot> {
+        if self.len == 0 {
+            return None;
+        }
+        let index = (self.head as usize + NAV_HISTORY_LEN - 1) % NAV_HISTORY_LEN;
+        Some(&self.snapshots[index])
+    }
+
+    /// Whether the crank may write a snapshot at `now`
+    pub fn is_due(&self, now: i64) -> bool {
+        self.latest()
+            .is_none_or(|latest| now.saturating_sub(latest.timestamp) >= self.interval_secs)
+    }
+
+    pub fn push(&mut self, snapshot: NavSnapshot) {
+        self.snapshots[self.head as usize] = snapshot;
+        self.head = ((self.head as usize + 1) % NAV_HISTORY_LEN) as u16;
+        if (self.len as usize) < NAV_HISTORY_LEN {
+            self.len += 1;
+        }
+    }
+
+    /// Snapshots from newest to oldest
+    pub fn iter_newest(&self) -> impl Iterator<Item = &NavSnapshot> {
+        (1..=self.len as usize).map(move |back| {
+            &self.snapshots[(self.head as usize + NAV_HISTORY_LEN - back) % NAV_HISTORY_LEN]
+        })
+    }
+
+    /// Annualized share-price return over the last `window_secs`, in basis points.
+    ///
+    /// Measured from the newest snapshot at least `window_secs` old to
+    /// `share_price` at `now`, and scaled linearly to a year. None when the
+    /// history doesn't reach back that far yet.
+    pub fn annualized_return_bps(
+        &self,
+        window_secs: i64,
+        now: i64,
+        share_price: u64,
+    ) -> Result<Option<i64>> {
+        let Some(base) = self
+            .iter_newest()
+            .find(|snapshot| now.saturating_sub(snapshot.timestamp) >= window_secs)
+        else {
+            return Ok(None);
+        };
+        if base.share_price == 0 {
+            return Ok(None);
+        }
+
+        let elapsed = now.saturating_sub(base.timestamp);
+        let change = share_price as i128 - base.share_price as i128;
+
+        // change / base_price * 10_000 * year / elapsed
+        let apy = change
+            .checked_mul(10_000)
+            .and_then(|value| value.checked_mul(SECONDS_PER_YEAR as i128))
+            .ok_or(VaultError::MathOverflow)?
+            .checked_div(base.share_price as i128)
+            .and_then(|value| value.checked_div(elapsed as i128))
+            .ok_or(VaultError::MathOverflow)?;
+
+        require!(
+            apy >= i64::MIN as i128 && apy <= i64::MAX as i128,
+            VaultError::MathOverflow
+        );
+        Ok(Some(apy as i64))
+    }
+}
@@ -0,0 +1,69 @@
+use crate::error::risk_error::RiskError;
+use anchor_lang::prelude::*;
+
+// Admin-pushed USD price for a mint, used to value vault trades on-chain
+#[account]
+#[derive(InitSpace)]
+pub struct PriceFeed {
+    /// Mint this price is for
+    pub mint: Pubkey,
+
+    /// USD price of one whole token (6 decimals, 1_000_000 = $1)
+    pub price: u64,
+
+    /// Decimals of the mint
+    pub decimals: u8,
+
+    /// Time of the last update
+    pub updated_at: i64,
+
+    /// Bump for PDA
+    pub bump: u8,
+}
+
+impl PriceFeed {
+    /// Prices older than this are rejected
+    pub const MAX_AGE_SECS: i64 = 300;
+
+    pub fn ensure_fresh(&self, now: i64) -> Result<()> {
+        require!(self.price > 0, RiskError::InvalidPrice);
+        require!(
+            now.saturating_sub(self.updated_at) <= Self::MAX_AGE_SECS,
+            RiskError::StalePrice
+        );
+        Ok(())
+    }
+
+    /// USD value (6 decimals) of `amount` base units of this feed's mint
+    pub fn value_of(&self, amount: u64) -> Result<u128> {
+        let value = (amount as u128)
+            .checked_mul(self.price as u128)
+            .ok_or(RiskError::MathOverflow)?
+            .checked_div(10u128.pow(self.decimals as u32))
+            .ok_or(RiskError::MathOverflow)?;
+        Ok(value)
+    }
+
+    /// Convert `amount` of this feed's mint into units of `target`'s mint
+    ///
+    /// amount * price * 10^target.decimals / (target.price * 10^decimals), rounded down
+    pub fn quote(&self, amount: u64, target: &PriceFeed) -> Result<u64> {
+        require!(self.price > 0 && target.price > 0, RiskError::InvalidPrice);
+
+        let numerator = (amount as u128)
+            .checked_mul(self.price as u128)
+            .ok_or(RiskError::MathOverflow)?
+            .checked_mul(10u128.pow(target.decimals as u32))
+            .ok_or(RiskError::MathOverflow)?;
+        let denominator = (target.price as u128)
+            .checked_mul(10u128.pow(self.decimals as u32))
+            .ok_or(RiskError::MathOverflow)?;
+
+        let value = numerator
+            .checked_div(denominator)
+            .ok_or(RiskError::MathOverflow)?;
+        require!(value <= u64::MAX as u128, RiskError::MathOverflow);
+
+        Ok(value as u64)
+    }
+}
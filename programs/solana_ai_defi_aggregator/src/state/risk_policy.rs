@@ -0,0 +1,190 @@
+use crate::error::risk_error::RiskError;
+use anchor_lang::prelude::*;
+
+pub const MAX_POLICY_MINTS: usize = 8;
+pub const RISK_WINDOW_HOURS: usize = 24;
+
+/// Limits a manager sets on keeper-executed strategies. 0 / empty = no limit.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+pub struct RiskLimits {
+    pub max_trade_notional: u64,
+    pub max_daily_notional: u64,
+    pub max_daily_loss: u64,
+    pub min_execution_interval: i64,
+    pub allowed_input_mints: Vec<Pubkey>,
+    pub allowed_output_mints: Vec<Pubkey>,
+}
+
+// On-chain risk limits for a vault's strategy executions.
+//
+// Notional and loss are measured in the vault's base token. The 24h totals are
+// kept in hourly buckets, so the window rolls forward one hour at a time.
+#[account]
+#[derive(InitSpace)]
+pub struct RiskPolicy {
+    /// Vault this policy applies to
+    pub vault: Pubkey,
+
+    /// Max input per execution
+    pub max_trade_notional: u64,
+
+    /// Max input over the rolling 24h window
+    pub max_daily_notional: u64,
+
+    /// Max realized loss over the rolling 24h window; hitting it disables strategies
+    pub max_daily_loss: u64,
+
+    /// Minimum seconds between two executions
+    pub min_execution_interval: i64,
+
+    /// Mints a strategy may sell (empty = any)
+    #[max_len(MAX_POLICY_MINTS)]
+    pub allowed_input_mints: Vec<Pubkey>,
+
+    /// Mints a strategy may buy (empty = any)
+    #[max_len(MAX_POLICY_MINTS)]
+    pub allowed_output_mints: Vec<Pubkey>,
+
+    /// Time of the last execution
+    pub last_execution_at: i64,
+
+    /// Hour (unix time / 3600) the buckets were last rolled to
+    pub current_hour: i64,
+
+    /// Input notional per hour, indexed by hour % 24
+    pub notional_buckets: [u64; RISK_WINDOW_HOURS],
+
+    /// Realized loss per hour, indexed by hour % 24
+    pub loss_buckets: [u64; RISK_WINDOW_HOURS],
+
+    /// Bump for PDA
+    pub bump: u8,
+}
+
+impl RiskPolicy {
+    pub fn apply_limits(&mut self, limits: RiskLimits) -> Result<()> {
+        require!(
+            limits.allowed_input_mints.len() <= MAX_POLICY_MINTS
+                && limits.allowed_output_mints.len() <= MAX_POLICY_MINTS,
+            RiskError::TooManyMints
+        );
+        require!(
+            limits.min_execution_interval >= 0,
+            RiskError::InvalidRiskLimits
+        );
+
+        self.max_trade_notional = limits.max_trade_notional;
+        self.max_daily_notional = limits.max_daily_notional;
+        self.max_daily_loss = limits.max_daily_loss;
+        self.min_execution_interval = limits.min_execution_interval;
+        self.allowed_input_mints = limits.allowed_input_mints;
+        self.allowed_output_mints = limits.allowed_output_mints;
+        Ok(())
+    }
+
+    /// Advance the hourly buckets to `now`, clearing hours that fell out of the window
+    pub fn roll_window(&mut self, now: i64) {
+        let hour = now / 3600;
+        let elapsed = hour.saturating_sub(self.current_hour);
+
+        if elapsed >= RISK_WINDOW_HOURS as i64 {
+            self.notional_buckets = [0; RISK_WINDOW_HOURS];
+            self.loss_buckets = [0; RISK_WINDOW_HOURS];
+        } else {
+            for h in (self.current_hour + 1)..=hour {
+                let index = h.rem_euclid(RISK_WINDOW_HOURS as i64) as usize;
+                self.notional_buckets[index] = 0;
+                self.loss_buckets[index] = 0;
+            }
+        }
+
+        if hour > self.current_hour {
+            self.current_hour = hour;
+        }
+    }
+
+    pub fn window_notional(&self) -> u64 {
+        self.notional_buckets
+            .iter()
+            .fold(0u64, |acc, v| acc.saturating_add(*v))
+    }
+
+    pub fn window_loss(&self) -> u64 {
+        self.loss_buckets
+            .iter()
+            .fold(0u64, |acc, v| acc.saturating_add(*v))
+    }
+
+    /// Pre-trade checks: cooldown, mint allowlists and notional limits.
+    /// Call `roll_window` first.
+    pub fn check_execution(
+        &self,
+        now: i64,
+        input_mint: &Pubkey,
+        output_mint: &Pubkey,
+        notional: u64,
+    ) -> Result<()> {
+        if self.last_execution_at > 0 {
+            require!(
+                now >= self
+                    .last_execution_at
+                    .saturating_add(self.min_execution_interval),
+                RiskError::ExecutionCooldown
+            );
+        }
+
+        require!(
+            self.allowed_input_mints.is_empty() || self.allowed_input_mints.contains(input_mint),
+            RiskError::MintNotAllowed
+        );
+        require!(
+            self.allowed_output_mints.is_empty() || self.allowed_output_mints.contains(output_mint),
+            RiskError::MintNotAllowed
+        );
+
+        if self.max_trade_notional > 0 {
+            require!(
+                notional <= self.max_trade_notional,
+                RiskError::TradeNotionalExceeded
+            );
+        }
+
+        if self.max_daily_notional > 0 {
+            let window_notional = self
+                .window_notional()
+                .checked_add(notional)
+                .ok_or(RiskError::MathOverflow)?;
+            require!(
+                window_notional <= self.max_daily_notional,
+                RiskError::DailyNotionalExceeded
+            );
+        }
+
+        if self.max_daily_loss > 0 {
+            require!(
+                self.window_loss() < self.max_daily_loss,
+                RiskError::DailyLossLimitReached
+            );
+        }
+
+        Ok(())
+    }
+
+    /// Book an execution into the current hour
+    pub fn record_execution(&mut self, now: i64, notional: u64, loss: u64) -> Result<()> {
+        let index = self.current_hour.rem_euclid(RISK_WINDOW_HOURS as i64) as usize;
+        self.notional_buckets[index] = self.notional_buckets[index]
+            .checked_add(notional)
+            .ok_or(RiskError::MathOverflow)?;
+        self.loss_buckets[index] = self.loss_buckets[index]
+            .checked_add(loss)
+            .ok_or(RiskError::MathOverflow)?;
+        self.last_execution_at = now;
+        Ok(())
+    }
+
+    /// True once the realized loss in the window has reached the limit
+    pub fn loss_limit_hit(&self) -> bool {
+        self.max_daily_loss > 0 && self.window_loss() >= self.max_daily_loss
+    }
+}
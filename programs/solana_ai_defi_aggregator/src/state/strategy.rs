@@ -1,24 +1,41 @@
+use crate::error::StrategyError;
+use crate::state::{StrategyVersion, Versioned};
 use anchor_lang::prelude::*;
 
 #[account]
+#[derive(InitSpace)]
 pub struct Strategy {
-    pub creator: Pubkey,                // Strategy creator
-    pub strategy_id: u64,               // Unique ID
-    pub name: String,                   // Strategy name (max 50 chars)
-    pub description: String,            // Description (max 200 chars)
-    pub price: u64,                     // Price in USDC (lamports)
-    pub is_active: bool,                // Can be purchased?
-    pub total_purchases: u64,           // Times purchased
-    pub total_executions: u64,          // Times executed
-    pub total_profit: i64,              // Cumulative profit/loss
-    pub success_rate: u16,              // Success rate (0-10000 = 0-100%)
-    pub created_at: i64,                // Timestamp
-    pub strategy_type: StrategyType,    // Type of strategy
+    pub creator: Pubkey,  // Strategy creator
+    pub strategy_id: u64, // Unique ID
+    #[max_len(50)]
+    pub name: String, // Strategy name (max 50 chars)
+    #[max_len(200)]
+    pub description: String, // Description (max 200 chars)
+    pub price: u64,       // Price in USDC (lamports)
+    pub is_active: bool,  // Can be purchased?
+    pub total_purchases: u64, // Times purchased
+    pub total_executions: u64, // Times executed
+    pub total_profit: i64, // Cumulative profit/loss
+    pub success_rate: u16, // Success rate (0-10000 = 0-100%)
+    pub created_at: i64,  // Timestamp
+    pub strategy_type: StrategyType, // Type of strategy
     pub parameters: StrategyParameters, // Strategy config
     pub bump: u8,
+    pub version: u8, // Layout version (see `Versioned`); new fields go after it
+    pub payment_mint: Pubkey, // Mint buyers pay `price` in
+    pub subscription_period: i64, // Seconds one payment buys (0 = one-time purchase)
+    pub grace_period: i64, // Seconds an expired subscription can still execute
+    pub parameters_version: u32, // Version of `parameters` (0 = as created)
+    pub profit_share_bps: u16, // Creator's cut of each execution's realized profit
+    pub creator_earnings: u64, // Profit share paid to the creator, in USD (6 decimals) when paid
+    pub successful_executions: u64, // Recorded executions that succeeded
+    pub recorded_executions: u64, // Executions with a recorded result; success_rate is over these
 }
 
-#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq)]
+/// Upper bound on the creator's profit share (50%)
+pub const MAX_PROFIT_SHARE_BPS: u16 = 5_000;
+
+#[derive(AnchorSerialize, AnchorDeserialize, InitSpace, Clone, PartialEq)]
 pub enum StrategyType {
     Arbitrage,    // Buy low, sell high across DEXs
     YieldFarming, // Stake and earn yields
@@ -26,7 +43,7 @@ pub enum StrategyType {
     Custom,       // User-defined
 }
 
-#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+#[derive(AnchorSerialize, AnchorDeserialize, InitSpace, Clone)]
 pub struct StrategyParameters {
     pub input_token: Pubkey,     // Token to trade
     pub output_token: Pubkey,    // Target token
@@ -36,19 +53,59 @@ pub struct StrategyParameters {
 }
 
 impl Strategy {
-    pub const MAX_SIZE: usize = 8 + // discriminator
-        32 +  // creator
-        8 +   // strategy_id
-        (4 + 50) +  // name
-        (4 + 200) + // description
-        8 +   // price
-        1 +   // is_active
-        8 +   // total_purchases
-        8 +   // total_executions
-        8 +   // total_profit
-        2 +   // success_rate
-        8 +   // created_at
-        1 +   // strategy_type enum
-        (32 + 32 + 2 + 2 + 8) + // parameters
-        1; // bump
+    /// Reject a purchase or renewal whose terms moved past what the buyer
+    /// agreed to
+    pub fn check_terms(&self, max_price: u64, expected_payment_mint: Pubkey) -> Result<()> {
+        require_keys_eq!(
+            self.payment_mint,
+            expected_payment_mint,
+            StrategyError::PaymentMintMismatch
+        );
+        require!(self.price <= max_price, StrategyError::PriceAboveMaximum);
+        Ok(())
+    }
+
+    /// Parameters of version `pinned`. Older versions are read from
+    /// `superseded_by`, the version account published right after them.
+    pub fn parameters_at(
+        &self,
+        pinned: u32,
+        superseded_by: Option<&StrategyVersion>,
+    ) -> Result<StrategyParameters> {
+        if pinned == self.parameters_version {
+            return Ok(self.parameters.clone());
+        }
+        let next = superseded_by.ok_or(StrategyError::PinnedVersionMissing)?;
+        require!(
+            next.parameters_version == pinned.saturating_add(1),
+            StrategyError::PinnedVersionMissing
+        );
+        Ok(next.previous.clone())
+    }
+}
+
+impl Versioned for Strategy {
+    const CURRENT_VERSION: u8 = 6;
+
+    fn version(&self) -> u8 {
+        self.version
+    }
+
+    fn upgrade(&mut self) -> Result<()> {
+        // v1: version byte only
+        // v2: payment_mint (default = not for sale until the creator sets one)
+        // v3: subscription_period, grace_period (zero = one-time purchase)
+        // v4: parameters_version (zero = parameters never changed)
+        // v5: profit_share_bps, creator_earnings (zero = no profit share)
+        if self.version < 6 {
+            // v6: successful_executions, recorded_executions. Earlier rates
+            // were taken over total_executions, so rebuild the counters from
+            // that basis; they are exact from the next recorded result on.
+            self.recorded_executions = self.total_executions;
+            self.successful_executions =
+                (self.success_rate as u64 * self.total_executions + 5_000) / 10_000;
+        }
+        self.version = Self::CURRENT_VERSION;
+        Ok(())
+    }
 }
@@ -1,7 +1,9 @@
+use crate::state::Versioned;
 use anchor_lang::prelude::*;
 
 // Strategy execution record
 #[account]
+#[derive(InitSpace)]
 pub struct StrategyExecution {
     pub strategy: Pubkey,
     pub executor: Pubkey,
@@ -11,8 +13,20 @@ pub struct StrategyExecution {
     pub profit: i64,
     pub success: bool,
     pub bump: u8,
+    pub recorded_at: i64, // When the result was recorded (0 = pending)
+    pub version: u8,      // Layout version (see `Versioned`); new fields go after it
 }
 
-impl StrategyExecution {
-    pub const MAX_SIZE: usize = 8 + 32 + 32 + 8 + 8 + 8 + 8 + 1 + 1;
+impl Versioned for StrategyExecution {
+    const CURRENT_VERSION: u8 = 1;
+
+    fn version(&self) -> u8 {
+        self.version
+    }
+
+    fn upgrade(&mut self) -> Result<()> {
+        // v1: version byte (recorded_at came earlier; see `migrate_execution`)
+        self.version = Self::CURRENT_VERSION;
+        Ok(())
+    }
 }
@@ -0,0 +1,62 @@
+use crate::state::StrategyParameters;
+use anchor_lang::prelude::*;
+
+// Bits of `StrategyVersion::changed_fields`
+pub const PARAM_INPUT_TOKEN: u8 = 1 << 0;
+pub const PARAM_OUTPUT_TOKEN: u8 = 1 << 1;
+pub const PARAM_MIN_PROFIT: u8 = 1 << 2;
+pub const PARAM_MAX_SLIPPAGE: u8 = 1 << 3;
+pub const PARAM_EXECUTION_INTERVAL: u8 = 1 << 4;
+
+// One published change to a strategy's parameters. Version 0 is the set the
+// strategy was created with; version N is written when the creator replaces
+// version N-1, and keeps the replaced set in `previous`. The latest set lives
+// on the strategy itself, so buyers pinned to an older version read theirs
+// from the version that superseded it.
+#[account]
+#[derive(InitSpace)]
+pub struct StrategyVersion {
+    /// Strategy these parameters belong to
+    pub strategy: Pubkey,
+
+    /// Version number (1, 2, ...); matches the PDA seed
+    pub parameters_version: u32,
+
+    /// Parameters of version `parameters_version - 1`
+    pub previous: StrategyParameters,
+
+    /// Parameters of this version
+    pub parameters: StrategyParameters,
+
+    /// PARAM_* bits that differ between `previous` and `parameters`
+    pub changed_fields: u8,
+
+    /// When the version was published
+    pub created_at: i64,
+
+    /// Bump for PDA
+    pub bump: u8,
+}
+
+impl StrategyParameters {
+    /// PARAM_* bits of the fields that differ from `other`
+    pub fn diff(&self, other: &StrategyParameters) -> u8 {
+        let mut changed = 0;
+        if self.input_token != other.input_token {
+            changed |= PARAM_INPUT_TOKEN;
+        }
+        if self.output_token != other.output_token {
+            changed |= PARAM_OUTPUT_TOKEN;
+        }
+        if self.min_profit_bps != other.min_profit_bps {
+            changed |= PARAM_MIN_PROFIT;
+        }
+        if self.max_slippage_bps != other.max_slippage_bps {
+            changed |= PARAM_MAX_SLIPPAGE;
+        }
+        if self.execution_interval != other.execution_interval {
+            changed |= PARAM_EXECUTION_INTERVAL;
+        }
+        changed
+    }
+}
@@ -1,13 +1,28 @@
+use crate::state::Versioned;
 use anchor_lang::prelude::*;
 
 #[account]
+#[derive(InitSpace)]
 pub struct UserState {
     pub user: Pubkey,
     pub bump: u8,
     pub total_volume: u64,
     pub swaps: u64,
+
+    /// Layout version (see `Versioned`)
+    pub version: u8,
 }
 
-impl UserState {
-    pub const LEN: usize = 32 + 1 + 8 + 8;
+impl Versioned for UserState {
+    const CURRENT_VERSION: u8 = 1;
+
+    fn version(&self) -> u8 {
+        self.version
+    }
+
+    fn upgrade(&mut self) -> Result<()> {
+        // v1: version byte only
+        self.version = Self::CURRENT_VERSION;
+        Ok(())
+    }
 }
@@ -1,6 +1,8 @@
+use crate::state::Versioned;
 use anchor_lang::prelude::*;
 // User's purchased strategies
 #[account]
+#[derive(InitSpace)]
 pub struct UserStrategy {
     pub owner: Pubkey,
     pub strategy: Pubkey,
@@ -8,8 +10,33 @@ pub struct UserStrategy {
     pub times_executed: u64,
     pub total_profit: i64,
     pub bump: u8,
+    pub version: u8,     // Layout version (see `Versioned`); new fields go after it
+    pub expires_at: i64, // End of the paid subscription (0 = one-time purchase, never expires)
+    pub pinned_version: u32, // Strategy parameters version this buyer executes
+    pub last_executed_at: i64, // Last execution (0 = never); spaced by `execution_interval`
+    pub profit_share_paid: u64, // Profit share this buyer paid the creator, in USD (6 decimals) when paid
 }
 
 impl UserStrategy {
-    pub const MAX_SIZE: usize = 8 + 32 + 32 + 8 + 8 + 8 + 1;
+    /// Past the paid period and the strategy's grace period
+    pub fn is_expired(&self, now: i64, grace_period: i64) -> bool {
+        self.expires_at != 0 && now > self.expires_at.saturating_add(grace_period)
+    }
+}
+
+impl Versioned for UserStrategy {
+    const CURRENT_VERSION: u8 = 4;
+
+    fn version(&self) -> u8 {
+        self.version
+    }
+
+    fn upgrade(&mut self) -> Result<()> {
+        // v1: version byte, expires_at (zero = earlier purchases never expire)
+        // v2: pinned_version (zero = the parameters the strategy was created with)
+        // v3: last_executed_at (zero = no interval to wait out)
+        // v4: profit_share_paid
+        self.version = Self::CURRENT_VERSION;
+        Ok(())
+    }
 }
@@ -1,11 +1,45 @@
 use crate::error::vault_error::VaultError;
+use crate::error::MigrationError;
+use crate::state::{DepositorPosition, Versioned};
+use crate::utils::merkle;
 use anchor_lang::prelude::*;
 
+/// Who may deposit
+#[derive(AnchorSerialize, AnchorDeserialize, InitSpace, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DepositGate {
+    /// Anyone
+    Open,
+    /// Depositors with an `AllowlistEntry` PDA
+    Allowlist,
+    /// Depositors with a merkle proof against `merkle_root`
+    Merkle,
+}
+
+/// Vault lifecycle
+#[derive(AnchorSerialize, AnchorDeserialize, InitSpace, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum VaultLifecycle {
+    /// Strategies run, deposits and withdrawals open
+    Active,
+    /// Strategies halted; deposits and withdrawals stay open
+    Paused,
+    /// Permanent: no strategies or deposits, adapters unwind, exits are in kind
+    Shutdown,
+}
+
 #[account]
 #[derive(InitSpace)]
 pub struct Vault {
-    /// Authority that can execute strategies
-    pub authority: Pubkey,
+    /// Manager: sets vault parameters and rotates the other roles
+    pub manager: Pubkey,
+
+    /// Keeper (e.g. AI agent hot key): executes strategies, settles withdrawals
+    pub keeper: Pubkey,
+
+    /// Guardian: can only pause strategies or shut the vault down
+    pub guardian: Pubkey,
+
+    /// Receives the vault's performance fees
+    pub fee_recipient: Pubkey,
 
     /// The underlying token the vault accepts (e.g., USDC)
     pub token_mint: Pubkey,
@@ -13,7 +47,7 @@ pub struct Vault {
     /// The share token mint (vault shares)
     pub share_mint: Pubkey,
 
-    /// Total underlying tokens in the vault
+    /// Underlying tokens held by the vault itself (see `nav` for the total)
     pub total_assets: u64,
 
     /// Total shares issued
@@ -22,25 +56,284 @@ pub struct Vault {
     /// Bump for PDA
     pub bump: u8,
 
-    /// Strategy enabled flag
-    pub strategy_enabled: bool,
+    /// Lifecycle state
+    pub status: VaultLifecycle,
 
     /// Performance fee in basis points (e.g., 1000 = 10%)
     pub performance_fee_bps: u16,
+
+    /// Maximum total assets the vault accepts (0 = no cap)
+    pub deposit_cap: u64,
+
+    /// Maximum net deposit per depositor (0 = no cap)
+    pub user_deposit_cap: u64,
+
+    /// Minimum size of a single deposit (0 = no minimum)
+    pub min_deposit: u64,
+
+    /// Current (open) withdrawal epoch
+    pub withdraw_epoch: u64,
+
+    /// Id assigned by the vault registry (PDA seed)
+    pub vault_id: u64,
+
+    /// Target allocation for rebalancing (mints not listed target 0)
+    #[max_len(MAX_TARGET_WEIGHTS)]
+    pub target_weights: Vec<TargetWeight>,
+
+    /// Drift (bps) of the most off-target mint before a rebalance may run
+    pub rebalance_threshold_bps: u16,
+
+    /// Max slippage (bps) of a rebalance leg against the oracle quote
+    pub rebalance_max_slippage_bps: u16,
+
+    /// Base tokens deployed in the yield adapter, as of the last sync
+    pub adapter_assets: u64,
+
+    /// Layout version (see `Versioned`); new fields go after it
+    pub version: u8,
+
+    /// Seconds a deposit stays locked (0 = no lockup)
+    pub lock_period: i64,
+
+    /// Penalty in bps on exits before the lock expires, left in the vault
+    pub early_exit_penalty_bps: u16,
+
+    /// Deposit gate
+    pub deposit_gate: DepositGate,
+
+    /// Root of the approved-depositor tree (used by `DepositGate::Merkle`)
+    pub merkle_root: [u8; 32],
+
+    /// Base-token value of strategy holdings in other mints. Booked at the
+    /// oracle price when bought and released at that booked cost when sold
+    /// (see `strategy_costs`); not re-marked in between.
+    pub strategy_assets: u64,
+
+    /// Realized PnL of every strategy execution and rebalance, in base tokens
+    pub cumulative_pnl: i64,
+
+    /// Value of the child vault shares held, at the children's share prices
+    /// as of the last allocation or sync
+    pub child_assets: u64,
+
+    /// Child vaults registered through `add_child_vault`
+    pub child_count: u8,
+
+    /// Share price (as `share_price`) at which performance fees were last
+    /// taken; only gains above it are charged again
+    pub high_water_mark: u64,
+
+    /// Mints other than the base token that strategies bought and may still
+    /// hold; a rebalance must price every one of them
+    #[max_len(MAX_STRATEGY_MINTS)]
+    pub strategy_mints: Vec<Pubkey>,
+
+    /// Tokens in the claim escrow owed to settled withdrawal requests that
+    /// have not been claimed yet
+    pub pending_claims: u64,
+
+    /// Part of `strategy_assets` booked for each mint of `strategy_mints`,
+    /// by index
+    #[max_len(MAX_STRATEGY_MINTS)]
+    pub strategy_costs: Vec<u64>,
+
+    /// Parent vaults holding this vault through `add_child_vault`. A child
+    /// cannot take children of its own, which keeps nesting one level deep.
+    pub parent_count: u8,
+}
+
+pub const MAX_TARGET_WEIGHTS: usize = 8;
+
+pub const MAX_STRATEGY_MINTS: usize = 8;
+
+/// Upper bound on the early-exit penalty (50%)
+pub const MAX_EARLY_EXIT_PENALTY_BPS: u16 = 5_000;
+
+/// Share of vault value (bps) a mint should hold
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq, InitSpace)]
+pub struct TargetWeight {
+    pub mint: Pubkey,
+    pub weight_bps: u16,
+}
+
+/// Rounding direction for share/asset conversions.
+///
+/// Every conversion rounds in favor of the vault so that no sequence of
+/// deposits and withdrawals can extract more than it put in.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Rounding {
+    Floor,
+    Ceil,
 }
 
 impl Vault {
-    /// Calculate share price: assets / shares
-    pub fn share_price(&self) -> Result<u64> {
-        if self.total_shares == 0 {
-            return Ok(1_000_000); // Initial price: 1.0 (with 6 decimals)
+    /// Manager or keeper: may run the vault's day-to-day operations
+    pub fn is_operator(&self, key: &Pubkey) -> bool {
+        *key == self.manager || *key == self.keeper
+    }
+
+    /// Target weight (bps) for `mint`, 0 if it is not in the allocation
+    pub fn target_weight(&self, mint: &Pubkey) -> u16 {
+        self.target_weights
+            .iter()
+            .find(|target| target.mint == *mint)
+            .map(|target| target.weight_bps)
+            .unwrap_or(0)
+    }
+
+    /// Book `cost` (base tokens) of strategy holdings bought in `mint` to
+    /// `strategy_assets`, and remember that strategies now hold `mint` (the
+    /// base token is implied)
+    pub fn book_strategy_cost(&mut self, mint: Pubkey, cost: u64) -> Result<()> {
+        self.strategy_assets = self
+            .strategy_assets
+            .checked_add(cost)
+            .ok_or(VaultError::MathOverflow)?;
+        if mint == self.token_mint {
+            return Ok(());
+        }
+
+        match self
+            .strategy_mints
+            .iter()
+            .position(|tracked| *tracked == mint)
+        {
+            Some(index) => {
+                self.strategy_costs[index] = self.strategy_costs[index]
+                    .checked_add(cost)
+                    .ok_or(VaultError::MathOverflow)?;
+            }
+            None => {
+                require!(
+                    self.strategy_mints.len() < MAX_STRATEGY_MINTS,
+                    VaultError::TooManyStrategyMints
+                );
+                self.strategy_mints.push(mint);
+                self.strategy_costs.push(cost);
+            }
+        }
+        Ok(())
+    }
+
+    /// Release the booked cost of `sold` out of the `held` tokens of `mint`
+    /// from `strategy_assets` (rounded down; all of it once nothing is left,
+    /// when the mint is forgotten). None if `mint` is not tracked.
+    pub fn release_strategy_cost(
+        &mut self,
+        mint: &Pubkey,
+        sold: u64,
+        held: u64,
+    ) -> Result<Option<u64>> {
+        let Some(index) = self
+            .strategy_mints
+            .iter()
+            .position(|tracked| tracked == mint)
+        else {
+            return Ok(None);
+        };
+
+        let cost = self.strategy_costs[index];
+        let released = if sold >= held {
+            self.strategy_mints.remove(index);
+            self.strategy_costs.remove(index);
+            cost
+        } else {
+            let released = mul_div(cost, sold as u128, held as u128, Rounding::Floor)?;
+            self.strategy_costs[index] = cost - released;
+            released
+        };
+        self.strategy_assets = self.strategy_assets.saturating_sub(released);
+        Ok(Some(released))
+    }
+
+    /// Take the slice of `shares` out of `supply` out of every booked cost,
+    /// as an in-kind exit takes it out of `strategy_assets`
+    pub fn release_strategy_costs_pro_rata(&mut self, shares: u64, supply: u64) -> Result<()> {
+        for cost in self.strategy_costs.iter_mut() {
+            *cost -= mul_div(*cost, shares as u128, supply as u128, Rounding::Floor)?;
+        }
+        Ok(())
+    }
+
+    /// Manager or guardian: may switch strategies off
+    pub fn can_pause(&self, key: &Pubkey) -> bool {
+        *key == self.manager || *key == self.guardian
+    }
+
+    /// Extra decimals the share mint carries over the underlying token
+    pub const DECIMALS_OFFSET: u8 = 3;
+
+    /// Virtual shares added to the supply in every conversion (10^DECIMALS_OFFSET)
+    pub const VIRTUAL_SHARES: u64 = 1_000;
+
+    /// Virtual assets added to the balance in every conversion
+    ///
+    /// Together with VIRTUAL_SHARES this makes the vault behave as if it was
+    /// seeded with a tiny deposit nobody owns. An attacker who donates tokens
+    /// to inflate the share price mostly donates to the virtual shares, so
+    /// the first-depositor inflation attack costs far more than it steals.
+    pub const VIRTUAL_ASSETS: u64 = 1;
+
+    /// Offset of the `target_weights` length prefix, discriminator included:
+    /// six keys, total_assets, total_shares, bump, status,
+    /// performance_fee_bps, then five u64 limits and counters
+    const TARGET_WEIGHTS_OFFSET: usize = 8 + 6 * 32 + 2 * 8 + 1 + 1 + 2 + 5 * 8;
+
+    /// Fixed fields between `target_weights` and `strategy_mints`, from
+    /// `rebalance_threshold_bps` through `high_water_mark`
+    const FIXED_BETWEEN_VECS: usize = 2 + 2 + 8 + 1 + 8 + 2 + 1 + 32 + 8 + 8 + 8 + 1 + 8;
+
+    /// Penalty (bps) releasing `position`'s locked shares pays at `now`.
+    /// Nothing once the lock has expired, and nothing after a shutdown.
+    pub fn exit_penalty_bps(&self, position: &DepositorPosition, now: i64) -> u16 {
+        if self.is_shutdown() || !position.is_locked(now) {
+            return 0;
         }
+        self.early_exit_penalty_bps
+    }
+
+    /// Part of `amount` withheld by a `penalty_bps` penalty (rounded up, in favor of the vault)
+    pub fn exit_penalty(amount: u64, penalty_bps: u16) -> Result<u64> {
+        mul_div(amount, penalty_bps as u128, 10_000, Rounding::Ceil)
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.status == VaultLifecycle::Active
+    }
+
+    pub fn is_shutdown(&self) -> bool {
+        self.status == VaultLifecycle::Shutdown
+    }
+
+    /// Net asset value: idle base tokens, the adapter position, strategy
+    /// holdings and child vault shares
+    pub fn nav(&self) -> u64 {
+        self.total_assets
+            .saturating_add(self.adapter_assets)
+            .saturating_add(self.strategy_assets)
+            .saturating_add(self.child_assets)
+    }
+
+    /// Add a realized result to `cumulative_pnl`
+    pub fn record_pnl(&mut self, pnl: i64) -> Result<()> {
+        self.cumulative_pnl = self
+            .cumulative_pnl
+            .checked_add(pnl)
+            .ok_or(VaultError::MathOverflow)?;
+        Ok(())
+    }
 
-        // Price = (total_assets * 1e6) / total_shares
-        let price = (self.total_assets as u128)
+    /// Calculate share price: assets per 10^DECIMALS_OFFSET shares
+    pub fn share_price(&self) -> Result<u64> {
+        // Price = ((nav + 1) * 1e6 * 1e3) / (total_shares + 1e3)
+        // Initial price: 1.0 (with 6 decimals)
+        let price = (self.nav() as u128 + Self::VIRTUAL_ASSETS as u128)
             .checked_mul(1_000_000)
             .ok_or(VaultError::MathOverflow)?
-            .checked_div(self.total_shares as u128)
+            .checked_mul(Self::VIRTUAL_SHARES as u128)
+            .ok_or(VaultError::MathOverflow)?
+            .checked_div(self.total_shares as u128 + Self::VIRTUAL_SHARES as u128)
             .ok_or(VaultError::MathOverflow)?;
 
         require!(price <= u64::MAX as u128, VaultError::MathOverflow);
@@ -48,41 +341,313 @@ impl Vault {
         Ok(price as u64)
     }
 
-    /// Convert assets to shares
-    pub fn assets_to_shares(&self, assets: u64) -> Result<u64> {
-        if self.total_shares == 0 {
-            // Initial deposit: 1:1 ratio
-            return Ok(assets);
+    /// Performance fee owed above the high-water mark, as
+    /// `(fee_assets, fee_shares)`: the fee in base tokens and the shares that,
+    /// minted to the fee recipient, are worth that much after dilution
+    pub fn performance_fee(&self) -> Result<(u64, u64)> {
+        let share_price = self.share_price()?;
+        if share_price <= self.high_water_mark || self.performance_fee_bps == 0 {
+            return Ok((0, 0));
         }
 
-        // shares = (assets * total_shares) / total_assets
-        let shares = (assets as u128)
-            .checked_mul(self.total_shares as u128)
-            .ok_or(VaultError::MathOverflow)?
-            .checked_div(self.total_assets as u128)
-            .ok_or(VaultError::MathOverflow)?;
+        // Gain of the real holders since the mark
+        let per_share_scale = 1_000_000u128 * Self::VIRTUAL_SHARES as u128;
+        let gain = mul_div(
+            share_price - self.high_water_mark,
+            self.total_shares as u128,
+            per_share_scale,
+            Rounding::Floor,
+        )?;
+        let fee_assets = mul_div(
+            gain,
+            self.performance_fee_bps as u128,
+            10_000,
+            Rounding::Floor,
+        )?;
+        if fee_assets == 0 {
+            return Ok((0, 0));
+        }
 
-        require!(shares <= u64::MAX as u128, VaultError::MathOverflow);
-        require!(shares > 0, VaultError::ZeroShares);
+        // Shares worth `fee_assets` once they are part of the supply
+        let fee_shares = mul_div(
+            fee_assets,
+            self.total_shares as u128 + Self::VIRTUAL_SHARES as u128,
+            self.nav() as u128 + Self::VIRTUAL_ASSETS as u128 - fee_assets as u128,
+            Rounding::Floor,
+        )?;
+        Ok((fee_assets, fee_shares))
+    }
 
-        Ok(shares as u64)
+    /// Base-token value of `shares` of this vault at its share price (rounds down)
+    pub fn value_of_shares(&self, shares: u64) -> Result<u64> {
+        let per_share_scale = 1_000_000u128 * Self::VIRTUAL_SHARES as u128;
+        mul_div(
+            shares,
+            self.share_price()? as u128,
+            per_share_scale,
+            Rounding::Floor,
+        )
     }
 
-    /// Convert shares to assets
-    pub fn shares_to_assets(&self, shares: u64) -> Result<u64> {
+    /// Convert assets to shares with the given rounding
+    /// shares = assets * (total_shares + virtual shares) / (nav + virtual assets)
+    pub fn convert_to_shares(&self, assets: u64, rounding: Rounding) -> Result<u64> {
+        mul_div(
+            assets,
+            self.total_shares as u128 + Self::VIRTUAL_SHARES as u128,
+            self.nav() as u128 + Self::VIRTUAL_ASSETS as u128,
+            rounding,
+        )
+    }
+
+    /// Convert shares to assets with the given rounding
+    /// assets = shares * (nav + virtual assets) / (total_shares + virtual shares)
+    pub fn convert_to_assets(&self, shares: u64, rounding: Rounding) -> Result<u64> {
+        mul_div(
+            shares,
+            self.nav() as u128 + Self::VIRTUAL_ASSETS as u128,
+            self.total_shares as u128 + Self::VIRTUAL_SHARES as u128,
+            rounding,
+        )
+    }
+
+    /// Shares minted for depositing exactly `assets` (rounds down)
+    pub fn preview_deposit(&self, assets: u64) -> Result<u64> {
+        let shares = self.convert_to_shares(assets, Rounding::Floor)?;
         require!(shares > 0, VaultError::ZeroShares);
+        Ok(shares)
+    }
+
+    /// Assets required to mint exactly `shares` (rounds up)
+    pub fn preview_mint(&self, shares: u64) -> Result<u64> {
+        require!(shares > 0, VaultError::ZeroShares);
+        self.convert_to_assets(shares, Rounding::Ceil)
+    }
+
+    /// Shares burned to withdraw exactly `assets` (rounds up)
+    pub fn preview_withdraw(&self, assets: u64) -> Result<u64> {
+        require!(assets > 0, VaultError::ZeroAmount);
         require!(self.total_shares > 0, VaultError::NoShares);
+        self.convert_to_shares(assets, Rounding::Ceil)
+    }
 
-        // assets = (shares * total_assets) / total_shares
-        let assets = (shares as u128)
-            .checked_mul(self.total_assets as u128)
-            .ok_or(VaultError::MathOverflow)?
-            .checked_div(self.total_shares as u128)
-            .ok_or(VaultError::MathOverflow)?;
+    /// Assets returned for redeeming exactly `shares` (rounds down)
+    pub fn preview_redeem(&self, shares: u64) -> Result<u64> {
+        require!(shares > 0, VaultError::ZeroShares);
+        require!(self.total_shares > 0, VaultError::NoShares);
 
-        require!(assets <= u64::MAX as u128, VaultError::MathOverflow);
+        let assets = self.convert_to_assets(shares, Rounding::Floor)?;
         require!(assets > 0, VaultError::ZeroAssets);
+        Ok(assets)
+    }
+
+    /// Maximum assets a depositor with `deposited` net tokens can still deposit
+    pub fn max_deposit(&self, deposited: u64) -> u64 {
+        if self.is_shutdown() {
+            return 0;
+        }
+
+        let mut max = u64::MAX - self.nav();
+
+        if self.deposit_cap > 0 {
+            max = max.min(self.deposit_cap.saturating_sub(self.nav()));
+        }
+        if self.user_deposit_cap > 0 {
+            max = max.min(self.user_deposit_cap.saturating_sub(deposited));
+        }
 
-        Ok(assets as u64)
+        // Anything below the minimum deposit would be rejected anyway
+        if max < self.min_deposit {
+            return 0;
+        }
+
+        max
+    }
+
+    /// Reject depositors who do not pass the gate. `allowlisted` is whether
+    /// the depositor's `AllowlistEntry` was supplied (its seeds are checked by
+    /// the accounts struct).
+    pub fn check_deposit_gate(
+        &self,
+        depositor: &Pubkey,
+        allowlisted: bool,
+        proof: &[[u8; 32]],
+    ) -> Result<()> {
+        match self.deposit_gate {
+            DepositGate::Open => {}
+            DepositGate::Allowlist => {
+                require!(allowlisted, VaultError::DepositorNotAllowlisted)
+            }
+            DepositGate::Merkle => require!(
+                merkle::verify_proof(proof, &self.merkle_root, merkle::depositor_leaf(depositor)),
+                VaultError::InvalidMerkleProof
+            ),
+        }
+        Ok(())
     }
+
+    /// Reject deposits once shut down; enforce minimum deposit, vault cap and per-depositor cap
+    pub fn check_deposit_limits(&self, amount: u64, deposited: u64) -> Result<()> {
+        require!(!self.is_shutdown(), VaultError::VaultShutdown);
+        require!(amount >= self.min_deposit, VaultError::DepositTooSmall);
+
+        if self.deposit_cap > 0 {
+            let total = self
+                .nav()
+                .checked_add(amount)
+                .ok_or(VaultError::MathOverflow)?;
+            require!(total <= self.deposit_cap, VaultError::DepositCapExceeded);
+        }
+
+        if self.user_deposit_cap > 0 {
+            let total = deposited
+                .checked_add(amount)
+                .ok_or(VaultError::MathOverflow)?;
+            require!(
+                total <= self.user_deposit_cap,
+                VaultError::UserDepositCapExceeded
+            );
+        }
+
+        Ok(())
+    }
+
+    /// Maximum assets an owner of `shares` can withdraw right now,
+    /// bounded by the tokens actually sitting in the vault (`liquidity`).
+    /// Zero once shut down, when only in-kind exits remain.
+    pub fn max_withdraw(&self, shares: u64, liquidity: u64) -> Result<u64> {
+        if self.is_shutdown() {
+            return Ok(0);
+        }
+        let assets = self.convert_to_assets(shares, Rounding::Floor)?;
+        Ok(assets.min(liquidity).min(self.total_assets))
+    }
+}
+
+impl Versioned for Vault {
+    const CURRENT_VERSION: u8 = 10;
+
+    fn version(&self) -> u8 {
+        self.version
+    }
+
+    fn upgrade(&mut self) -> Result<()> {
+        // v1: version byte only
+        if self.version < 2 {
+            // v2: no lockup
+            self.lock_period = 0;
+            self.early_exit_penalty_bps = 0;
+        }
+        if self.version < 3 {
+            // v3: open to anyone
+            self.deposit_gate = DepositGate::Open;
+            self.merkle_root = [0; 32];
+        }
+        if self.version < 4 {
+            // v4: nothing booked yet
+            self.strategy_assets = 0;
+            self.cumulative_pnl = 0;
+        }
+        if self.version < 5 {
+            // v5: no child vaults
+            self.child_assets = 0;
+            self.child_count = 0;
+        }
+        if self.version < 6 {
+            // v6: high_water_mark, starting at today's price so past gains
+            // are not charged
+            self.high_water_mark = self.share_price()?;
+        }
+        if self.version < 7 {
+            // v7: mints bought before v7 are not tracked
+            self.strategy_mints.clear();
+        }
+        if self.version < 8 {
+            // v8: nothing owed yet; `migrate_vault` books the claim escrow
+            self.pending_claims = 0;
+        }
+        if self.version < 9 {
+            // v9: strategy_costs. Earlier books kept only the total, so it is
+            // spread evenly over the tracked mints
+            let mints = self.strategy_mints.len() as u64;
+            self.strategy_costs = (0..mints)
+                .map(|index| {
+                    let even = self.strategy_assets / mints;
+                    if index == 0 {
+                        even + self.strategy_assets % mints
+                    } else {
+                        even
+                    }
+                })
+                .collect();
+        }
+        if self.version < 10 {
+            // v10: parents are counted from here on; a vault added as a child
+            // before v10 is not known to be one
+            self.parent_count = 0;
+        }
+        self.version = Self::CURRENT_VERSION;
+        Ok(())
+    }
+
+    /// `target_weights` sits in front of `version` and every later field,
+    /// from v7 on `strategy_mints` and from v9 on `strategy_costs` in front of
+    /// the fields after them, so a legacy vault ends short of its size by the
+    /// entries they leave unused
+    fn unused_len(data: &[u8]) -> Result<usize> {
+        let weights = vec_len(data, Self::TARGET_WEIGHTS_OFFSET, MAX_TARGET_WEIGHTS)?;
+        let mut unused = (MAX_TARGET_WEIGHTS - weights) * TargetWeight::INIT_SPACE;
+
+        let mints_offset = Self::TARGET_WEIGHTS_OFFSET
+            + 4
+            + weights * TargetWeight::INIT_SPACE
+            + Self::FIXED_BETWEEN_VECS;
+        let v6_len = Self::TARGET_WEIGHTS_OFFSET
+            + 4
+            + MAX_TARGET_WEIGHTS * TargetWeight::INIT_SPACE
+            + Self::FIXED_BETWEEN_VECS;
+        if data.len() <= v6_len {
+            return Ok(unused);
+        }
+        let mints = vec_len(data, mints_offset, MAX_STRATEGY_MINTS)?;
+        unused += (MAX_STRATEGY_MINTS - mints) * 32;
+
+        // pending_claims sits between the two
+        let costs_offset = mints_offset + 4 + mints * 32 + 8;
+        let v8_len = v6_len + 4 + MAX_STRATEGY_MINTS * 32 + 8;
+        if data.len() > v8_len {
+            let costs = vec_len(data, costs_offset, MAX_STRATEGY_MINTS)?;
+            unused += (MAX_STRATEGY_MINTS - costs) * 8;
+        }
+        Ok(unused)
+    }
+}
+
+/// Length prefix of the Vec at `offset`, at most `max_len`
+fn vec_len(data: &[u8], offset: usize, max_len: usize) -> Result<usize> {
+    let len = data
+        .get(offset..offset + 4)
+        .map(|bytes| u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as usize)
+        .ok_or(MigrationError::UnknownLayout)?;
+    require!(len <= max_len, MigrationError::UnknownLayout);
+    Ok(len)
+}
+
+/// (value * numerator) / denominator in u128, rounded as requested
+fn mul_div(value: u64, numerator: u128, denominator: u128, rounding: Rounding) -> Result<u64> {
+    let product = (value as u128)
+        .checked_mul(numerator)
+        .ok_or(VaultError::MathOverflow)?;
+
+    let mut result = product
+        .checked_div(denominator)
+        .ok_or(VaultError::MathOverflow)?;
+
+    if rounding == Rounding::Ceil && product % denominator != 0 {
+        result = result.checked_add(1).ok_or(VaultError::MathOverflow)?;
+    }
+
+    require!(result <= u64::MAX as u128, VaultError::MathOverflow);
+
+    Ok(result as u64)
 }
@@ -0,0 +1,36 @@
+use anchor_lang::prelude::*;
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq, InitSpace)]
+pub enum VaultStatus {
+    Active,
+    Closed,
+    /// Emergency shutdown: exits only, in kind
+    Shutdown,
+}
+
+// Protocol-wide vault counter. It hands out vault ids: a vault PDA is seeded
+// by its id, so any number of managers can run vaults over the same mint.
+#[account]
+#[derive(InitSpace)]
+pub struct VaultRegistry {
+    /// Number of vaults ever created (next vault id)
+    pub vault_count: u64,
+
+    /// Bump for PDA
+    pub bump: u8,
+}
+
+// One per vault id, so frontends can discover every vault by walking ids
+// 0..vault_count without the registry growing with each vault.
+#[account]
+#[derive(InitSpace)]
+pub struct VaultRegistryEntry {
+    pub vault: Pubkey,
+    pub vault_id: u64,
+    pub token_mint: Pubkey,
+    pub manager: Pubkey,
+    pub status: VaultStatus,
+
+    /// Bump for PDA
+    pub bump: u8,
+}
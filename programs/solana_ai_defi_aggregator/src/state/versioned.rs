@@ -0,0 +1,24 @@
+use anchor_lang::prelude::*;
+
+/// Accounts whose layout grows by appending fields.
+///
+/// New fields are appended after `version`. An account written before a
+/// field existed is grown by `migrate_*`, which zeroes everything past the
+/// end of its serialized data, so it reads back with the new fields (and
+/// `version`) at zero, and `upgrade` sets each of them explicitly.
+pub trait Versioned {
+    const CURRENT_VERSION: u8;
+
+    fn version(&self) -> u8;
+
+    /// Fill fields added after `version()` and stamp CURRENT_VERSION
+    fn upgrade(&mut self) -> Result<()>;
+
+    /// Bytes at the end of a legacy account (`data`, discriminator included)
+    /// that its serialization does not reach because a variable-length field
+    /// is shorter than its maximum. They may hold stale bytes from an earlier,
+    /// longer write, so `migrate_*` zeroes them before decoding.
+    fn unused_len(_data: &[u8]) -> Result<usize> {
+        Ok(0)
+    }
+}
@@ -0,0 +1,63 @@
+use anchor_lang::prelude::*;
+
+// One withdrawal epoch: every request in it is paid at the same share price
+#[account]
+#[derive(InitSpace)]
+pub struct WithdrawEpoch {
+    /// Vault the epoch belongs to
+    pub vault: Pubkey,
+
+    /// Epoch number
+    pub epoch: u64,
+
+    /// Shares locked in escrow by all requests of this epoch; once settled,
+    /// the shares of the requests not claimed yet
+    pub total_shares: u64,
+
+    /// Tokens set aside for this epoch at settlement and not claimed yet
+    pub total_assets: u64,
+
+    /// Set once the epoch has been settled
+    pub settled: bool,
+
+    /// Settlement timestamp
+    pub settled_at: i64,
+
+    /// Bump for PDA
+    pub bump: u8,
+}
+
+// A depositor's queued withdrawal in a given epoch
+#[account]
+#[derive(InitSpace)]
+pub struct WithdrawalRequest {
+    /// Vault the request belongs to
+    pub vault: Pubkey,
+
+    /// Depositor who requested the withdrawal
+    pub owner: Pubkey,
+
+    /// Epoch the request joined
+    pub epoch: u64,
+
+    /// Shares locked in escrow
+    pub shares: u64,
+
+    /// Bump for PDA
+    pub bump: u8,
+}
+
+impl WithdrawEpoch {
+    /// Tokens owed to a request of `shares` in this settled epoch (rounded down)
+    pub fn assets_for(&self, shares: u64) -> Option<u64> {
+        if self.total_shares == 0 {
+            return Some(0);
+        }
+
+        let assets = (shares as u128)
+            .checked_mul(self.total_assets as u128)?
+            .checked_div(self.total_shares as u128)?;
+
+        u64::try_from(assets).ok()
+    }
+}
@@ -0,0 +1,63 @@
+use anchor_lang::prelude::*;
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq, InitSpace)]
+pub enum AdapterKind {
+    /// SPL token-lending reserve (deposit liquidity, hold collateral)
+    TokenLending,
+}
+
+// Where a vault's Yield strategy parks base tokens
+#[account]
+#[derive(InitSpace)]
+pub struct YieldAdapter {
+    /// Vault this adapter belongs to
+    pub vault: Pubkey,
+
+    /// Protocol interface
+    pub kind: AdapterKind,
+
+    /// Lending program invoked by the adapter
+    pub lending_program: Pubkey,
+
+    /// Reserve for the vault's base token
+    pub reserve: Pubkey,
+
+    /// Reserve's liquidity supply token account
+    pub reserve_liquidity_supply: Pubkey,
+
+    /// Reserve's collateral (receipt) mint
+    pub reserve_collateral_mint: Pubkey,
+
+    /// Lending market the reserve belongs to
+    pub lending_market: Pubkey,
+
+    /// Lending market authority PDA
+    pub lending_market_authority: Pubkey,
+
+    /// Vault-owned account holding the collateral
+    pub collateral_account: Pubkey,
+
+    /// Net base tokens deployed (deposits minus recalls)
+    pub deployed: u64,
+
+    /// Time of the last deploy, recall or sync
+    pub last_synced_at: i64,
+
+    /// Bump for PDA
+    pub bump: u8,
+}
+
+// Admin approval for one lending program. Vaults sign CPIs into their
+// adapter's program, so only approved programs can back an adapter.
+#[account]
+#[derive(InitSpace)]
+pub struct ApprovedLendingProgram {
+    /// Approved lending program
+    pub program: Pubkey,
+
+    /// When the admin approved it
+    pub approved_at: i64,
+
+    /// Bump for PDA
+    pub bump: u8,
+}
@@ -0,0 +1,20 @@
+use anchor_lang::prelude::Pubkey;
+use solana_sha256_hasher::hashv;
+
+/// Leaf for a depositor in a vault's deposit merkle tree: sha256(depositor)
+pub fn depositor_leaf(depositor: &Pubkey) -> [u8; 32] {
+    hashv(&[depositor.as_ref()]).to_bytes()
+}
+
+/// Check `leaf` against `root`. Pairs are hashed in sorted order, so the
+/// proof carries sibling hashes only, no left/right flags.
+pub fn verify_proof(proof: &[[u8; 32]], root: &[u8; 32], leaf: [u8; 32]) -> bool {
+    let computed = proof.iter().fold(leaf, |node, sibling| {
+        if node <= *sibling {
+            hashv(&[&node, sibling]).to_bytes()
+        } else {
+            hashv(&[sibling, &node]).to_bytes()
+        }
+    });
+    computed == *root
+}
@@ -1,4 +1,5 @@
 pub mod fee;
+pub mod merkle;
 pub mod profit;
 pub mod token;
 
@@ -11,10 +11,16 @@ async function main() {
 
   const usdcMint = new PublicKey("Gh9ZwEmdLJ8DscKNTkTqPbNwLNNBjuSzaG9Vp2KGtKJr");
 
-  const [vault] = PublicKey.findProgramAddressSync(
-    [Buffer.from("vault"), usdcMint.toBuffer()],
+  const [vaultRegistry] = PublicKey.findProgramAddressSync(
+    [Buffer.from("vault_registry")],
     program.programId
   );
+  const registry = await program.account.vaultRegistry.fetch(vaultRegistry);
+  const entry = registry.vaults.find((v) => v.tokenMint.equals(usdcMint));
+  if (!entry) {
+    throw new Error(`No vault registered for mint ${usdcMint.toString()}`);
+  }
+  const vault = entry.vault;
 
   console.log("🔍 Checking Vault State...\n");
 
@@ -22,12 +28,16 @@ async function main() {
 
   console.log("Vault Details:");
   console.log("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
-  console.log("Authority:      ", vaultAccount.authority.toString());
+  console.log("Vault Id:       ", vaultAccount.vaultId.toString());
+  console.log("Manager:        ", vaultAccount.manager.toString());
+  console.log("Keeper:         ", vaultAccount.keeper.toString());
+  console.log("Guardian:       ", vaultAccount.guardian.toString());
+  console.log("Fee Recipient:  ", vaultAccount.feeRecipient.toString());
   console.log("Token Mint:     ", vaultAccount.tokenMint.toString());
   console.log("Share Mint:     ", vaultAccount.shareMint.toString());
   console.log("Total Assets:   ", vaultAccount.totalAssets.toString());
   console.log("Total Shares:   ", vaultAccount.totalShares.toString());
-  console.log("Strategy:       ", vaultAccount.strategyEnabled ? "✅ Enabled" : "❌ Disabled");
+  console.log("Status:         ", Object.keys(vaultAccount.status)[0]);
   console.log("Performance Fee:", vaultAccount.performanceFeeBps, "bps");
   console.log("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━\n");
 
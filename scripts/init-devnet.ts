@@ -55,8 +55,37 @@ async function main() {
     program.programId
   );
 
+  // Vaults are seeded by a registry-assigned id: reuse this wallet's USDC
+  // vault if the registry already lists one, otherwise take the next id
+  const [vaultRegistry] = PublicKey.findProgramAddressSync(
+    [Buffer.from("vault_registry")],
+    program.programId
+  );
+  try {
+    await program.methods
+      .initializeVaultRegistry()
+      .accounts({
+        payer: provider.wallet.publicKey,
+        registry: vaultRegistry,
+        systemProgram: SystemProgram.programId,
+      } as any)
+      .rpc();
+    console.log("   ✅ Vault Registry initialized");
+  } catch (error) {
+    if (!error.message.includes("already in use")) {
+      throw error;
+    }
+  }
+  const registry = await program.account.vaultRegistry.fetch(vaultRegistry);
+  const existing = registry.vaults.find(
+    (entry) =>
+      entry.tokenMint.equals(usdcMint) &&
+      entry.manager.equals(provider.wallet.publicKey)
+  );
+  const vaultId = existing ? existing.vaultId : registry.vaultCount;
+
   const [vault] = PublicKey.findProgramAddressSync(
-    [Buffer.from("vault"), usdcMint.toBuffer()],
+    [Buffer.from("vault"), vaultId.toArrayLike(Buffer, "le", 8)],
     program.programId
   );
 
@@ -142,20 +171,25 @@ async function main() {
   // ===========================================
   try {
     console.log("3️⃣ Initializing Main Vault...");
-    await program.methods
-      .initializeVault(PERFORMANCE_FEE_BPS)
-      .accounts({
-        authority: provider.wallet.publicKey,
-        vault: vault,
-        tokenMint: usdcMint,
-        shareMint: shareMint,
-        vaultTokenAccount: vaultTokenAccount,
-        tokenProgram: TOKEN_PROGRAM_ID,
-        systemProgram: SystemProgram.programId,
-        rent: SYSVAR_RENT_PUBKEY,
-      } as any)
-      .rpc();
-    console.log("   ✅ Main Vault initialized");
+    if (existing) {
+      console.log("   ℹ️  Main Vault already registered:", vault.toString());
+    } else {
+      await program.methods
+        .initializeVault(PERFORMANCE_FEE_BPS)
+        .accounts({
+          authority: provider.wallet.publicKey,
+          registry: vaultRegistry,
+          vault: vault,
+          tokenMint: usdcMint,
+          shareMint: shareMint,
+          vaultTokenAccount: vaultTokenAccount,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+          rent: SYSVAR_RENT_PUBKEY,
+        } as any)
+        .rpc();
+      console.log("   ✅ Main Vault initialized");
+    }
   } catch (error) {
     if (error.message.includes("already in use")) {
       console.log("   ℹ️  Main Vault already initialized");
@@ -192,7 +226,7 @@ async function main() {
   try {
     console.log("5️⃣ Enabling Strategy...");
     await program.methods
-      .setStrategyEnabled(true)
+      .setVaultStatus({ active: {} })
       .accounts({
         authority: provider.wallet.publicKey,
         vault: vault,
@@ -203,6 +237,36 @@ async function main() {
     console.log("   ⚠️  Could not enable strategy:", error.message);
   }
 
+  // ===========================================
+  // Risk Policy (strategies cannot execute without one)
+  // ===========================================
+  try {
+    console.log("6️⃣ Setting Risk Policy...");
+    const [riskPolicy] = PublicKey.findProgramAddressSync(
+      [Buffer.from("risk_policy"), vault.toBuffer()],
+      program.programId
+    );
+    await program.methods
+      .setRiskPolicy({
+        maxTradeNotional: new anchor.BN(100_000_000), // 100 USDC
+        maxDailyNotional: new anchor.BN(1_000_000_000), // 1,000 USDC
+        maxDailyLoss: new anchor.BN(50_000_000), // 50 USDC
+        minExecutionInterval: new anchor.BN(60),
+        allowedInputMints: [usdcMint],
+        allowedOutputMints: [],
+      })
+      .accounts({
+        manager: provider.wallet.publicKey,
+        vault: vault,
+        riskPolicy: riskPolicy,
+        systemProgram: SystemProgram.programId,
+      } as any)
+      .rpc();
+    console.log("   ✅ Risk policy set");
+  } catch (error) {
+    console.log("   ⚠️  Could not set risk policy:", error.message);
+  }
+
   // ===========================================
   // Summary
   // ===========================================
@@ -224,6 +288,7 @@ async function main() {
     "2. Copy IDL to frontend: cp target/idl/your_program.json ../vault-frontend/src/idl/"
   );
   console.log("3. Get some devnet USDC from faucet");
+  console.log("   Push oracle prices with updatePriceFeed before executing strategies");
   console.log("4. Run frontend: cd ../vault-frontend && npm run dev");
 }
 
@@ -38,11 +38,16 @@ describe("Vault Strategy Execution Tests", () => {
   // PDAs
   let globalState: PublicKey;
   let vault: PublicKey;
+  let vaultRegistry: PublicKey;
+  let vaultRegistryEntry: PublicKey;
   let vaultUserState: PublicKey;
   let shareMint: PublicKey;
   let vaultUsdcAccount: PublicKey;
   let vaultSolAccount: PublicKey;
   let feeVault: PublicKey;
+  let riskPolicy: PublicKey;
+  let usdcPriceFeed: PublicKey;
+  let solPriceFeed: PublicKey;
 
   // Constants
   const JUPITER_PROGRAM_ID = new PublicKey(
@@ -51,6 +56,16 @@ describe("Vault Strategy Execution Tests", () => {
   const PERFORMANCE_FEE_BPS = 1000; // 10%
   const FEE_RATE_BPS = 30; // 0.3%
   const INITIAL_DEPOSIT = 10_000_000; // 10 USDC
+  const USDC_PRICE = 1_000_000; // $1 (6 decimals)
+  const SOL_PRICE = 250_000_000; // $250 (6 decimals)
+  const OPEN_RISK_LIMITS = {
+    maxTradeNotional: new anchor.BN(0),
+    maxDailyNotional: new anchor.BN(0),
+    maxDailyLoss: new anchor.BN(0),
+    minExecutionInterval: new anchor.BN(0),
+    allowedInputMints: [],
+    allowedOutputMints: [],
+  };
 
   before(async () => {
     console.log("\n🔧 Setting up test environment...\n");
@@ -88,8 +103,40 @@ describe("Vault Strategy Execution Tests", () => {
       program.programId
     );
 
+    // Vaults are seeded by the id the registry hands out
+    [vaultRegistry] = PublicKey.findProgramAddressSync(
+      [Buffer.from("vault_registry")],
+      program.programId
+    );
+    try {
+      await program.methods
+        .initializeVaultRegistry()
+        .accounts({
+          payer: authority.publicKey,
+          registry: vaultRegistry,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([authority.payer])
+        .rpc();
+    } catch (e) {
+      console.log("⚠️  Vault registry already initialized");
+    }
+    const registryState = await program.account.vaultRegistry.fetch(
+      vaultRegistry
+    );
+
     [vault] = PublicKey.findProgramAddressSync(
-      [Buffer.from("vault"), usdcMint.toBuffer()],
+      [
+        Buffer.from("vault"),
+        registryState.vaultCount.toArrayLike(Buffer, "le", 8),
+      ],
+      program.programId
+    );
+    [vaultRegistryEntry] = PublicKey.findProgramAddressSync(
+      [
+        Buffer.from("vault_registry_entry"),
+        registryState.vaultCount.toArrayLike(Buffer, "le", 8),
+      ],
       program.programId
     );
 
@@ -113,6 +160,21 @@ describe("Vault Strategy Execution Tests", () => {
       program.programId
     );
 
+    [riskPolicy] = PublicKey.findProgramAddressSync(
+      [Buffer.from("risk_policy"), vault.toBuffer()],
+      program.programId
+    );
+
+    [usdcPriceFeed] = PublicKey.findProgramAddressSync(
+      [Buffer.from("price_feed"), usdcMint.toBuffer()],
+      program.programId
+    );
+
+    [solPriceFeed] = PublicKey.findProgramAddressSync(
+      [Buffer.from("price_feed"), solMint.toBuffer()],
+      program.programId
+    );
+
     console.log("\nPDAs:");
     console.log("  Global State:", globalState.toString());
     console.log("  Vault:", vault.toString());
@@ -166,6 +228,8 @@ describe("Vault Strategy Execution Tests", () => {
       .initializeVault(PERFORMANCE_FEE_BPS)
       .accounts({
         authority: authority.publicKey,
+        registry: vaultRegistry,
+        registryEntry: vaultRegistryEntry,
         vault: vault,
         tokenMint: usdcMint,
         shareMint: shareMint,
@@ -195,7 +259,7 @@ describe("Vault Strategy Execution Tests", () => {
     // Enable strategy
     console.log("Enabling strategy...");
     await program.methods
-      .setStrategyEnabled(true)
+      .setVaultStatus({ active: {} })
       .accounts({
         authority: authority.publicKey,
         vault: vault,
@@ -204,6 +268,14 @@ describe("Vault Strategy Execution Tests", () => {
       .rpc();
     console.log("Strategy enabled");
 
+    // Oracle prices and an open risk policy
+    console.log("Pushing oracle prices...");
+    await updatePrices();
+
+    console.log("Setting risk policy...");
+    await setRiskLimits(OPEN_RISK_LIMITS);
+    console.log("Risk policy set");
+
     // Create vault's SOL token account
     console.log("Creating vault SOL account...");
     const vaultSolAccountInfo = await getOrCreateAssociatedTokenAccount(
@@ -242,6 +314,38 @@ describe("Vault Strategy Execution Tests", () => {
     return await getTokenBalance(userShareAccount);
   }
 
+  async function updatePrices() {
+    for (const [mint, feed, price] of [
+      [usdcMint, usdcPriceFeed, USDC_PRICE],
+      [solMint, solPriceFeed, SOL_PRICE],
+    ] as [PublicKey, PublicKey, number][]) {
+      await program.methods
+        .updatePriceFeed(new anchor.BN(price))
+        .accounts({
+          admin: authority.publicKey,
+          globalState: globalState,
+          priceFeed: feed,
+          mint: mint,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([authority.payer])
+        .rpc();
+    }
+  }
+
+  async function setRiskLimits(limits: typeof OPEN_RISK_LIMITS) {
+    await program.methods
+      .setRiskPolicy(limits)
+      .accounts({
+        manager: authority.publicKey,
+        vault: vault,
+        riskPolicy: riskPolicy,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([authority.payer])
+      .rpc();
+  }
+
   function createMockJupiterIx(): Buffer {
     // Mock Jupiter instruction data
     return Buffer.from([1, 2, 3, 4, 5, 6, 7, 8]);
@@ -283,7 +387,7 @@ describe("Vault Strategy Execution Tests", () => {
     console.log("  Depositing:", INITIAL_DEPOSIT / 1_000_000, "USDC");
 
     await program.methods
-      .deposit(new anchor.BN(INITIAL_DEPOSIT))
+      .deposit(new anchor.BN(INITIAL_DEPOSIT), [])
       .accounts({
         user: user1.publicKey,
         vault: vault,
@@ -291,6 +395,8 @@ describe("Vault Strategy Execution Tests", () => {
         vaultTokenAccount: vaultUsdcAccount,
         userShareAccount: user1ShareAccount,
         shareMint: shareMint,
+        allowlistEntry: null,
+        lockedShareAccount: null,
         tokenProgram: TOKEN_PROGRAM_ID,
         associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
         systemProgram: SystemProgram.programId,
@@ -310,7 +416,8 @@ describe("Vault Strategy Execution Tests", () => {
     console.log("  User shares:", shareBalance / 1_000_000);
 
     assert.equal(vaultState.totalAssets.toNumber(), INITIAL_DEPOSIT);
-    assert.equal(shareBalance, INITIAL_DEPOSIT);
+    // Shares carry 3 extra decimals (Vault::DECIMALS_OFFSET)
+    assert.equal(shareBalance, INITIAL_DEPOSIT * 1_000);
   });
 
   // ============================================
@@ -368,7 +475,7 @@ describe("Vault Strategy Execution Tests", () => {
         accountsMeta
       )
       .accounts({
-        authority: authority.publicKey,
+        keeper: authority.publicKey,
         vault: vault,
         globalState: globalState,
         vaultUserState: vaultUserState,
@@ -377,6 +484,9 @@ describe("Vault Strategy Execution Tests", () => {
         feeVaultAta: feeVault,
         inputMint: usdcMint,
         outputMint: solMint,
+        riskPolicy: riskPolicy,
+        inputPriceFeed: usdcPriceFeed,
+        outputPriceFeed: solPriceFeed,
         jupiterProgram: JUPITER_PROGRAM_ID,
         tokenProgram: TOKEN_PROGRAM_ID,
         associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
@@ -522,7 +632,7 @@ describe("Vault Strategy Execution Tests", () => {
           [vaultUsdcAccount, vaultSolAccount]
         )
         .accounts({
-          authority: authority.publicKey,
+          keeper: authority.publicKey,
           vault: vault,
           globalState: globalState,
           vaultUserState: vaultUserState,
@@ -531,6 +641,9 @@ describe("Vault Strategy Execution Tests", () => {
           feeVaultAta: feeVault,
           inputMint: usdcMint,
           outputMint: solMint,
+          riskPolicy: riskPolicy,
+          inputPriceFeed: usdcPriceFeed,
+          outputPriceFeed: solPriceFeed,
           jupiterProgram: JUPITER_PROGRAM_ID,
           tokenProgram: TOKEN_PROGRAM_ID,
           associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
@@ -581,7 +694,7 @@ describe("Vault Strategy Execution Tests", () => {
           [vaultUsdcAccount, vaultSolAccount]
         )
         .accounts({
-          authority: user1.publicKey, // Wrong authority!
+          keeper: user1.publicKey, // Not the keeper!
           vault: vault,
           globalState: globalState,
           vaultUserState: vaultUserState,
@@ -590,6 +703,9 @@ describe("Vault Strategy Execution Tests", () => {
           feeVaultAta: feeVault,
           inputMint: usdcMint,
           outputMint: solMint,
+          riskPolicy: riskPolicy,
+          inputPriceFeed: usdcPriceFeed,
+          outputPriceFeed: solPriceFeed,
           jupiterProgram: JUPITER_PROGRAM_ID,
           tokenProgram: TOKEN_PROGRAM_ID,
           associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
@@ -630,7 +746,7 @@ describe("Vault Strategy Execution Tests", () => {
           [vaultUsdcAccount, vaultSolAccount]
         )
         .accounts({
-          authority: authority.publicKey,
+          keeper: authority.publicKey,
           vault: vault,
           globalState: globalState,
           vaultUserState: vaultUserState,
@@ -639,6 +755,9 @@ describe("Vault Strategy Execution Tests", () => {
           feeVaultAta: feeVault,
           inputMint: usdcMint,
           outputMint: solMint,
+          riskPolicy: riskPolicy,
+          inputPriceFeed: usdcPriceFeed,
+          outputPriceFeed: solPriceFeed,
           jupiterProgram: JUPITER_PROGRAM_ID,
           tokenProgram: TOKEN_PROGRAM_ID,
           associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
@@ -701,7 +820,7 @@ describe("Vault Strategy Execution Tests", () => {
         vaultState.totalAssets.toNumber() / vaultState.totalShares.toNumber()
       ).toFixed(6)
     );
-    console.log("  Strategy enabled:", vaultState.strategyEnabled);
+    console.log("  Status:", Object.keys(vaultState.status)[0]);
     console.log("\n  Fees Collected:");
     console.log("  Fee vault balance:", feeVaultBalance / 1_000_000, "USDC");
     console.log("\n  Vault Statistics:");
@@ -0,0 +1,1211 @@
+import * as anchor from "@coral-xyz/anchor";
+import { Program } from "@coral-xyz/anchor";
+import { SolanaAiDefiAggregator } from "../target/types/solana_ai_defi_aggregator";
+import { MockSwap } from "../target/types/mock_swap";
+import {
+  PublicKey,
+  Keypair,
+  SystemProgram,
+  SYSVAR_RENT_PUBKEY,
+} from "@solana/web3.js";
+import {
+  TOKEN_PROGRAM_ID,
+  ASSOCIATED_TOKEN_PROGRAM_ID,
+  createAccount,
+  createMint,
+  mintTo,
+  getAccount,
+  getOrCreateAssociatedTokenAccount,
+} from "@solana/spl-token";
+import { assert } from "chai";
+
+describe("Strategy Marketplace Tests", () => {
+  const provider = anchor.AnchorProvider.env();
+  anchor.setProvider(provider);
+
+  const program = anchor.workspace
+    .solanaAiDefiAggregator as Program<SolanaAiDefiAggregator>;
+  // Stands in for Jupiter: routes are real token movements at a chosen fill
+  const swap = anchor.workspace.mockSwap as Program<MockSwap>;
+
+  let admin: Keypair;
+  let creator: Keypair;
+  let buyer: Keypair;
+
+  let usdcMint: PublicKey;
+  let otherMint: PublicKey;
+  let globalState: PublicKey;
+  let feeVault: PublicKey;
+  let strategy: PublicKey;
+  let poolAuthority: PublicKey;
+  // Buyer's second USDC account, where round trips land
+  let buyerUsdcOut: PublicKey;
+
+  const STRATEGY_ID = new anchor.BN(1);
+  const PRICE = 10_000_000; // 10 USDC
+  const COMMISSION_BPS = 500; // 5%
+  const EXECUTION_AMOUNT = 1_000_000; // 1 USDC per execution
+  const POOL_LIQUIDITY = 100_000_000;
+
+  before(async () => {
+    admin = Keypair.generate();
+    creator = Keypair.generate();
+    buyer = Keypair.generate();
+    await airdrop(admin.publicKey, 10);
+    await airdrop(creator.publicKey, 10);
+    await airdrop(buyer.publicKey, 10);
+
+    usdcMint = await createMint(
+      provider.connection,
+      admin,
+      admin.publicKey,
+      null,
+      6
+    );
+    otherMint = await createMint(
+      provider.connection,
+      admin,
+      admin.publicKey,
+      null,
+      6
+    );
+
+    [globalState] = PublicKey.findProgramAddressSync(
+      [Buffer.from("global_state")],
+      program.programId
+    );
+    [poolAuthority] = PublicKey.findProgramAddressSync(
+      [Buffer.from("pool_authority")],
+      swap.programId
+    );
+    [feeVault] = PublicKey.findProgramAddressSync(
+      [Buffer.from("fee_vault"), usdcMint.toBuffer()],
+      program.programId
+    );
+    [strategy] = PublicKey.findProgramAddressSync(
+      [
+        Buffer.from("strategy"),
+        creator.publicKey.toBuffer(),
+        STRATEGY_ID.toArrayLike(Buffer, "le", 8),
+      ],
+      program.programId
+    );
+
+    // Take over the admin role, keeping the swap fee other suites rely on
+    let feeRate = 30;
+    try {
+      feeRate = (await program.account.globalState.fetch(globalState)).feeRate;
+    } catch (e) {
+      // Not initialized yet
+    }
+    await program.methods
+      .initializeGlobalState(feeRate)
+      .accounts({
+        admin: admin.publicKey,
+        globalState,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([admin])
+      .rpc();
+
+    await program.methods
+      .initializeFeeVault()
+      .accounts({
+        authority: admin.publicKey,
+        globalState,
+        vaultAta: feeVault,
+        inputMint: usdcMint,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+        rent: SYSVAR_RENT_PUBKEY,
+      })
+      .signers([admin])
+      .rpc();
+
+    await program.methods
+      .createStrategy(
+        STRATEGY_ID,
+        "Commissioned Arb",
+        "Marketplace test strategy",
+        new anchor.BN(PRICE),
+        { arbitrage: {} },
+        {
+          inputToken: usdcMint,
+          outputToken: usdcMint,
+          minProfitBps: 0,
+          maxSlippageBps: 50,
+          executionInterval: new anchor.BN(0),
+        }
+      )
+      .accounts({
+        strategy,
+        creator: creator.publicKey,
+        paymentMint: usdcMint,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([creator])
+      .rpc();
+
+    // Executions are bounded by the oracle; both tokens at $1
+    for (const mint of [usdcMint, otherMint]) {
+      await program.methods
+        .updatePriceFeed(new anchor.BN(1_000_000))
+        .accounts({
+          admin: admin.publicKey,
+          globalState,
+          priceFeed: priceFeedOf(mint),
+          mint,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([admin])
+        .rpc();
+    }
+
+    // Liquidity for the routes to fill from
+    for (const mint of [usdcMint, otherMint]) {
+      const pool = await getOrCreateAssociatedTokenAccount(
+        provider.connection,
+        admin,
+        mint,
+        poolAuthority,
+        true
+      );
+      await mintTo(
+        provider.connection,
+        admin,
+        mint,
+        pool.address,
+        admin,
+        POOL_LIQUIDITY
+      );
+    }
+    buyerUsdcOut = await createAccount(
+      provider.connection,
+      admin,
+      usdcMint,
+      buyer.publicKey,
+      Keypair.generate()
+    );
+  });
+
+  async function airdrop(publicKey: PublicKey, amount: number) {
+    const sig = await provider.connection.requestAirdrop(
+      publicKey,
+      amount * anchor.web3.LAMPORTS_PER_SOL
+    );
+    await provider.connection.confirmTransaction(sig);
+  }
+
+  async function getTokenBalance(tokenAccount: PublicKey): Promise<number> {
+    const account = await getAccount(provider.connection, tokenAccount);
+    return Number(account.amount);
+  }
+
+  async function tokenAccount(mint: PublicKey, owner: PublicKey) {
+    return (
+      await getOrCreateAssociatedTokenAccount(
+        provider.connection,
+        admin,
+        mint,
+        owner
+      )
+    ).address;
+  }
+
+  function priceFeedOf(mint: PublicKey) {
+    return PublicKey.findProgramAddressSync(
+      [Buffer.from("price_feed"), mint.toBuffer()],
+      program.programId
+    )[0];
+  }
+
+  function poolOf(mint: PublicKey) {
+    return PublicKey.findProgramAddressSync(
+      [poolAuthority.toBuffer(), TOKEN_PROGRAM_ID.toBuffer(), mint.toBuffer()],
+      ASSOCIATED_TOKEN_PROGRAM_ID
+    )[0];
+  }
+
+  function userStrategyOf(owner: PublicKey, target: PublicKey) {
+    return PublicKey.findProgramAddressSync(
+      [Buffer.from("user_strategy"), owner.toBuffer(), target.toBuffer()],
+      program.programId
+    )[0];
+  }
+
+  // Execute `target` as the buyer, routing EXECUTION_AMOUNT of USDC through
+  // mock_swap into `outputMint` (the strategy's output token by default).
+  // The route fills at `fill`, the oracle price of $1 unless set, and pays
+  // out to `routeDestination` instead when one is given.
+  async function executeStrategy(
+    target: PublicKey,
+    {
+      supersedingVersion = null as PublicKey | null,
+      fill = EXECUTION_AMOUNT,
+      minOutput = 0,
+      outputMint = null as PublicKey | null,
+      outputAccount = null as PublicKey | null,
+      routeDestination = null as PublicKey | null,
+      creatorOutputAta = null as PublicKey | null,
+      discriminator = null as number[] | null,
+    } = {}
+  ) {
+    const { totalExecutions, parameters } =
+      await program.account.strategy.fetch(target);
+    const [execution] = PublicKey.findProgramAddressSync(
+      [
+        Buffer.from("execution"),
+        target.toBuffer(),
+        totalExecutions.toArrayLike(Buffer, "le", 8),
+      ],
+      program.programId
+    );
+    outputMint = outputMint ?? parameters.outputToken;
+    const buyerInput = await tokenAccount(usdcMint, buyer.publicKey);
+    const buyerOutput =
+      outputAccount ??
+      (outputMint.equals(usdcMint)
+        ? buyerUsdcOut
+        : await tokenAccount(outputMint, buyer.publicKey));
+    await mintTo(
+      provider.connection,
+      admin,
+      usdcMint,
+      buyerInput,
+      admin,
+      EXECUTION_AMOUNT
+    );
+    const route = await swap.methods
+      .swap(new anchor.BN(EXECUTION_AMOUNT), new anchor.BN(fill))
+      .accountsPartial({
+        tokenProgram: TOKEN_PROGRAM_ID,
+        userTransferAuthority: buyer.publicKey,
+        userSourceTokenAccount: buyerInput,
+        userDestinationTokenAccount: buyerOutput,
+        destinationTokenAccount: routeDestination,
+        poolSource: poolOf(usdcMint),
+        poolDestination: poolOf(outputMint),
+        poolAuthority,
+      })
+      .instruction();
+    if (discriminator) {
+      Buffer.from(discriminator).copy(route.data);
+    }
+    await program.methods
+      .executeStrategy(
+        new anchor.BN(EXECUTION_AMOUNT),
+        new anchor.BN(minOutput),
+        route.data,
+        route.keys.map(({ pubkey, isWritable, isSigner }) => ({
+          pubkey,
+          isWritable,
+          isSigner,
+        }))
+      )
+      .accounts({
+        strategy: target,
+        userStrategy: userStrategyOf(buyer.publicKey, target),
+        executor: buyer.publicKey,
+        execution,
+        supersedingVersion,
+        inputMint: usdcMint,
+        outputMint,
+        executorInputAta: buyerInput,
+        executorOutputAta: buyerOutput,
+        creatorOutputAta,
+        inputPriceFeed: priceFeedOf(usdcMint),
+        outputPriceFeed: priceFeedOf(outputMint),
+        jupiterProgram: swap.programId,
+        systemProgram: SystemProgram.programId,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .remainingAccounts(route.keys)
+      .signers([buyer])
+      .rpc();
+    return execution;
+  }
+
+  it("Should only let the admin set the commission", async () => {
+    try {
+      await program.methods
+        .setMarketplaceCommission(COMMISSION_BPS)
+        .accounts({ globalState, admin: creator.publicKey })
+        .signers([creator])
+        .rpc();
+      assert.fail("Only the admin may set the commission");
+    } catch (error) {
+      assert.include(error.message, "Unauthorized");
+    }
+
+    try {
+      await program.methods
+        .setMarketplaceCommission(5_000)
+        .accounts({ globalState, admin: admin.publicKey })
+        .signers([admin])
+        .rpc();
+      assert.fail("Commission above the cap should be rejected");
+    } catch (error) {
+      assert.include(error.message, "InvalidCommission");
+    }
+
+    await program.methods
+      .setMarketplaceCommission(COMMISSION_BPS)
+      .accounts({ globalState, admin: admin.publicKey })
+      .signers([admin])
+      .rpc();
+
+    const state = await program.account.globalState.fetch(globalState);
+    assert.equal(state.marketplaceCommissionBps, COMMISSION_BPS);
+  });
+
+  it("Should validate payment accounts and split the price", async () => {
+    const buyerUsdc = await tokenAccount(usdcMint, buyer.publicKey);
+    const buyerOther = await tokenAccount(otherMint, buyer.publicKey);
+    const creatorUsdc = await tokenAccount(usdcMint, creator.publicKey);
+    const strangerUsdc = await tokenAccount(
+      usdcMint,
+      Keypair.generate().publicKey
+    );
+    for (const [mint, account] of [
+      [usdcMint, buyerUsdc],
+      [otherMint, buyerOther],
+    ]) {
+      await mintTo(provider.connection, admin, mint, account, admin, PRICE);
+    }
+
+    const [userStrategy] = PublicKey.findProgramAddressSync(
+      [
+        Buffer.from("user_strategy"),
+        buyer.publicKey.toBuffer(),
+        strategy.toBuffer(),
+      ],
+      program.programId
+    );
+    const buy = (
+      buyerTokenAccount: PublicKey,
+      creatorTokenAccount: PublicKey,
+      maxPrice = PRICE,
+      expectedMint = usdcMint
+    ) =>
+      program.methods
+        .buyStrategy(new anchor.BN(maxPrice), expectedMint)
+        .accounts({
+          strategy,
+          userStrategy,
+          buyer: buyer.publicKey,
+          creator: creator.publicKey,
+          globalState,
+          paymentMint: usdcMint,
+          buyerTokenAccount,
+          creatorTokenAccount,
+          feeVaultAta: feeVault,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([buyer])
+        .rpc();
+
+    // Paying in any other token is rejected
+    try {
+      await buy(buyerOther, creatorUsdc);
+      assert.fail("Payment in the wrong mint should be rejected");
+    } catch (error) {
+      assert.include(error.message, "PaymentMintMismatch");
+    }
+
+    // The proceeds must go to the creator
+    try {
+      await buy(buyerUsdc, strangerUsdc);
+      assert.fail("Proceeds to a stranger should be rejected");
+    } catch (error) {
+      assert.include(error.message, "InvalidPaymentAccount");
+    }
+
+    // The buyer's terms bound what the creator can charge
+    try {
+      await buy(buyerUsdc, creatorUsdc, PRICE - 1);
+      assert.fail("A price above the buyer's maximum should be rejected");
+    } catch (error) {
+      assert.include(error.message, "PriceAboveMaximum");
+    }
+    try {
+      await buy(buyerUsdc, creatorUsdc, PRICE, otherMint);
+      assert.fail("A payment mint the buyer did not expect should be rejected");
+    } catch (error) {
+      assert.include(error.message, "PaymentMintMismatch");
+    }
+
+    const treasuryBefore = await getTokenBalance(feeVault);
+    await buy(buyerUsdc, creatorUsdc);
+
+    const commission = Math.ceil((PRICE * COMMISSION_BPS) / 10_000);
+    assert.equal(await getTokenBalance(buyerUsdc), 0);
+    assert.equal(await getTokenBalance(creatorUsdc), PRICE - commission);
+    assert.equal(await getTokenBalance(feeVault) - treasuryBefore, commission);
+
+    const purchased = await program.account.strategy.fetch(strategy);
+    assert.equal(purchased.totalPurchases.toNumber(), 1);
+  });
+
+  it("Should expire subscriptions after the grace period", async () => {
+    const SUBSCRIPTION_ID = new anchor.BN(2);
+    const PERIOD = 2; // seconds
+    const GRACE = 1; // seconds
+    const [subscription] = PublicKey.findProgramAddressSync(
+      [
+        Buffer.from("strategy"),
+        creator.publicKey.toBuffer(),
+        SUBSCRIPTION_ID.toArrayLike(Buffer, "le", 8),
+      ],
+      program.programId
+    );
+    await program.methods
+      .createStrategy(
+        SUBSCRIPTION_ID,
+        "Monthly Rebalancer",
+        "Marketplace subscription strategy",
+        new anchor.BN(PRICE),
+        { rebalancing: {} },
+        {
+          inputToken: usdcMint,
+          outputToken: otherMint,
+          minProfitBps: 0,
+          maxSlippageBps: 50,
+          executionInterval: new anchor.BN(0),
+        }
+      )
+      .accounts({
+        strategy: subscription,
+        creator: creator.publicKey,
+        paymentMint: usdcMint,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([creator])
+      .rpc();
+    await program.methods
+      .setSubscriptionTerms(new anchor.BN(PERIOD), new anchor.BN(GRACE))
+      .accounts({ strategy: subscription, creator: creator.publicKey })
+      .signers([creator])
+      .rpc();
+
+    const buyerUsdc = await tokenAccount(usdcMint, buyer.publicKey);
+    const creatorUsdc = await tokenAccount(usdcMint, creator.publicKey);
+    await mintTo(
+      provider.connection,
+      admin,
+      usdcMint,
+      buyerUsdc,
+      admin,
+      2 * PRICE
+    );
+    const payment = {
+      globalState,
+      paymentMint: usdcMint,
+      creatorTokenAccount: creatorUsdc,
+      feeVaultAta: feeVault,
+      tokenProgram: TOKEN_PROGRAM_ID,
+    };
+
+    const [userStrategy] = PublicKey.findProgramAddressSync(
+      [
+        Buffer.from("user_strategy"),
+        buyer.publicKey.toBuffer(),
+        subscription.toBuffer(),
+      ],
+      program.programId
+    );
+    await program.methods
+      .buyStrategy(new anchor.BN(PRICE), usdcMint)
+      .accounts({
+        ...payment,
+        strategy: subscription,
+        userStrategy,
+        buyer: buyer.publicKey,
+        creator: creator.publicKey,
+        buyerTokenAccount: buyerUsdc,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([buyer])
+      .rpc();
+
+    let purchase = await program.account.userStrategy.fetch(userStrategy);
+    assert.equal(
+      purchase.expiresAt.toNumber() - purchase.purchasedAt.toNumber(),
+      PERIOD
+    );
+
+    const execute = () => executeStrategy(subscription);
+
+    // A one-way route realizes nothing; its result is left for the attestor
+    const opened = await program.account.strategyExecution.fetch(
+      await execute()
+    );
+    assert.equal(opened.outputAmount.toNumber(), EXECUTION_AMOUNT);
+    assert.equal(opened.profit.toNumber(), 0);
+    assert.equal(opened.recordedAt.toNumber(), 0);
+
+    // Past the period and the grace period
+    await new Promise((resolve) =>
+      setTimeout(resolve, (PERIOD + GRACE + 2) * 1000)
+    );
+    try {
+      await execute();
+      assert.fail("Expired subscriptions cannot execute");
+    } catch (error) {
+      assert.include(error.message, "SubscriptionExpired");
+    }
+
+    // A lapsed subscription restarts from the renewal, at the agreed price
+    const renew = (maxPrice: number) =>
+      program.methods
+        .renewSubscription(new anchor.BN(maxPrice), usdcMint)
+        .accounts({
+          ...payment,
+          strategy: subscription,
+          userStrategy,
+          subscriber: buyer.publicKey,
+          subscriberTokenAccount: buyerUsdc,
+        })
+        .signers([buyer])
+        .rpc();
+    try {
+      await renew(PRICE - 1);
+      assert.fail("A renewal above the subscriber's maximum should fail");
+    } catch (error) {
+      assert.include(error.message, "PriceAboveMaximum");
+    }
+    await renew(PRICE);
+
+    purchase = await program.account.userStrategy.fetch(userStrategy);
+    assert.isAbove(
+      purchase.expiresAt.toNumber(),
+      purchase.purchasedAt.toNumber() + PERIOD + GRACE
+    );
+    await execute();
+
+    // One-time purchases cannot be renewed
+    const [oneTime] = PublicKey.findProgramAddressSync(
+      [
+        Buffer.from("user_strategy"),
+        buyer.publicKey.toBuffer(),
+        strategy.toBuffer(),
+      ],
+      program.programId
+    );
+    try {
+      await program.methods
+        .renewSubscription(new anchor.BN(PRICE), usdcMint)
+        .accounts({
+          ...payment,
+          strategy,
+          userStrategy: oneTime,
+          subscriber: buyer.publicKey,
+          subscriberTokenAccount: buyerUsdc,
+        })
+        .signers([buyer])
+        .rpc();
+      assert.fail("Only subscriptions can be renewed");
+    } catch (error) {
+      assert.include(error.message, "NotASubscription");
+    }
+  });
+
+  it("Should keep buyers on their pinned parameters", async () => {
+    const purchase = userStrategyOf(buyer.publicKey, strategy);
+    const [version1] = PublicKey.findProgramAddressSync(
+      [
+        Buffer.from("strategy_version"),
+        strategy.toBuffer(),
+        new anchor.BN(1).toArrayLike(Buffer, "le", 4),
+      ],
+      program.programId
+    );
+    const original = (await program.account.strategy.fetch(strategy))
+      .parameters;
+    const wider = { ...original, maxSlippageBps: 300 };
+
+    try {
+      await program.methods
+        .updateStrategyParameters(wider)
+        .accounts({
+          strategy,
+          strategyVersion: version1,
+          creator: buyer.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([buyer])
+        .rpc();
+      assert.fail("Only the creator can publish parameters");
+    } catch (error) {
+      assert.include(error.message, "Unauthorized");
+    }
+
+    await program.methods
+      .updateStrategyParameters(wider)
+      .accounts({
+        strategy,
+        strategyVersion: version1,
+        creator: creator.publicKey,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([creator])
+      .rpc();
+
+    const record = await program.account.strategyVersion.fetch(version1);
+    assert.equal(record.parametersVersion, 1);
+    assert.equal(record.previous.maxSlippageBps, original.maxSlippageBps);
+    assert.equal(record.parameters.maxSlippageBps, 300);
+    assert.equal(record.changedFields, 1 << 3); // max slippage only
+    assert.equal(
+      (await program.account.strategy.fetch(strategy)).parametersVersion,
+      1
+    );
+
+    // The buyer was not moved
+    let pinned = await program.account.userStrategy.fetch(purchase);
+    assert.equal(pinned.pinnedVersion, 0);
+
+    // Pinned to version 0, so the record holding it must come along
+    try {
+      await executeStrategy(strategy);
+      assert.fail("Version 0 is no longer on the strategy");
+    } catch (error) {
+      assert.include(error.message, "PinnedVersionMissing");
+    }
+    await executeStrategy(strategy, { supersedingVersion: version1 });
+
+    await program.methods
+      .adoptLatestParameters()
+      .accounts({ strategy, userStrategy: purchase, owner: buyer.publicKey })
+      .signers([buyer])
+      .rpc();
+    pinned = await program.account.userStrategy.fetch(purchase);
+    assert.equal(pinned.pinnedVersion, 1);
+    await executeStrategy(strategy);
+
+    // Republishing the same set is not a new version
+    const [version2] = PublicKey.findProgramAddressSync(
+      [
+        Buffer.from("strategy_version"),
+        strategy.toBuffer(),
+        new anchor.BN(2).toArrayLike(Buffer, "le", 4),
+      ],
+      program.programId
+    );
+    try {
+      await program.methods
+        .updateStrategyParameters(wider)
+        .accounts({
+          strategy,
+          strategyVersion: version2,
+          creator: creator.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([creator])
+        .rpc();
+      assert.fail("Unchanged parameters are rejected");
+    } catch (error) {
+      assert.include(error.message, "ParametersUnchanged");
+    }
+  });
+
+  it("Should only accept execution results from the attestor", async () => {
+    const attestor = Keypair.generate();
+
+    // Yield strategies earn after the swap, so their results are attested
+    const YIELD_ID = new anchor.BN(3);
+    const [yieldStrategy] = PublicKey.findProgramAddressSync(
+      [
+        Buffer.from("strategy"),
+        creator.publicKey.toBuffer(),
+        YIELD_ID.toArrayLike(Buffer, "le", 8),
+      ],
+      program.programId
+    );
+    await program.methods
+      .createStrategy(
+        YIELD_ID,
+        "Stable Yield",
+        "Marketplace yield strategy",
+        new anchor.BN(PRICE),
+        { yieldFarming: {} },
+        {
+          inputToken: usdcMint,
+          outputToken: otherMint,
+          minProfitBps: 0,
+          maxSlippageBps: 50,
+          executionInterval: new anchor.BN(0),
+        }
+      )
+      .accounts({
+        strategy: yieldStrategy,
+        creator: creator.publicKey,
+        paymentMint: usdcMint,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([creator])
+      .rpc();
+
+    const buyerUsdc = await tokenAccount(usdcMint, buyer.publicKey);
+    await mintTo(provider.connection, admin, usdcMint, buyerUsdc, admin, PRICE);
+    const purchase = userStrategyOf(buyer.publicKey, yieldStrategy);
+    await program.methods
+      .buyStrategy(new anchor.BN(PRICE), usdcMint)
+      .accounts({
+        strategy: yieldStrategy,
+        userStrategy: purchase,
+        buyer: buyer.publicKey,
+        creator: creator.publicKey,
+        globalState,
+        paymentMint: usdcMint,
+        buyerTokenAccount: buyerUsdc,
+        creatorTokenAccount: await tokenAccount(usdcMint, creator.publicKey),
+        feeVaultAta: feeVault,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([buyer])
+      .rpc();
+
+    // Its result is only attested, so there is no profit to take a share of
+    try {
+      await program.methods
+        .setProfitShare(1_000)
+        .accounts({ strategy: yieldStrategy, creator: creator.publicKey })
+        .signers([creator])
+        .rpc();
+      assert.fail("Yield strategies cannot take a profit share");
+    } catch (error) {
+      assert.include(error.message, "ProfitShareNotRealizable");
+    }
+
+    const execution = await executeStrategy(yieldStrategy);
+    const pending = await program.account.strategyExecution.fetch(execution);
+    assert.equal(pending.recordedAt.toNumber(), 0);
+    const record = (signer: Keypair, target = execution) =>
+      program.methods
+        .recordExecutionResult(
+          new anchor.BN(1_050_000),
+          new anchor.BN(50_000),
+          true
+        )
+        .accounts({
+          globalState,
+          strategy: yieldStrategy,
+          userStrategy: purchase,
+          execution: target,
+          attestor: signer.publicKey,
+        })
+        .signers([signer])
+        .rpc();
+
+    // Nobody may record until the admin names an attestor
+    try {
+      await record(buyer);
+      assert.fail("Executors cannot report their own results");
+    } catch (error) {
+      assert.include(error.message, "NotAttestor");
+    }
+
+    try {
+      await program.methods
+        .setExecutionAttestor(attestor.publicKey)
+        .accounts({ globalState, admin: buyer.publicKey })
+        .signers([buyer])
+        .rpc();
+      assert.fail("Only the admin names the attestor");
+    } catch (error) {
+      assert.include(error.message, "Unauthorized");
+    }
+    await program.methods
+      .setExecutionAttestor(attestor.publicKey)
+      .accounts({ globalState, admin: admin.publicKey })
+      .signers([admin])
+      .rpc();
+
+    // An execution of another strategy can't be booked here
+    const [subscription] = PublicKey.findProgramAddressSync(
+      [
+        Buffer.from("strategy"),
+        creator.publicKey.toBuffer(),
+        new anchor.BN(2).toArrayLike(Buffer, "le", 8),
+      ],
+      program.programId
+    );
+    const [otherExecution] = PublicKey.findProgramAddressSync(
+      [
+        Buffer.from("execution"),
+        subscription.toBuffer(),
+        new anchor.BN(0).toArrayLike(Buffer, "le", 8),
+      ],
+      program.programId
+    );
+    try {
+      await record(attestor, otherExecution);
+      assert.fail("Execution belongs to another strategy");
+    } catch (error) {
+      assert.include(error.message, "InvalidExecution");
+    }
+
+    const before = await program.account.strategy.fetch(yieldStrategy);
+    await record(attestor);
+
+    const recorded = await program.account.strategyExecution.fetch(execution);
+    assert.isAbove(recorded.recordedAt.toNumber(), 0);
+    assert.equal(recorded.outputAmount.toNumber(), 1_050_000);
+    assert.equal(recorded.profit.toNumber(), 50_000);
+    assert.isTrue(recorded.success);
+    const after = await program.account.strategy.fetch(yieldStrategy);
+    assert.equal(
+      after.totalProfit.toNumber() - before.totalProfit.toNumber(),
+      50_000
+    );
+    // The rate follows recorded outcomes, not every execution
+    assert.equal(
+      after.recordedExecutions.toNumber(),
+      before.recordedExecutions.toNumber() + 1
+    );
+    assert.equal(
+      after.successfulExecutions.toNumber(),
+      before.successfulExecutions.toNumber() + 1
+    );
+    assert.equal(
+      after.successRate,
+      Math.floor(
+        (after.successfulExecutions.toNumber() * 10_000) /
+          after.recordedExecutions.toNumber()
+      )
+    );
+    assert.equal(recorded.version, 1);
+
+    try {
+      await record(attestor);
+      assert.fail("Results are recorded once");
+    } catch (error) {
+      assert.include(error.message, "ResultAlreadyRecorded");
+    }
+  });
+
+  it("Should trade with the pinned parameters enforced", async () => {
+    const purchase = userStrategyOf(buyer.publicKey, strategy);
+
+    // The swap must use the strategy's tokens
+    try {
+      await executeStrategy(strategy, { outputMint: otherMint });
+      assert.fail("Output token differs from the strategy");
+    } catch (error) {
+      assert.include(error.message, "TokenMismatch");
+    }
+
+    // Both sides of the fill are measured, so they need their own accounts
+    try {
+      await executeStrategy(strategy, {
+        outputAccount: await tokenAccount(usdcMint, buyer.publicKey),
+      });
+      assert.fail("Input and output share an account");
+    } catch (error) {
+      assert.include(error.message, "DuplicateTokenAccount");
+    }
+
+    // ...and the route has to pay out where the output is measured
+    try {
+      await executeStrategy(strategy, {
+        routeDestination: await tokenAccount(usdcMint, creator.publicKey),
+      });
+      assert.fail("Route output diverted from the executor");
+    } catch (error) {
+      assert.include(error.message, "RouteOutputMismatch");
+    }
+
+    // ...which is only known for Jupiter's `route` (mock_swap shares its
+    // discriminator); `shared_accounts_route` orders its accounts differently
+    try {
+      await executeStrategy(strategy, {
+        discriminator: [193, 32, 155, 51, 65, 214, 156, 129],
+      });
+      assert.fail("Only route instructions can be checked");
+    } catch (error) {
+      assert.include(error.message, "UnsupportedSwapInstruction");
+    }
+
+    // USDC trades at $1; a fill 10% under that breaks the 3% bound
+    try {
+      await executeStrategy(strategy, { fill: 900_000 });
+      assert.fail("Output below the oracle slippage bound");
+    } catch (error) {
+      assert.include(error.message, "SlippageExceeded");
+    }
+    // ...and a fill under the quote breaks `min_output`
+    try {
+      await executeStrategy(strategy, { minOutput: EXECUTION_AMOUNT + 1 });
+      assert.fail("Output below the quote");
+    } catch (error) {
+      assert.include(error.message, "SlippageExceeded");
+    }
+
+    // The outcome is recorded in the same instruction, from what moved
+    const outBefore = await getTokenBalance(buyerUsdcOut);
+    const execution = await executeStrategy(strategy);
+    assert.equal(
+      (await getTokenBalance(buyerUsdcOut)) - outBefore,
+      EXECUTION_AMOUNT
+    );
+    const recorded = await program.account.strategyExecution.fetch(execution);
+    assert.isAbove(recorded.recordedAt.toNumber(), 0);
+    assert.equal(recorded.inputAmount.toNumber(), EXECUTION_AMOUNT);
+    assert.equal(recorded.outputAmount.toNumber(), EXECUTION_AMOUNT);
+    assert.equal(recorded.profit.toNumber(), 0);
+    assert.isTrue(recorded.success);
+    assert.isAbove(
+      (await program.account.userStrategy.fetch(purchase)).lastExecutedAt
+        .toNumber(),
+      0
+    );
+
+    const adopt = async (changes: object) => {
+      const current = await program.account.strategy.fetch(strategy);
+      const [next] = PublicKey.findProgramAddressSync(
+        [
+          Buffer.from("strategy_version"),
+          strategy.toBuffer(),
+          new anchor.BN(current.parametersVersion + 1).toArrayLike(
+            Buffer,
+            "le",
+            4
+          ),
+        ],
+        program.programId
+      );
+      await program.methods
+        .updateStrategyParameters({ ...current.parameters, ...changes })
+        .accounts({
+          strategy,
+          strategyVersion: next,
+          creator: creator.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([creator])
+        .rpc();
+      await program.methods
+        .adoptLatestParameters()
+        .accounts({ strategy, userStrategy: purchase, owner: buyer.publicKey })
+        .signers([buyer])
+        .rpc();
+    };
+
+    // Arbitrage has to clear its profit floor
+    await adopt({ minProfitBps: 100 });
+    try {
+      await executeStrategy(strategy);
+      assert.fail("A break-even arbitrage misses a 1% floor");
+    } catch (error) {
+      assert.include(error.message, "ProfitBelowMinimum");
+    }
+
+    // A one-way swap can't realize an arbitrage
+    await adopt({ minProfitBps: 0, outputToken: otherMint });
+    try {
+      await executeStrategy(strategy);
+      assert.fail("Arbitrage has to come back to USDC");
+    } catch (error) {
+      assert.include(error.message, "ArbitrageNotRoundTrip");
+    }
+
+    // Executions are spaced by the execution interval
+    await adopt({
+      outputToken: usdcMint,
+      executionInterval: new anchor.BN(3600),
+    });
+    try {
+      await executeStrategy(strategy);
+      assert.fail("Executed again within the interval");
+    } catch (error) {
+      assert.include(error.message, "ExecutionTooSoon");
+    }
+  });
+
+  it("Should pay the creator a share of realized profit", async () => {
+    const SHARE_ID = new anchor.BN(4);
+    const SHARE_BPS = 2_000; // 20%
+    const [shared] = PublicKey.findProgramAddressSync(
+      [
+        Buffer.from("strategy"),
+        creator.publicKey.toBuffer(),
+        SHARE_ID.toArrayLike(Buffer, "le", 8),
+      ],
+      program.programId
+    );
+    await program.methods
+      .createStrategy(
+        SHARE_ID,
+        "Profit Sharer",
+        "Marketplace profit share strategy",
+        new anchor.BN(PRICE),
+        { custom: {} },
+        {
+          inputToken: usdcMint,
+          outputToken: usdcMint,
+          minProfitBps: 0,
+          maxSlippageBps: 300,
+          executionInterval: new anchor.BN(0),
+        }
+      )
+      .accounts({
+        strategy: shared,
+        creator: creator.publicKey,
+        paymentMint: usdcMint,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([creator])
+      .rpc();
+    const setShare = (bps: number) =>
+      program.methods
+        .setProfitShare(bps)
+        .accounts({ strategy: shared, creator: creator.publicKey })
+        .signers([creator])
+        .rpc();
+    await setShare(SHARE_BPS);
+
+    const buyerUsdc = await tokenAccount(usdcMint, buyer.publicKey);
+    await mintTo(provider.connection, admin, usdcMint, buyerUsdc, admin, PRICE);
+    const purchase = userStrategyOf(buyer.publicKey, shared);
+    await program.methods
+      .buyStrategy(new anchor.BN(PRICE), usdcMint)
+      .accounts({
+        strategy: shared,
+        userStrategy: purchase,
+        buyer: buyer.publicKey,
+        creator: creator.publicKey,
+        globalState,
+        paymentMint: usdcMint,
+        buyerTokenAccount: buyerUsdc,
+        creatorTokenAccount: await tokenAccount(usdcMint, creator.publicKey),
+        feeVaultAta: feeVault,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([buyer])
+      .rpc();
+
+    // Buyers are never charged more than they signed up for
+    try {
+      await setShare(SHARE_BPS + 1);
+      assert.fail("Profit share raised after a purchase");
+    } catch (error) {
+      assert.include(error.message, "ProfitShareIncrease");
+    }
+
+    // A round trip 10% up: 100_000 profit, 20_000 of it to the creator
+    const creatorUsdc = await tokenAccount(usdcMint, creator.publicKey);
+    const GAIN_FILL = 1_100_000;
+    try {
+      await executeStrategy(shared, { fill: GAIN_FILL });
+      assert.fail("The creator's account is needed to pay the share");
+    } catch (error) {
+      assert.include(error.message, "CreatorAccountMissing");
+    }
+    const creatorBefore = await getTokenBalance(creatorUsdc);
+    const outBefore = await getTokenBalance(buyerUsdcOut);
+    const gain = await executeStrategy(shared, {
+      fill: GAIN_FILL,
+      creatorOutputAta: creatorUsdc,
+    });
+    assert.equal(await getTokenBalance(creatorUsdc) - creatorBefore, 20_000);
+    assert.equal(
+      (await getTokenBalance(buyerUsdcOut)) - outBefore,
+      GAIN_FILL - 20_000
+    );
+    const realized = await program.account.strategyExecution.fetch(gain);
+    assert.equal(realized.profit.toNumber(), 100_000);
+    assert.isAbove(realized.recordedAt.toNumber(), 0);
+
+    let strategyState = await program.account.strategy.fetch(shared);
+    let purchaseState = await program.account.userStrategy.fetch(purchase);
+    // Booked at its USD value: 20_000 USDC at $1
+    assert.equal(strategyState.creatorEarnings.toNumber(), 20_000);
+    assert.equal(purchaseState.profitSharePaid.toNumber(), 20_000);
+
+    // A 2% loss pays nothing
+    await executeStrategy(shared, {
+      fill: 980_000,
+      creatorOutputAta: creatorUsdc,
+    });
+    assert.equal(await getTokenBalance(creatorUsdc) - creatorBefore, 20_000);
+    strategyState = await program.account.strategy.fetch(shared);
+    purchaseState = await program.account.userStrategy.fetch(purchase);
+    assert.equal(strategyState.creatorEarnings.toNumber(), 20_000);
+    assert.equal(purchaseState.profitSharePaid.toNumber(), 20_000);
+
+    // The same share with USDC at $0.50 adds half the value
+    const setUsdcPrice = (price: number) =>
+      program.methods
+        .updatePriceFeed(new anchor.BN(price))
+        .accounts({
+          admin: admin.publicKey,
+          globalState,
+          priceFeed: priceFeedOf(usdcMint),
+          mint: usdcMint,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([admin])
+        .rpc();
+    await setUsdcPrice(500_000);
+    try {
+      await executeStrategy(shared, {
+        fill: GAIN_FILL,
+        creatorOutputAta: creatorUsdc,
+      });
+    } finally {
+      await setUsdcPrice(1_000_000);
+    }
+    assert.equal(await getTokenBalance(creatorUsdc) - creatorBefore, 40_000);
+    strategyState = await program.account.strategy.fetch(shared);
+    purchaseState = await program.account.userStrategy.fetch(purchase);
+    assert.equal(strategyState.creatorEarnings.toNumber(), 30_000);
+    assert.equal(purchaseState.profitSharePaid.toNumber(), 30_000);
+
+    // A one-way route leaves its result to the attestor, where no share
+    // could be collected, so a profit-share strategy may not run one
+    const [oneWay] = PublicKey.findProgramAddressSync(
+      [
+        Buffer.from("strategy_version"),
+        shared.toBuffer(),
+        new anchor.BN(strategyState.parametersVersion + 1).toArrayLike(
+          Buffer,
+          "le",
+          4
+        ),
+      ],
+      program.programId
+    );
+    await program.methods
+      .updateStrategyParameters({
+        ...strategyState.parameters,
+        outputToken: otherMint,
+      })
+      .accounts({
+        strategy: shared,
+        strategyVersion: oneWay,
+        creator: creator.publicKey,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([creator])
+      .rpc();
+    await program.methods
+      .adoptLatestParameters()
+      .accounts({
+        strategy: shared,
+        userStrategy: purchase,
+        owner: buyer.publicKey,
+      })
+      .signers([buyer])
+      .rpc();
+    try {
+      await executeStrategy(shared, { creatorOutputAta: creatorUsdc });
+      assert.fail("A profit share needs a realizing route");
+    } catch (error) {
+      assert.include(error.message, "ProfitShareNotRealizable");
+    }
+  });
+});
@@ -0,0 +1,318 @@
+import * as anchor from "@coral-xyz/anchor";
+import { Program } from "@coral-xyz/anchor";
+import { SolanaAiDefiAggregator } from "../target/types/solana_ai_defi_aggregator";
+import { PublicKey, SystemProgram } from "@solana/web3.js";
+import { assert } from "chai";
+
+// Fixtures in tests/fixtures are loaded by the validator (see Anchor.toml).
+// Each holds an account written with the layout from before the trailing
+// `version` byte existed:
+//   legacy_vault         548 bytes, v0: no version byte or any later field
+//   legacy_global_state   44 bytes, the old GlobalState::LEN had a spare byte
+//   legacy_user_state     57 bytes, one byte short of the current UserState
+//   legacy_strategy      427 bytes, the old Strategy::MAX_SIZE
+//   legacy_position       81 bytes, v0: no version byte, no lock_until
+//   legacy_user_strategy  97 bytes, v0: no version byte, no expires_at
+//   legacy_execution     106 bytes, v0: no version byte, no recorded_at
+//   shrunk_vault         625 bytes, v6, its target weights cut from three
+//                        to one, leaving the old tail behind its data
+// Migration identifies the layout by size, so each fixture sits at exactly
+// one of the sizes migrate.rs accepts. baseline_vault is the 124-byte vault
+// from the original mint-seeded layout, which cannot be migrated in place.
+const LEGACY_VAULT = new PublicKey(
+  "6XhVvbS8NFrejD3tKuw3pMuCdzG7qTcBBgLai7JYccjH"
+);
+const LEGACY_GLOBAL_STATE = new PublicKey(
+  "Gkbg9FA5mG6ZsZhU4V2NwjQDZvRp5i8sDxVdjZjjJziR"
+);
+const LEGACY_USER_STATE = new PublicKey(
+  "3kGfGEr9zXnsUZvchm1Xse2GKDj1WMyuKSxFBJBJSsBL"
+);
+const LEGACY_STRATEGY = new PublicKey(
+  "AfZ3S4piE7sC3oSM468GUPHvCfhRvkS55MHUUyq3xcyk"
+);
+const LEGACY_POSITION = new PublicKey(
+  "9W4yRsS8EigYaAHtiAvuvNwP5iBsoi7DnctjtMQQM3Ss"
+);
+const LEGACY_USER_STRATEGY = new PublicKey(
+  "9o1SGHaRQqCJig8hJ8U1NBcEETKiRPNJ3oiQ2QvqYuUU"
+);
+const LEGACY_EXECUTION = new PublicKey(
+  "DF7yf5wqKb9LLYR4mHTxjWKEZUGEmtTjgffyYDBU8c9t"
+);
+const SHRUNK_VAULT = new PublicKey(
+  "6FzzDCzyjXap6ZF3qmzrCB7AFqAmnBWcud4YdwEZXRua"
+);
+const BASELINE_VAULT = new PublicKey(
+  "BpLckHsDxyG4BJzdrYNx22fG6aEEjipWEUfyQHwFETVg"
+);
+const FIXTURE_MANAGER = new PublicKey(
+  "9E5sXcozkzBEoGLcXDLUF3cYrrHTpKGtiy1KeCxCV8Y1"
+);
+const FIXTURE_MINT = new PublicKey(
+  "8bYtTeiECvrVMd17evgYcK47mHBkPtFm188iXhqsUKZo"
+);
+
+describe("Account Migration Tests", () => {
+  const provider = anchor.AnchorProvider.env();
+  anchor.setProvider(provider);
+
+  const program = anchor.workspace
+    .solanaAiDefiAggregator as Program<SolanaAiDefiAggregator>;
+
+  const migrateAccounts = (account: PublicKey) => ({
+    payer: provider.wallet.publicKey,
+    account,
+    systemProgram: SystemProgram.programId,
+  });
+
+  // Vault migrations also take the vault's claim escrow, created or not
+  const migrateVault = (vault: PublicKey) =>
+    program.methods
+      .migrateVault()
+      .accounts(migrateAccounts(vault))
+      .remainingAccounts([
+        {
+          pubkey: PublicKey.findProgramAddressSync(
+            [Buffer.from("claim_escrow"), vault.toBuffer()],
+            program.programId
+          )[0],
+          isWritable: false,
+          isSigner: false,
+        },
+      ])
+      .rpc();
+
+  async function dataLength(account: PublicKey): Promise<number> {
+    const info = await provider.connection.getAccountInfo(account);
+    return info.data.length;
+  }
+
+  it("Should migrate a legacy vault in place", async () => {
+    assert.equal(await dataLength(LEGACY_VAULT), 548);
+
+    // Too short for the current layout
+    try {
+      await program.account.vault.fetch(LEGACY_VAULT);
+      assert.fail("Legacy vault should not decode");
+    } catch (error) {
+      assert.notInclude(error.message, "Legacy vault should not decode");
+    }
+
+    await migrateVault(LEGACY_VAULT);
+
+    assert.equal(await dataLength(LEGACY_VAULT), 962);
+    const vault = await program.account.vault.fetch(LEGACY_VAULT);
+    assert.equal(vault.version, 10);
+    assert.equal(vault.manager.toString(), FIXTURE_MANAGER.toString());
+    assert.equal(vault.tokenMint.toString(), FIXTURE_MINT.toString());
+    assert.equal(vault.totalAssets.toNumber(), 5_000_000);
+    assert.equal(vault.totalShares.toNumber(), 5_000_000_000);
+    assert.deepEqual(vault.status, { paused: {} });
+    assert.equal(vault.performanceFeeBps, 1000);
+    assert.equal(vault.withdrawEpoch.toNumber(), 3);
+    assert.equal(vault.vaultId.toNumber(), 4242);
+    assert.equal(vault.targetWeights.length, 1);
+    assert.equal(vault.targetWeights[0].weightBps, 10_000);
+    assert.equal(vault.rebalanceThresholdBps, 500);
+    assert.equal(vault.rebalanceMaxSlippageBps, 100);
+    // v2 fields default to no lockup
+    assert.equal(vault.lockPeriod.toNumber(), 0);
+    assert.equal(vault.earlyExitPenaltyBps, 0);
+    // v3 fields default to an open vault
+    assert.deepEqual(vault.depositGate, { open: {} });
+    // v4 fields start with nothing booked
+    assert.equal(vault.strategyAssets.toNumber(), 0);
+    assert.equal(vault.cumulativePnl.toNumber(), 0);
+    // v5 fields start without child vaults
+    assert.equal(vault.childAssets.toNumber(), 0);
+    assert.equal(vault.childCount, 0);
+    // v6 marks fees from the share price at migration
+    assert.equal(vault.highWaterMark.toNumber(), 1_000_000);
+    // v7 starts without tracked strategy mints
+    assert.equal(vault.strategyMints.length, 0);
+    // v8: no claim escrow was ever created, so nothing is owed
+    assert.equal(vault.pendingClaims.toNumber(), 0);
+    // v9 books no cost for mints that were never tracked
+    assert.equal(vault.strategyCosts.length, 0);
+    // v10 starts without parents
+    assert.equal(vault.parentCount, 0);
+
+    // Running it twice is rejected
+    try {
+      await migrateVault(LEGACY_VAULT);
+      assert.fail("Second migration should fail");
+    } catch (error) {
+      assert.include(error.message, "AlreadyMigrated");
+    }
+  });
+
+  it("Should migrate a vault whose target weights shrank", async () => {
+    // Rewriting the vault with fewer weights left the tail of the longer
+    // write behind its data, where v7 puts strategy_mints
+    const before = await provider.connection.getAccountInfo(SHRUNK_VAULT);
+    assert.equal(before.data.length, 625);
+    assert.notEqual(before.data.readUInt32LE(387), 0);
+
+    await migrateVault(SHRUNK_VAULT);
+
+    assert.equal(await dataLength(SHRUNK_VAULT), 962);
+    const vault = await program.account.vault.fetch(SHRUNK_VAULT);
+    assert.equal(vault.version, 10);
+    assert.equal(vault.targetWeights.length, 1);
+    assert.equal(vault.targetWeights[0].weightBps, 10_000);
+    // v6 fields are kept as they were
+    assert.equal(vault.lockPeriod.toNumber(), 86_400);
+    assert.equal(vault.earlyExitPenaltyBps, 300);
+    assert.equal(vault.highWaterMark.toNumber(), 1_000_000);
+    // The stale tail did not turn into tracked mints
+    assert.equal(vault.strategyMints.length, 0);
+    assert.equal(vault.strategyCosts.length, 0);
+  });
+
+  it("Should migrate legacy global and user state", async () => {
+    // The old GlobalState::LEN over-counted by one byte, which became the
+    // version byte; v2 grows it for the marketplace commission
+    assert.equal(await dataLength(LEGACY_GLOBAL_STATE), 44);
+
+    await program.methods
+      .migrateGlobalState()
+      .accounts(migrateAccounts(LEGACY_GLOBAL_STATE))
+      .rpc();
+
+    assert.equal(await dataLength(LEGACY_GLOBAL_STATE), 78);
+    const globalState = await program.account.globalState.fetch(
+      LEGACY_GLOBAL_STATE
+    );
+    assert.equal(globalState.version, 3);
+    assert.equal(globalState.admin.toString(), FIXTURE_MANAGER.toString());
+    assert.equal(globalState.feeRate, 30);
+    assert.equal(globalState.marketplaceCommissionBps, 0);
+    assert.equal(
+      globalState.executionAttestor.toString(),
+      PublicKey.default.toString()
+    );
+
+    await program.methods
+      .migrateUserState()
+      .accounts(migrateAccounts(LEGACY_USER_STATE))
+      .rpc();
+
+    assert.equal(await dataLength(LEGACY_USER_STATE), 58);
+    const userState = await program.account.userState.fetch(LEGACY_USER_STATE);
+    assert.equal(userState.version, 1);
+    assert.equal(userState.totalVolume.toNumber(), 123_456);
+    assert.equal(userState.swaps.toNumber(), 7);
+  });
+
+  it("Should migrate a legacy strategy", async () => {
+    await program.methods
+      .migrateStrategy()
+      .accounts(migrateAccounts(LEGACY_STRATEGY))
+      .rpc();
+
+    assert.equal(await dataLength(LEGACY_STRATEGY), 506);
+    const strategy = await program.account.strategy.fetch(LEGACY_STRATEGY);
+    assert.equal(strategy.version, 6);
+    assert.equal(strategy.name, "Legacy Arb");
+    assert.equal(strategy.totalExecutions.toNumber(), 5);
+    assert.equal(strategy.totalProfit.toNumber(), -250);
+    assert.equal(strategy.parameters.executionInterval.toNumber(), 3600);
+    // Not for sale until the creator picks a payment mint
+    assert.equal(strategy.paymentMint.toString(), PublicKey.default.toString());
+    // ...and sold as a one-time purchase
+    assert.equal(strategy.subscriptionPeriod.toNumber(), 0);
+    assert.equal(strategy.gracePeriod.toNumber(), 0);
+    assert.equal(strategy.parametersVersion, 0);
+    assert.equal(strategy.profitShareBps, 0);
+    assert.equal(strategy.creatorEarnings.toNumber(), 0);
+    // Counters are seeded from the stored rate: 60% of 5 executions
+    assert.equal(strategy.recordedExecutions.toNumber(), 5);
+    assert.equal(strategy.successfulExecutions.toNumber(), 3);
+    assert.equal(strategy.successRate, 6000);
+  });
+
+  it("Should migrate a legacy depositor position", async () => {
+    await program.methods
+      .migratePosition()
+      .accounts(migrateAccounts(LEGACY_POSITION))
+      .rpc();
+
+    assert.equal(await dataLength(LEGACY_POSITION), 222);
+    const position = await program.account.depositorPosition.fetch(
+      LEGACY_POSITION
+    );
+    assert.equal(position.version, 2);
+    assert.equal(position.vault.toString(), LEGACY_VAULT.toString());
+    assert.equal(position.owner.toString(), FIXTURE_MANAGER.toString());
+    assert.equal(position.deposited.toNumber(), 2_500_000);
+    assert.equal(position.lockUntil.toNumber(), 0);
+    // Nothing was locked, so nothing carries over
+    assert.equal(position.shareLocks.length, 0);
+  });
+
+  it("Should migrate a legacy strategy purchase", async () => {
+    await program.methods
+      .migrateUserStrategy()
+      .accounts(migrateAccounts(LEGACY_USER_STRATEGY))
+      .rpc();
+
+    assert.equal(await dataLength(LEGACY_USER_STRATEGY), 126);
+    const userStrategy = await program.account.userStrategy.fetch(
+      LEGACY_USER_STRATEGY
+    );
+    assert.equal(userStrategy.version, 4);
+    assert.equal(userStrategy.owner.toString(), FIXTURE_MANAGER.toString());
+    assert.equal(userStrategy.strategy.toString(), LEGACY_STRATEGY.toString());
+    assert.equal(userStrategy.timesExecuted.toNumber(), 3);
+    assert.equal(userStrategy.totalProfit.toNumber(), 150);
+    // Purchases made before subscriptions never expire
+    assert.equal(userStrategy.expiresAt.toNumber(), 0);
+    assert.equal(userStrategy.pinnedVersion, 0);
+    assert.equal(userStrategy.lastExecutedAt.toNumber(), 0);
+    assert.equal(userStrategy.profitSharePaid.toNumber(), 0);
+  });
+
+  it("Should migrate a legacy execution record as already recorded", async () => {
+    await program.methods
+      .migrateExecution()
+      .accounts(migrateAccounts(LEGACY_EXECUTION))
+      .rpc();
+
+    assert.equal(await dataLength(LEGACY_EXECUTION), 115);
+    const execution = await program.account.strategyExecution.fetch(
+      LEGACY_EXECUTION
+    );
+    assert.equal(execution.version, 1);
+    assert.equal(execution.strategy.toString(), LEGACY_STRATEGY.toString());
+    assert.equal(execution.executor.toString(), FIXTURE_MANAGER.toString());
+    assert.equal(execution.profit.toNumber(), -10_000);
+    // Its outcome was already folded into the strategy's stats
+    assert.equal(
+      execution.recordedAt.toNumber(),
+      execution.executedAt.toNumber()
+    );
+  });
+
+  it("Should refuse to migrate an account as the wrong type", async () => {
+    try {
+      await migrateVault(LEGACY_USER_STATE);
+      assert.fail("A user state is not a vault");
+    } catch (error) {
+      assert.include(error.message, "AccountTypeMismatch");
+    }
+  });
+
+  it("Should refuse to migrate a baseline vault", async () => {
+    assert.equal(await dataLength(BASELINE_VAULT), 124);
+
+    try {
+      await migrateVault(BASELINE_VAULT);
+      assert.fail("A baseline vault should not migrate");
+    } catch (error) {
+      assert.include(error.message, "BaselineVaultNotMigratable");
+    }
+    // Left untouched
+    assert.equal(await dataLength(BASELINE_VAULT), 124);
+  });
+});
@@ -10,12 +10,15 @@ import {
 import {
   TOKEN_PROGRAM_ID,
   ASSOCIATED_TOKEN_PROGRAM_ID,
+  createAccount,
   createMint,
   mintTo,
   getAccount,
+  getMint,
   getOrCreateAssociatedTokenAccount,
 } from "@solana/spl-token";
 import { assert, expect } from "chai";
+import { createHash } from "crypto";
 
 describe("Vault Module Tests", () => {
   const provider = anchor.AnchorProvider.env();
@@ -28,12 +31,16 @@ describe("Vault Module Tests", () => {
   let user1: Keypair;
   let user2: Keypair;
   let tokenMint: PublicKey;
+  let vaultRegistry: PublicKey;
   let vault: PublicKey;
   let vaultBump: number;
   let shareMint: PublicKey;
   let vaultTokenAccount: PublicKey;
 
   const PERFORMANCE_FEE_BPS = 1000; // 10%
+  // Mirrors Vault::VIRTUAL_SHARES / Vault::VIRTUAL_ASSETS
+  const VIRTUAL_SHARES = 1_000;
+  const VIRTUAL_ASSETS = 1;
 
   before(async () => {
     authority = Keypair.generate();
@@ -57,10 +64,11 @@ describe("Vault Module Tests", () => {
     console.log("Token Mint:", tokenMint.toString());
 
     // Derive PDAs
-    [vault, vaultBump] = await PublicKey.findProgramAddress(
-      [Buffer.from("vault"), tokenMint.toBuffer()],
+    [vaultRegistry] = await PublicKey.findProgramAddress(
+      [Buffer.from("vault_registry")],
       program.programId
     );
+    [vault, vaultBump] = await nextVaultAddress();
 
     [shareMint] = await PublicKey.findProgramAddress(
       [Buffer.from("share_mint"), vault.toBuffer()],
@@ -77,6 +85,49 @@ describe("Vault Module Tests", () => {
     console.log("Vault Token Account:", vaultTokenAccount.toString());
   });
 
+  // Vault PDAs are seeded by the id the registry will hand out next
+  async function nextVaultAddress(): Promise<[PublicKey, number]> {
+    try {
+      await program.methods
+        .initializeVaultRegistry()
+        .accounts({
+          payer: authority.publicKey,
+          registry: vaultRegistry,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([authority])
+        .rpc();
+    } catch (e) {
+      // Registry already initialized
+    }
+    const registry = await program.account.vaultRegistry.fetch(vaultRegistry);
+    return await PublicKey.findProgramAddress(
+      [Buffer.from("vault"), registry.vaultCount.toArrayLike(Buffer, "le", 8)],
+      program.programId
+    );
+  }
+
+  // Each vault id gets its own registry entry
+  function registryEntryAddress(vaultId: anchor.BN): PublicKey {
+    return PublicKey.findProgramAddressSync(
+      [
+        Buffer.from("vault_registry_entry"),
+        vaultId.toArrayLike(Buffer, "le", 8),
+      ],
+      program.programId
+    )[0];
+  }
+
+  async function nextRegistryEntry(): Promise<PublicKey> {
+    const registry = await program.account.vaultRegistry.fetch(vaultRegistry);
+    return registryEntryAddress(registry.vaultCount);
+  }
+
+  async function registryEntryOf(vaultKey: PublicKey): Promise<PublicKey> {
+    const state = await program.account.vault.fetch(vaultKey);
+    return registryEntryAddress(state.vaultId);
+  }
+
   async function airdrop(publicKey: PublicKey, amount: number) {
     const sig = await provider.connection.requestAirdrop(
       publicKey,
@@ -103,6 +154,8 @@ describe("Vault Module Tests", () => {
       .initializeVault(PERFORMANCE_FEE_BPS)
       .accounts({
         authority: authority.publicKey,
+        registry: vaultRegistry,
+        registryEntry: await nextRegistryEntry(),
         vault: vault,
         tokenMint: tokenMint,
         shareMint: shareMint,
@@ -116,24 +169,38 @@ describe("Vault Module Tests", () => {
 
     const vaultState = await getVaultState();
 
+    assert.equal(vaultState.manager.toString(), authority.publicKey.toString());
+    // All roles start with the manager
+    assert.equal(vaultState.keeper.toString(), authority.publicKey.toString());
     assert.equal(
-      vaultState.authority.toString(),
+      vaultState.guardian.toString(),
+      authority.publicKey.toString()
+    );
+    assert.equal(
+      vaultState.feeRecipient.toString(),
       authority.publicKey.toString()
     );
     assert.equal(vaultState.tokenMint.toString(), tokenMint.toString());
     assert.equal(vaultState.totalAssets.toNumber(), 0);
     assert.equal(vaultState.totalShares.toNumber(), 0);
     assert.equal(vaultState.performanceFeeBps, PERFORMANCE_FEE_BPS);
-    assert.equal(vaultState.strategyEnabled, false);
+    assert.deepEqual(vaultState.status, { paused: {} });
+
+    const entry = await program.account.vaultRegistryEntry.fetch(
+      registryEntryAddress(vaultState.vaultId)
+    );
+    assert.equal(entry.vault.toString(), vault.toString());
+    assert.equal(entry.tokenMint.toString(), tokenMint.toString());
+    assert.equal(entry.vaultId.toString(), vaultState.vaultId.toString());
 
     console.log("✅ Vault initialized");
   });
 
   // ============================================
-  // TEST 2: First Deposit (1:1 ratio)
+  // TEST 2: First Deposit (1:1 value, 1:1000 units)
   // ============================================
 
-  it("Should handle first deposit with 1:1 ratio", async () => {
+  it("Should handle first deposit at the virtual share ratio", async () => {
     const depositAmount = 1_000_000; // 1 USDC
 
     // Mint tokens to user1
@@ -165,7 +232,7 @@ describe("Vault Module Tests", () => {
 
     // Deposit
     await program.methods
-      .deposit(new anchor.BN(depositAmount))
+      .deposit(new anchor.BN(depositAmount), [])
       .accounts({
         user: user1.publicKey,
         vault: vault,
@@ -173,6 +240,8 @@ describe("Vault Module Tests", () => {
         vaultTokenAccount: vaultTokenAccount,
         userShareAccount: user1ShareAccount[0],
         shareMint: shareMint,
+        allowlistEntry: null,
+        lockedShareAccount: null,
         tokenProgram: TOKEN_PROGRAM_ID,
         associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
         systemProgram: SystemProgram.programId,
@@ -181,13 +250,18 @@ describe("Vault Module Tests", () => {
       .rpc();
 
     // Verify vault state
+    // First deposit mints assets * VIRTUAL_SHARES / VIRTUAL_ASSETS shares;
+    // the share mint carries 3 extra decimals, so the UI ratio stays 1:1
     const vaultState = await getVaultState();
     assert.equal(vaultState.totalAssets.toNumber(), depositAmount);
-    assert.equal(vaultState.totalShares.toNumber(), depositAmount);
+    assert.equal(
+      vaultState.totalShares.toNumber(),
+      depositAmount * VIRTUAL_SHARES
+    );
 
     // Verify shares minted
     const shareBalance = await getTokenBalance(user1ShareAccount[0]);
-    assert.equal(shareBalance, depositAmount);
+    assert.equal(shareBalance, depositAmount * VIRTUAL_SHARES);
 
     console.log("✅ First deposit successful");
     console.log(`   Assets: ${vaultState.totalAssets.toNumber()}`);
@@ -255,15 +329,16 @@ describe("Vault Module Tests", () => {
     console.log(`   Total assets: ${vaultStateBefore.totalAssets.toNumber()}`);
     console.log(`   Total shares: ${vaultStateBefore.totalShares.toNumber()}`);
 
-    // Calculate expected shares
-    // shares = (deposit * totalShares) / totalAssets
+    // Calculate expected shares (rounded down)
+    // shares = deposit * (totalShares + virtual) / (totalAssets + virtual)
     const expectedShares = Math.floor(
-      (depositAmount * vaultStateBefore.totalShares.toNumber()) /
-        (vaultStateBefore.totalAssets.toNumber() + profitAmount)
+      (depositAmount *
+        (vaultStateBefore.totalShares.toNumber() + VIRTUAL_SHARES)) /
+        (vaultStateBefore.totalAssets.toNumber() + VIRTUAL_ASSETS)
     );
 
     await program.methods
-      .deposit(new anchor.BN(depositAmount))
+      .deposit(new anchor.BN(depositAmount), [])
       .accounts({
         user: user2.publicKey,
         vault: vault,
@@ -271,6 +346,8 @@ describe("Vault Module Tests", () => {
         vaultTokenAccount: vaultTokenAccount,
         userShareAccount: user2ShareAccount[0],
         shareMint: shareMint,
+        allowlistEntry: null,
+        lockedShareAccount: null,
         tokenProgram: TOKEN_PROGRAM_ID,
         associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
         systemProgram: SystemProgram.programId,
@@ -288,7 +365,8 @@ describe("Vault Module Tests", () => {
     console.log(`   Actual shares: ${shareBalance}`);
 
     // User2 should get fewer shares due to increased vault value
-    assert.isBelow(shareBalance, depositAmount);
+    assert.equal(shareBalance, expectedShares);
+    assert.isBelow(shareBalance, depositAmount * VIRTUAL_SHARES);
   });
 
   // ============================================
@@ -318,10 +396,11 @@ describe("Vault Module Tests", () => {
     const tokenBalanceBefore = await getTokenBalance(user1TokenAccount.address);
     const vaultStateBefore = await getVaultState();
 
-    // Calculate expected withdrawal
+    // Calculate expected withdrawal (rounded down)
     const expectedAssets = Math.floor(
-      (sharesToWithdraw * vaultStateBefore.totalAssets.toNumber()) /
-        vaultStateBefore.totalShares.toNumber()
+      (sharesToWithdraw *
+        (vaultStateBefore.totalAssets.toNumber() + VIRTUAL_ASSETS)) /
+        (vaultStateBefore.totalShares.toNumber() + VIRTUAL_SHARES)
     );
 
     await program.methods
@@ -363,7 +442,7 @@ describe("Vault Module Tests", () => {
   it("Should enable strategy and execute stub", async () => {
     // First, enable strategy
     await program.methods
-      .setStrategyEnabled(true)
+      .setVaultStatus({ active: {} })
       .accounts({
         authority: authority.publicKey,
         vault: vault,
@@ -372,7 +451,7 @@ describe("Vault Module Tests", () => {
       .rpc();
 
     const vaultState = await getVaultState();
-    assert.equal(vaultState.strategyEnabled, true);
+    assert.deepEqual(vaultState.status, { active: {} });
 
     console.log("✅ Strategy enabled");
 
@@ -387,7 +466,7 @@ describe("Vault Module Tests", () => {
         new anchor.BN(minOutput)
       )
       .accounts({
-        authority: authority.publicKey,
+        keeper: authority.publicKey,
         vault: vault,
         vaultTokenAccount: vaultTokenAccount,
       })
@@ -410,7 +489,7 @@ describe("Vault Module Tests", () => {
           new anchor.BN(95_000)
         )
         .accounts({
-          authority: user1.publicKey, // Wrong authority!
+          keeper: user1.publicKey, // Not the keeper!
           vault: vault,
           vaultTokenAccount: vaultTokenAccount,
         })
@@ -447,7 +526,7 @@ describe("Vault Module Tests", () => {
 
     try {
       await program.methods
-        .deposit(new anchor.BN(0))
+        .deposit(new anchor.BN(0), [])
         .accounts({
           user: user1.publicKey,
           vault: vault,
@@ -455,6 +534,8 @@ describe("Vault Module Tests", () => {
           vaultTokenAccount: vaultTokenAccount,
           userShareAccount: user1ShareAccount[0],
           shareMint: shareMint,
+          allowlistEntry: null,
+          lockedShareAccount: null,
           tokenProgram: TOKEN_PROGRAM_ID,
           associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
           systemProgram: SystemProgram.programId,
@@ -476,10 +557,12 @@ describe("Vault Module Tests", () => {
   it("Should calculate share price correctly", async () => {
     const vaultState = await getVaultState();
 
-    // Share price = (total_assets * 1e6) / total_shares
+    // Share price = ((total_assets + 1) * 1e6 * 1e3) / (total_shares + 1e3)
     const expectedSharePrice = Math.floor(
-      (vaultState.totalAssets.toNumber() * 1_000_000) /
-        vaultState.totalShares.toNumber()
+      ((vaultState.totalAssets.toNumber() + VIRTUAL_ASSETS) *
+        1_000_000 *
+        VIRTUAL_SHARES) /
+        (vaultState.totalShares.toNumber() + VIRTUAL_SHARES)
     );
 
     console.log("✅ Share price calculation verified");
@@ -490,4 +573,1958 @@ describe("Vault Module Tests", () => {
     // Share price should be > 1.0 due to the profit we added
     assert.isAbove(expectedSharePrice, 1_000_000);
   });
+  // ============================================
+  // TEST 9: First-Depositor Inflation Attack
+  // ============================================
+
+  it("Should protect later depositors from share inflation", async () => {
+    // Fresh vault so the attacker is the first depositor
+    const attackMint = await createMint(
+      provider.connection,
+      authority,
+      authority.publicKey,
+      null,
+      6
+    );
+    const [attackVault] = await nextVaultAddress();
+    const [attackShareMint] = await PublicKey.findProgramAddress(
+      [Buffer.from("share_mint"), attackVault.toBuffer()],
+      program.programId
+    );
+    const [attackVaultTokenAccount] = await PublicKey.findProgramAddress(
+      [Buffer.from("vault_token_account"), attackVault.toBuffer()],
+      program.programId
+    );
+
+    await program.methods
+      .initializeVault(PERFORMANCE_FEE_BPS)
+      .accounts({
+        authority: authority.publicKey,
+        registry: vaultRegistry,
+        registryEntry: await nextRegistryEntry(),
+        vault: attackVault,
+        tokenMint: attackMint,
+        shareMint: attackShareMint,
+        vaultTokenAccount: attackVaultTokenAccount,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+        rent: SYSVAR_RENT_PUBKEY,
+      })
+      .signers([authority])
+      .rpc();
+
+    const depositInto = async (user: Keypair, amount: number) => {
+      const userTokenAccount = await getOrCreateAssociatedTokenAccount(
+        provider.connection,
+        authority,
+        attackMint,
+        user.publicKey
+      );
+      await mintTo(
+        provider.connection,
+        authority,
+        attackMint,
+        userTokenAccount.address,
+        authority,
+        amount
+      );
+      const [userShareAccount] = await PublicKey.findProgramAddress(
+        [
+          user.publicKey.toBuffer(),
+          TOKEN_PROGRAM_ID.toBuffer(),
+          attackShareMint.toBuffer(),
+        ],
+        ASSOCIATED_TOKEN_PROGRAM_ID
+      );
+      await program.methods
+        .deposit(new anchor.BN(amount), [])
+        .accounts({
+          user: user.publicKey,
+          vault: attackVault,
+          userTokenAccount: userTokenAccount.address,
+          vaultTokenAccount: attackVaultTokenAccount,
+          userShareAccount,
+          shareMint: attackShareMint,
+          allowlistEntry: null,
+          lockedShareAccount: null,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([user])
+        .rpc();
+      return await getTokenBalance(userShareAccount);
+    };
+
+    // Attacker deposits 1 unit, then "donates" 1 USDC to inflate the price
+    const attackerShares = await depositInto(user1, 1);
+    await program.methods
+      .testIncreaseAssets(new anchor.BN(1_000_000))
+      .accounts({ vault: attackVault, authority: authority.publicKey })
+      .rpc();
+
+    // Victim deposits 1 USDC and must still receive a fair amount of shares
+    const victimShares = await depositInto(user2, 1_000_000);
+
+    const state = await program.account.vault.fetch(attackVault);
+    const valueOf = (shares: number) =>
+      Math.floor(
+        (shares * (state.totalAssets.toNumber() + VIRTUAL_ASSETS)) /
+          (state.totalShares.toNumber() + VIRTUAL_SHARES)
+      );
+
+    console.log("✅ Inflation attack mitigated");
+    console.log(`   Attacker shares: ${attackerShares}`);
+    console.log(`   Victim shares: ${victimShares}`);
+    console.log(`   Victim value: ${valueOf(victimShares)}`);
+
+    // Victim keeps (almost) all of their deposit, attacker loses the donation
+    assert.isAbove(victimShares, 0);
+    assert.isAtLeast(valueOf(victimShares), 990_000);
+    assert.isBelow(valueOf(attackerShares), 1_000_001);
+  });
+  // ============================================
+  // TEST 10: Mint / Redeem Assets with Slippage Bounds
+  // ============================================
+
+  it("Should mint exact shares and redeem exact assets", async () => {
+    const user1TokenAccount = await getOrCreateAssociatedTokenAccount(
+      provider.connection,
+      authority,
+      tokenMint,
+      user1.publicKey
+    );
+    await mintTo(
+      provider.connection,
+      authority,
+      tokenMint,
+      user1TokenAccount.address,
+      authority,
+      10_000_000
+    );
+    const [user1ShareAccount] = await PublicKey.findProgramAddress(
+      [
+        user1.publicKey.toBuffer(),
+        TOKEN_PROGRAM_ID.toBuffer(),
+        shareMint.toBuffer(),
+      ],
+      ASSOCIATED_TOKEN_PROGRAM_ID
+    );
+
+    const sharesToMint = 500_000_000;
+    const sharesBefore = await getTokenBalance(user1ShareAccount);
+
+    // A zero max-assets bound must be rejected
+    try {
+      await program.methods
+        .mint(new anchor.BN(sharesToMint), new anchor.BN(0), [])
+        .accounts({
+          user: user1.publicKey,
+          vault,
+          userTokenAccount: user1TokenAccount.address,
+          vaultTokenAccount,
+          userShareAccount: user1ShareAccount,
+          shareMint,
+          allowlistEntry: null,
+          lockedShareAccount: null,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([user1])
+        .rpc();
+      assert.fail("Should have failed");
+    } catch (error) {
+      assert.include(error.message, "SlippageExceeded");
+    }
+
+    await program.methods
+      .mint(new anchor.BN(sharesToMint), new anchor.BN(10_000_000), [])
+      .accounts({
+        user: user1.publicKey,
+        vault,
+        userTokenAccount: user1TokenAccount.address,
+        vaultTokenAccount,
+        userShareAccount: user1ShareAccount,
+        shareMint,
+        allowlistEntry: null,
+        lockedShareAccount: null,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([user1])
+      .rpc();
+
+    assert.equal(
+      await getTokenBalance(user1ShareAccount),
+      sharesBefore + sharesToMint
+    );
+
+    const assetsToRedeem = 100_000;
+    const tokensBefore = await getTokenBalance(user1TokenAccount.address);
+
+    await program.methods
+      .redeemAssets(new anchor.BN(assetsToRedeem), new anchor.BN(sharesToMint))
+      .accounts({
+        user: user1.publicKey,
+        vault,
+        userTokenAccount: user1TokenAccount.address,
+        vaultTokenAccount,
+        userShareAccount: user1ShareAccount,
+        shareMint,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .signers([user1])
+      .rpc();
+
+    assert.equal(
+      await getTokenBalance(user1TokenAccount.address),
+      tokensBefore + assetsToRedeem
+    );
+
+    const maxWithdraw = await program.methods
+      .maxWithdraw()
+      .accounts({
+        vault,
+        vaultTokenAccount,
+        ownerShareAccount: user1ShareAccount,
+      })
+      .view();
+    assert.isAbove(maxWithdraw.toNumber(), 0);
+
+    console.log("✅ Mint and redeem_assets successful");
+    console.log(`   Max withdraw: ${maxWithdraw.toNumber()}`);
+  });
+  // ============================================
+  // TEST 11: Deposit Caps and Minimum Deposit
+  // ============================================
+
+  it("Should enforce minimum deposit and per-user cap", async () => {
+    const user2TokenAccount = await getOrCreateAssociatedTokenAccount(
+      provider.connection,
+      authority,
+      tokenMint,
+      user2.publicKey
+    );
+    await mintTo(
+      provider.connection,
+      authority,
+      tokenMint,
+      user2TokenAccount.address,
+      authority,
+      5_000_000
+    );
+    const [user2ShareAccount] = await PublicKey.findProgramAddress(
+      [
+        user2.publicKey.toBuffer(),
+        TOKEN_PROGRAM_ID.toBuffer(),
+        shareMint.toBuffer(),
+      ],
+      ASSOCIATED_TOKEN_PROGRAM_ID
+    );
+    const [user2Position] = await PublicKey.findProgramAddress(
+      [Buffer.from("position"), vault.toBuffer(), user2.publicKey.toBuffer()],
+      program.programId
+    );
+
+    const depositAs = (amount: number) =>
+      program.methods
+        .deposit(new anchor.BN(amount), [])
+        .accounts({
+          user: user2.publicKey,
+          vault,
+          userTokenAccount: user2TokenAccount.address,
+          vaultTokenAccount,
+          userShareAccount: user2ShareAccount,
+          shareMint,
+          position: user2Position,
+          allowlistEntry: null,
+          lockedShareAccount: null,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([user2])
+        .rpc();
+
+    const position = await program.account.depositorPosition.fetch(
+      user2Position
+    );
+    const userCap = position.deposited.toNumber() + 2_000_000;
+
+    await program.methods
+      .setDepositLimits(
+        new anchor.BN(0),
+        new anchor.BN(userCap),
+        new anchor.BN(500_000)
+      )
+      .accounts({ authority: authority.publicKey, vault })
+      .signers([authority])
+      .rpc();
+
+    try {
+      await depositAs(100_000);
+      assert.fail("Should have failed");
+    } catch (error) {
+      assert.include(error.message, "DepositTooSmall");
+    }
+
+    const maxDeposit = await program.methods
+      .maxDeposit()
+      .accounts({ vault, owner: user2.publicKey, position: user2Position })
+      .view();
+    assert.equal(maxDeposit.toNumber(), 2_000_000);
+
+    await depositAs(2_000_000);
+
+    try {
+      await depositAs(500_000);
+      assert.fail("Should have failed");
+    } catch (error) {
+      assert.include(error.message, "UserDepositCapExceeded");
+    }
+
+    // Lift the limits again for the remaining tests
+    await program.methods
+      .setDepositLimits(new anchor.BN(0), new anchor.BN(0), new anchor.BN(0))
+      .accounts({ authority: authority.publicKey, vault })
+      .signers([authority])
+      .rpc();
+
+    console.log("✅ Deposit limits enforced");
+  });
+  // ============================================
+  // TEST 12: Queued Withdrawal Epoch
+  // ============================================
+
+  it("Should queue, settle and claim a withdrawal", async () => {
+    const user1TokenAccount = await getOrCreateAssociatedTokenAccount(
+      provider.connection,
+      authority,
+      tokenMint,
+      user1.publicKey
+    );
+    const [user1ShareAccount] = await PublicKey.findProgramAddress(
+      [
+        user1.publicKey.toBuffer(),
+        TOKEN_PROGRAM_ID.toBuffer(),
+        shareMint.toBuffer(),
+      ],
+      ASSOCIATED_TOKEN_PROGRAM_ID
+    );
+    const [shareEscrow] = await PublicKey.findProgramAddress(
+      [Buffer.from("withdraw_escrow"), vault.toBuffer()],
+      program.programId
+    );
+    const [claimEscrow] = await PublicKey.findProgramAddress(
+      [Buffer.from("claim_escrow"), vault.toBuffer()],
+      program.programId
+    );
+
+    const vaultStateBefore = await getVaultState();
+    const epoch = vaultStateBefore.withdrawEpoch;
+    const epochSeed = epoch.toArrayLike(Buffer, "le", 8);
+    const [withdrawEpoch] = await PublicKey.findProgramAddress(
+      [Buffer.from("withdraw_epoch"), vault.toBuffer(), epochSeed],
+      program.programId
+    );
+    const [withdrawalRequest] = await PublicKey.findProgramAddress(
+      [
+        Buffer.from("withdrawal_request"),
+        vault.toBuffer(),
+        user1.publicKey.toBuffer(),
+        epochSeed,
+      ],
+      program.programId
+    );
+
+    const sharesToQueue = Math.floor(
+      (await getTokenBalance(user1ShareAccount)) / 4
+    );
+
+    await program.methods
+      .requestWithdraw(new anchor.BN(sharesToQueue))
+      .accounts({
+        user: user1.publicKey,
+        vault,
+        userShareAccount: user1ShareAccount,
+        shareEscrow,
+        shareMint,
+        withdrawEpoch,
+        withdrawalRequest,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([user1])
+      .rpc();
+
+    assert.equal(await getTokenBalance(shareEscrow), sharesToQueue);
+
+    // Claiming before settlement must fail
+    try {
+      await program.methods
+        .claimWithdrawal(epoch)
+        .accounts({
+          user: user1.publicKey,
+          vault,
+          withdrawEpoch,
+          withdrawalRequest,
+          claimEscrow,
+          userTokenAccount: user1TokenAccount.address,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([user1])
+        .rpc();
+      assert.fail("Should have failed");
+    } catch (error) {
+      assert.include(error.message, "EpochNotSettled");
+    }
+
+    await program.methods
+      .settleWithdrawEpoch()
+      .accounts({
+        authority: authority.publicKey,
+        vault,
+        withdrawEpoch,
+        vaultTokenAccount,
+        shareEscrow,
+        claimEscrow,
+        shareMint,
+        tokenMint,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([authority])
+      .rpc();
+
+    const epochState = await program.account.withdrawEpoch.fetch(
+      withdrawEpoch
+    );
+    const vaultStateAfter = await getVaultState();
+    assert.isTrue(epochState.settled);
+    assert.equal(
+      vaultStateAfter.withdrawEpoch.toNumber(),
+      epoch.toNumber() + 1
+    );
+    assert.equal(await getTokenBalance(shareEscrow), 0);
+
+    const tokensBefore = await getTokenBalance(user1TokenAccount.address);
+    await program.methods
+      .claimWithdrawal(epoch)
+      .accounts({
+        user: user1.publicKey,
+        vault,
+        withdrawEpoch,
+        withdrawalRequest,
+        claimEscrow,
+        userTokenAccount: user1TokenAccount.address,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([user1])
+      .rpc();
+
+    const received =
+      (await getTokenBalance(user1TokenAccount.address)) - tokensBefore;
+    assert.equal(received, epochState.totalAssets.toNumber());
+
+    console.log("✅ Queued withdrawal settled and claimed");
+    console.log(`   Shares queued: ${sharesToQueue}`);
+    console.log(`   Tokens received: ${received}`);
+  });
+  // ============================================
+  // TEST 13: In-Kind Proportional Withdrawal
+  // ============================================
+
+  it("Should withdraw a pro-rata slice of every vault token", async () => {
+    // Simulate a strategy position: the vault holds a second token
+    const otherMint = await createMint(
+      provider.connection,
+      authority,
+      authority.publicKey,
+      null,
+      9
+    );
+    const vaultOtherAccount = await createAccount(
+      provider.connection,
+      authority,
+      otherMint,
+      vault,
+      Keypair.generate()
+    );
+    await mintTo(
+      provider.connection,
+      authority,
+      otherMint,
+      vaultOtherAccount,
+      authority,
+      1_000_000_000
+    );
+    const user1OtherAccount = await getOrCreateAssociatedTokenAccount(
+      provider.connection,
+      authority,
+      otherMint,
+      user1.publicKey
+    );
+    const user1TokenAccount = await getOrCreateAssociatedTokenAccount(
+      provider.connection,
+      authority,
+      tokenMint,
+      user1.publicKey
+    );
+    const [user1ShareAccount] = await PublicKey.findProgramAddress(
+      [
+        user1.publicKey.toBuffer(),
+        TOKEN_PROGRAM_ID.toBuffer(),
+        shareMint.toBuffer(),
+      ],
+      ASSOCIATED_TOKEN_PROGRAM_ID
+    );
+
+    const vaultStateBefore = await getVaultState();
+    const totalShares = vaultStateBefore.totalShares.toNumber();
+    const sharesToBurn = Math.floor(
+      (await getTokenBalance(user1ShareAccount)) / 2
+    );
+    const baseBefore = await getTokenBalance(vaultTokenAccount);
+    // BN keeps the products exact (they exceed 2^53); the virtual shares
+    // keep their slice
+    const proRata = (amount: number) =>
+      new anchor.BN(amount)
+        .mul(new anchor.BN(sharesToBurn))
+        .div(new anchor.BN(totalShares + VIRTUAL_SHARES))
+        .toNumber();
+    const expectedBase = proRata(baseBefore);
+    const expectedOther = proRata(1_000_000_000);
+    const userBaseBefore = await getTokenBalance(user1TokenAccount.address);
+
+    await program.methods
+      .withdrawInKind(new anchor.BN(sharesToBurn))
+      .accounts({
+        user: user1.publicKey,
+        vault,
+        vaultTokenAccount,
+        userTokenAccount: user1TokenAccount.address,
+        userShareAccount: user1ShareAccount,
+        shareMint,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+      })
+      .remainingAccounts([
+        { pubkey: vaultOtherAccount, isWritable: true, isSigner: false },
+        {
+          pubkey: user1OtherAccount.address,
+          isWritable: true,
+          isSigner: false,
+        },
+      ])
+      .signers([user1])
+      .rpc();
+
+    assert.equal(
+      (await getTokenBalance(user1TokenAccount.address)) - userBaseBefore,
+      expectedBase
+    );
+    assert.equal(
+      await getTokenBalance(user1OtherAccount.address),
+      expectedOther
+    );
+
+    const vaultStateAfter = await getVaultState();
+    assert.equal(
+      vaultStateAfter.totalShares.toNumber(),
+      totalShares - sharesToBurn
+    );
+
+    console.log("✅ In-kind withdrawal successful");
+    console.log(`   Base received: ${expectedBase}`);
+    console.log(`   Other token received: ${expectedOther}`);
+  });
+
+  // ============================================
+  // TEST 14: NAV History and APY
+  // ============================================
+
+  it("Should record NAV snapshots and report APY", async () => {
+    const [navHistory] = PublicKey.findProgramAddressSync(
+      [Buffer.from("nav_history"), vault.toBuffer()],
+      program.programId
+    );
+
+    try {
+      await program.methods
+        .configureNavHistory(new anchor.BN(0))
+        .accounts({ manager: authority.publicKey, vault, navHistory })
+        .signers([authority])
+        .rpc();
+      assert.fail("Interval must be positive");
+    } catch (error) {
+      assert.include(error.message, "InvalidSnapshotInterval");
+    }
+
+    await program.methods
+      .configureNavHistory(new anchor.BN(3600))
+      .accounts({ manager: authority.publicKey, vault, navHistory })
+      .signers([authority])
+      .rpc();
+
+    // Permissionless crank: the provider wallet is neither manager nor keeper
+    await program.methods
+      .recordNavSnapshot()
+      .accounts({ vault, navHistory })
+      .rpc();
+
+    const vaultState = await getVaultState();
+    let history = await program.account.navHistory.fetch(navHistory);
+    assert.equal(history.len, 1);
+    assert.equal(history.head, 1);
+    assert.equal(
+      history.snapshots[0].totalAssets.toNumber(),
+      vaultState.totalAssets.toNumber() + vaultState.adapterAssets.toNumber()
+    );
+
+    // A second snapshot inside the interval is rejected
+    try {
+      await program.methods
+        .recordNavSnapshot()
+        .accounts({ vault, navHistory })
+        .rpc();
+      assert.fail("Snapshot should not be due yet");
+    } catch (error) {
+      assert.include(error.message, "SnapshotNotDue");
+    }
+
+    // Not enough history for either window yet
+    const apy = await program.methods
+      .vaultApy()
+      .accounts({ vault, navHistory })
+      .view();
+    assert.equal(apy.snapshots, 1);
+    assert.isNull(apy.apy7dBps);
+    assert.isNull(apy.apy30dBps);
+    assert.equal(
+      apy.sharePrice.toString(),
+      history.snapshots[0].sharePrice.toString()
+    );
+
+    // Shorter interval: the next crank goes through
+    await program.methods
+      .configureNavHistory(new anchor.BN(1))
+      .accounts({ manager: authority.publicKey, vault, navHistory })
+      .signers([authority])
+      .rpc();
+    await new Promise((resolve) => setTimeout(resolve, 2000));
+    await program.methods
+      .recordNavSnapshot()
+      .accounts({ vault, navHistory })
+      .rpc();
+
+    history = await program.account.navHistory.fetch(navHistory);
+    assert.equal(history.len, 2);
+    assert.equal(history.intervalSecs.toNumber(), 1);
+
+    console.log("✅ NAV history recorded");
+  });
+
+  // ============================================
+  // TEST 15: Deposit Lockup and Early-Release Penalty
+  // ============================================
+
+  it("Should lock shares in escrow and charge early release", async () => {
+    const user2TokenAccount = await getOrCreateAssociatedTokenAccount(
+      provider.connection,
+      authority,
+      tokenMint,
+      user2.publicKey
+    );
+    const [user2ShareAccount] = await PublicKey.findProgramAddress(
+      [
+        user2.publicKey.toBuffer(),
+        TOKEN_PROGRAM_ID.toBuffer(),
+        shareMint.toBuffer(),
+      ],
+      ASSOCIATED_TOKEN_PROGRAM_ID
+    );
+    const [user2Position] = await PublicKey.findProgramAddress(
+      [Buffer.from("position"), vault.toBuffer(), user2.publicKey.toBuffer()],
+      program.programId
+    );
+    const PENALTY_BPS = 500;
+
+    try {
+      await program.methods
+        .setLockup(new anchor.BN(3600), 6000)
+        .accounts({ authority: authority.publicKey, vault })
+        .signers([authority])
+        .rpc();
+      assert.fail("Penalty above the maximum should fail");
+    } catch (error) {
+      assert.include(error.message, "InvalidLockup");
+    }
+
+    await program.methods
+      .setLockup(new anchor.BN(3600), PENALTY_BPS)
+      .accounts({ authority: authority.publicKey, vault })
+      .signers([authority])
+      .rpc();
+
+    const [lockedShareAccount] = PublicKey.findProgramAddressSync(
+      [Buffer.from("locked_shares"), user2Position.toBuffer()],
+      program.programId
+    );
+    const depositAccounts = {
+      user: user2.publicKey,
+      vault,
+      userTokenAccount: user2TokenAccount.address,
+      vaultTokenAccount,
+      userShareAccount: user2ShareAccount,
+      shareMint,
+      position: user2Position,
+      allowlistEntry: null,
+      tokenProgram: TOKEN_PROGRAM_ID,
+      associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+      systemProgram: SystemProgram.programId,
+    };
+
+    // The locked shares need somewhere to go
+    try {
+      await program.methods
+        .deposit(new anchor.BN(1_000_000), [])
+        .accounts({ ...depositAccounts, lockedShareAccount: null })
+        .signers([user2])
+        .rpc();
+      assert.fail("Locked deposit without the escrow should fail");
+    } catch (error) {
+      assert.include(error.message, "LockedShareAccountRequired");
+    }
+
+    const walletSharesBefore = await getTokenBalance(user2ShareAccount);
+    await program.methods
+      .deposit(new anchor.BN(1_000_000), [])
+      .accounts({ ...depositAccounts, lockedShareAccount })
+      .signers([user2])
+      .rpc();
+
+    const position = await program.account.depositorPosition.fetch(
+      user2Position
+    );
+    const now = Math.floor(Date.now() / 1000);
+    assert.isAbove(position.lockUntil.toNumber(), now + 3000);
+
+    // The shares sit in the vault-owned escrow, not in the wallet, so they
+    // cannot be moved to another wallet to dodge the lock
+    const locked = await getTokenBalance(lockedShareAccount);
+    assert.isAbove(locked, 0);
+    assert.equal(await getTokenBalance(user2ShareAccount), walletSharesBefore);
+    const escrow = await getAccount(provider.connection, lockedShareAccount);
+    assert.equal(escrow.owner.toString(), vault.toString());
+
+    const vaultStateBefore = await getVaultState();
+    const navBefore =
+      vaultStateBefore.totalAssets.toNumber() +
+      vaultStateBefore.adapterAssets.toNumber();
+    const sharesBefore = vaultStateBefore.totalShares.toNumber();
+    const penaltyShares = Math.ceil((locked * PENALTY_BPS) / 10_000);
+
+    // Early release burns the penalty out of the locked shares
+    await program.methods
+      .releaseLockedShares()
+      .accounts({
+        owner: user2.publicKey,
+        vault,
+        position: user2Position,
+        lockedShareAccount,
+        ownerShareAccount: user2ShareAccount,
+        shareMint,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .signers([user2])
+      .rpc();
+    assert.equal(await getTokenBalance(lockedShareAccount), 0);
+    assert.equal(
+      (await getTokenBalance(user2ShareAccount)) - walletSharesBefore,
+      locked - penaltyShares
+    );
+
+    // The penalty stays behind: remaining holders' shares are worth more
+    const vaultStateAfter = await getVaultState();
+    assert.equal(
+      vaultStateAfter.totalShares.toNumber(),
+      sharesBefore - penaltyShares
+    );
+    assert.equal(
+      vaultStateAfter.totalAssets.toNumber(),
+      vaultStateBefore.totalAssets.toNumber()
+    );
+    const priceBefore =
+      (navBefore + VIRTUAL_ASSETS) / (sharesBefore + VIRTUAL_SHARES);
+    const priceAfter =
+      (vaultStateAfter.totalAssets.toNumber() +
+        vaultStateAfter.adapterAssets.toNumber() +
+        VIRTUAL_ASSETS) /
+      (vaultStateAfter.totalShares.toNumber() + VIRTUAL_SHARES);
+    assert.isAbove(priceAfter, priceBefore);
+
+    // Released shares redeem at the full share price
+    const shares = Math.floor((locked - penaltyShares) / 2);
+    const assets = Math.floor(
+      (shares *
+        (vaultStateAfter.totalAssets.toNumber() +
+          vaultStateAfter.adapterAssets.toNumber() +
+          VIRTUAL_ASSETS)) /
+        (vaultStateAfter.totalShares.toNumber() + VIRTUAL_SHARES)
+    );
+    const tokensBefore = await getTokenBalance(user2TokenAccount.address);
+    await program.methods
+      .withdraw(new anchor.BN(shares))
+      .accounts({
+        user: user2.publicKey,
+        vault,
+        userTokenAccount: user2TokenAccount.address,
+        vaultTokenAccount,
+        userShareAccount: user2ShareAccount,
+        shareMint,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .signers([user2])
+      .rpc();
+    assert.equal(
+      (await getTokenBalance(user2TokenAccount.address)) - tokensBefore,
+      assets
+    );
+
+    // A later deposit locks only the shares it mints: shares whose lock has
+    // expired are released in full even while newer ones are still locked
+    const setLockPeriod = (seconds: number) =>
+      program.methods
+        .setLockup(new anchor.BN(seconds), PENALTY_BPS)
+        .accounts({ authority: authority.publicKey, vault })
+        .signers([authority])
+        .rpc();
+    const lockedDeposit = () =>
+      program.methods
+        .deposit(new anchor.BN(1_000_000), [])
+        .accounts({ ...depositAccounts, lockedShareAccount })
+        .signers([user2])
+        .rpc();
+
+    await setLockPeriod(1);
+    await lockedDeposit();
+    const matured = await getTokenBalance(lockedShareAccount);
+    await new Promise((resolve) => setTimeout(resolve, 3000));
+
+    await setLockPeriod(3600);
+    await lockedDeposit();
+    const escrowed = await getTokenBalance(lockedShareAccount);
+    const relocked = await program.account.depositorPosition.fetch(
+      user2Position
+    );
+    assert.equal(relocked.shareLocks.length, 1);
+    assert.equal(relocked.shareLocks[0].shares.toNumber(), escrowed - matured);
+
+    const walletBeforeSecond = await getTokenBalance(user2ShareAccount);
+    await program.methods
+      .releaseLockedShares()
+      .accounts({
+        owner: user2.publicKey,
+        vault,
+        position: user2Position,
+        lockedShareAccount,
+        ownerShareAccount: user2ShareAccount,
+        shareMint,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .signers([user2])
+      .rpc();
+    const secondPenalty = Math.ceil(
+      ((escrowed - matured) * PENALTY_BPS) / 10_000
+    );
+    assert.equal(
+      (await getTokenBalance(user2ShareAccount)) - walletBeforeSecond,
+      escrowed - secondPenalty
+    );
+    const released = await program.account.depositorPosition.fetch(
+      user2Position
+    );
+    assert.equal(released.shareLocks.length, 0);
+
+    // Turn the lockup off again for the remaining tests
+    await program.methods
+      .setLockup(new anchor.BN(0), 0)
+      .accounts({ authority: authority.publicKey, vault })
+      .signers([authority])
+      .rpc();
+
+    console.log("✅ Early-release penalty kept in the vault");
+    console.log(`   Penalty: ${penaltyShares} shares`);
+  });
+
+  // ============================================
+  // TEST 16: Allowlist and Merkle Deposit Gates
+  // ============================================
+
+  it("Should gate deposits by allowlist and merkle proof", async () => {
+    const user2TokenAccount = await getOrCreateAssociatedTokenAccount(
+      provider.connection,
+      authority,
+      tokenMint,
+      user2.publicKey
+    );
+    const [user2ShareAccount] = await PublicKey.findProgramAddress(
+      [
+        user2.publicKey.toBuffer(),
+        TOKEN_PROGRAM_ID.toBuffer(),
+        shareMint.toBuffer(),
+      ],
+      ASSOCIATED_TOKEN_PROGRAM_ID
+    );
+    const [user2Position] = await PublicKey.findProgramAddress(
+      [Buffer.from("position"), vault.toBuffer(), user2.publicKey.toBuffer()],
+      program.programId
+    );
+    const [user2Allowlist] = await PublicKey.findProgramAddress(
+      [Buffer.from("allowlist"), vault.toBuffer(), user2.publicKey.toBuffer()],
+      program.programId
+    );
+
+    const depositAs = (allowlistEntry: PublicKey | null, proof: number[][]) =>
+      program.methods
+        .deposit(new anchor.BN(100_000), proof)
+        .accounts({
+          user: user2.publicKey,
+          vault,
+          userTokenAccount: user2TokenAccount.address,
+          vaultTokenAccount,
+          userShareAccount: user2ShareAccount,
+          shareMint,
+          position: user2Position,
+          allowlistEntry,
+          lockedShareAccount: null,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([user2])
+        .rpc();
+    const setGate = (depositGate: object) =>
+      program.methods
+        .setDepositGate(depositGate as any)
+        .accounts({ authority: authority.publicKey, vault })
+        .signers([authority])
+        .rpc();
+
+    // Allowlist mode
+    await setGate({ allowlist: {} });
+    try {
+      await depositAs(null, []);
+      assert.fail("Unlisted depositor should be refused");
+    } catch (error) {
+      assert.include(error.message, "DepositorNotAllowlisted");
+    }
+
+    await program.methods
+      .addToAllowlist(user2.publicKey)
+      .accounts({
+        manager: authority.publicKey,
+        vault,
+        allowlistEntry: user2Allowlist,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([authority])
+      .rpc();
+    await depositAs(user2Allowlist, []);
+
+    await program.methods
+      .removeFromAllowlist()
+      .accounts({
+        manager: authority.publicKey,
+        vault,
+        allowlistEntry: user2Allowlist,
+      })
+      .signers([authority])
+      .rpc();
+    assert.isNull(await provider.connection.getAccountInfo(user2Allowlist));
+
+    // Merkle mode: leaves are sha256(pubkey), pairs hashed in sorted order
+    const sha256 = (...parts: Buffer[]) =>
+      createHash("sha256").update(Buffer.concat(parts)).digest();
+    const leaf1 = sha256(user1.publicKey.toBuffer());
+    const leaf2 = sha256(user2.publicKey.toBuffer());
+    const root =
+      Buffer.compare(leaf1, leaf2) <= 0
+        ? sha256(leaf1, leaf2)
+        : sha256(leaf2, leaf1);
+
+    await program.methods
+      .setMerkleRoot(Array.from(root))
+      .accounts({ authority: authority.publicKey, vault })
+      .signers([authority])
+      .rpc();
+    await setGate({ merkle: {} });
+
+    const vaultState = await getVaultState();
+    assert.deepEqual(vaultState.depositGate, { merkle: {} });
+    assert.deepEqual(Buffer.from(vaultState.merkleRoot), root);
+
+    try {
+      await depositAs(null, []);
+      assert.fail("Deposit without a proof should be refused");
+    } catch (error) {
+      assert.include(error.message, "InvalidMerkleProof");
+    }
+    await depositAs(null, [Array.from(leaf1)]);
+
+    // Open the vault again for the remaining tests
+    await setGate({ open: {} });
+
+    console.log("✅ Deposit gates enforced");
+  });
+
+  // ============================================
+  // TEST 17: Bookkeeping Sync and Health Check
+  // ============================================
+
+  it("Should reconcile total assets with the token balance", async () => {
+    const health = () =>
+      program.methods
+        .vaultHealth()
+        .accounts({ vault, shareMint, vaultTokenAccount })
+        .view();
+    const syncVault = () =>
+      program.methods
+        .syncVault()
+        .accounts({ authority: authority.publicKey, vault, vaultTokenAccount })
+        .signers([authority])
+        .rpc();
+
+    // Earlier tests booked profit that never arrived as tokens
+    await syncVault();
+    let vaultState = await getVaultState();
+    assert.equal(
+      vaultState.totalAssets.toNumber(),
+      await getTokenBalance(vaultTokenAccount)
+    );
+
+    let view = await health();
+    assert.equal(view.totalShares.toString(), view.shareSupply.toString());
+    assert.equal(view.unbookedAssets.toNumber(), 0);
+
+    // Bookkeeping ahead of the balance fails the health check
+    await program.methods
+      .testIncreaseAssets(new anchor.BN(1_000))
+      .accounts({ vault, authority: authority.publicKey })
+      .rpc();
+    let healthy = true;
+    try {
+      await health();
+    } catch (error) {
+      healthy = false;
+    }
+    assert.isFalse(healthy, "Over-booked assets should fail the health check");
+
+    // A donation shows up as unbooked assets until the next sync
+    await mintTo(
+      provider.connection,
+      authority,
+      tokenMint,
+      vaultTokenAccount,
+      authority,
+      5_000
+    );
+    view = await health();
+    assert.equal(view.unbookedAssets.toNumber(), 4_000);
+
+    try {
+      await program.methods
+        .syncVault()
+        .accounts({ authority: user1.publicKey, vault, vaultTokenAccount })
+        .signers([user1])
+        .rpc();
+      assert.fail("Only the manager or keeper may sync");
+    } catch (error) {
+      assert.include(error.message, "Unauthorized");
+    }
+
+    await syncVault();
+    vaultState = await getVaultState();
+    assert.equal(
+      vaultState.totalAssets.toNumber(),
+      await getTokenBalance(vaultTokenAccount)
+    );
+
+    console.log("✅ Vault bookkeeping reconciled");
+  });
+
+  // ============================================
+  // TEST 18: Performance Fee Crystallization
+  // ============================================
+
+  it("Should crystallize fees above the high-water mark", async () => {
+    // Mirrors Vault::share_price / Vault::performance_fee
+    const BN = anchor.BN;
+    const priceOf = (nav: anchor.BN, shares: anchor.BN) =>
+      nav
+        .addn(VIRTUAL_ASSETS)
+        .mul(new BN(1_000_000_000))
+        .div(shares.addn(VIRTUAL_SHARES));
+    const feeRecipientShares = (
+      await getOrCreateAssociatedTokenAccount(
+        provider.connection,
+        authority,
+        shareMint,
+        authority.publicKey
+      )
+    ).address;
+    const crystallize = (signer: Keypair) =>
+      program.methods
+        .crystallizeFees()
+        .accounts({
+          authority: signer.publicKey,
+          vault,
+          shareMint,
+          feeRecipientShareAccount: feeRecipientShares,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([signer])
+        .rpc();
+
+    try {
+      await crystallize(user1);
+      assert.fail("Only the manager or keeper may crystallize fees");
+    } catch (error) {
+      assert.include(error.message, "Unauthorized");
+    }
+
+    // Bring the mark up to date first
+    await crystallize(authority);
+    let state = await getVaultState();
+    const nav = (s: typeof state) =>
+      s.totalAssets
+        .add(s.adapterAssets)
+        .add(s.strategyAssets)
+        .add(s.childAssets);
+    const mark = state.highWaterMark;
+    assert.isTrue(mark.gte(priceOf(nav(state), state.totalShares)));
+
+    // Yield arrives and is synced into the share price
+    await mintTo(
+      provider.connection,
+      authority,
+      tokenMint,
+      vaultTokenAccount,
+      authority,
+      1_000_000
+    );
+    await program.methods
+      .syncVault()
+      .accounts({ authority: authority.publicKey, vault, vaultTokenAccount })
+      .signers([authority])
+      .rpc();
+
+    state = await getVaultState();
+    const price = priceOf(nav(state), state.totalShares);
+    assert.isTrue(price.gt(mark));
+    const gain = price
+      .sub(mark)
+      .mul(state.totalShares)
+      .div(new BN(1_000_000_000));
+    const feeAssets = gain.muln(state.performanceFeeBps).divn(10_000);
+    const feeShares = feeAssets
+      .mul(state.totalShares.addn(VIRTUAL_SHARES))
+      .div(nav(state).addn(VIRTUAL_ASSETS).sub(feeAssets));
+    const sharesBefore = await getTokenBalance(feeRecipientShares);
+
+    await crystallize(authority);
+
+    state = await getVaultState();
+    assert.equal(
+      (await getTokenBalance(feeRecipientShares)) - sharesBefore,
+      feeShares.toNumber()
+    );
+    assert.equal(
+      state.highWaterMark.toString(),
+      priceOf(nav(state), state.totalShares).toString()
+    );
+
+    // No new gains, no new fee
+    const sharesAfter = await getTokenBalance(feeRecipientShares);
+    await crystallize(authority);
+    assert.equal(await getTokenBalance(feeRecipientShares), sharesAfter);
+
+    console.log("✅ Performance fee crystallized");
+    console.log(`   Fee: ${feeAssets} base tokens as ${feeShares} shares`);
+  });
+
+  // ============================================
+  // TEST 19: Role-Based Permissions
+  // ============================================
+
+  it("Should separate manager, keeper and guardian powers", async () => {
+    const keeper = Keypair.generate();
+    const guardian = Keypair.generate();
+    await airdrop(keeper.publicKey, 1);
+    await airdrop(guardian.publicKey, 1);
+
+    await program.methods
+      .setKeeper(keeper.publicKey)
+      .accounts({ manager: authority.publicKey, vault })
+      .signers([authority])
+      .rpc();
+    await program.methods
+      .setGuardian(guardian.publicKey)
+      .accounts({ manager: authority.publicKey, vault })
+      .signers([authority])
+      .rpc();
+
+    let vaultState = await getVaultState();
+    assert.equal(vaultState.keeper.toString(), keeper.publicKey.toString());
+    assert.equal(vaultState.guardian.toString(), guardian.publicKey.toString());
+
+    // Keeper hot key cannot touch fees or fee recipient
+    try {
+      await program.methods
+        .setPerformanceFee(5000)
+        .accounts({ manager: keeper.publicKey, vault })
+        .signers([keeper])
+        .rpc();
+      assert.fail("Keeper should not set fees");
+    } catch (error) {
+      assert.include(error.message, "Unauthorized");
+    }
+    try {
+      await program.methods
+        .setFeeRecipient(keeper.publicKey)
+        .accounts({ manager: keeper.publicKey, vault })
+        .signers([keeper])
+        .rpc();
+      assert.fail("Keeper should not redirect fees");
+    } catch (error) {
+      assert.include(error.message, "Unauthorized");
+    }
+
+    // Keeper cannot rotate roles either
+    try {
+      await program.methods
+        .setKeeper(user1.publicKey)
+        .accounts({ manager: keeper.publicKey, vault })
+        .signers([keeper])
+        .rpc();
+      assert.fail("Keeper should not rotate roles");
+    } catch (error) {
+      assert.include(error.message, "Unauthorized");
+    }
+
+    // Guardian can disable strategies but not re-enable them
+    await program.methods
+      .setVaultStatus({ paused: {} })
+      .accounts({ authority: guardian.publicKey, vault })
+      .signers([guardian])
+      .rpc();
+    vaultState = await getVaultState();
+    assert.deepEqual(vaultState.status, { paused: {} });
+
+    try {
+      await program.methods
+        .setVaultStatus({ active: {} })
+        .accounts({ authority: guardian.publicKey, vault })
+        .signers([guardian])
+        .rpc();
+      assert.fail("Guardian should not enable strategies");
+    } catch (error) {
+      assert.include(error.message, "Unauthorized");
+    }
+
+    // Manager owns fee settings
+    const feeRecipient = Keypair.generate().publicKey;
+    await program.methods
+      .setPerformanceFee(1500)
+      .accounts({ manager: authority.publicKey, vault })
+      .signers([authority])
+      .rpc();
+    await program.methods
+      .setFeeRecipient(feeRecipient)
+      .accounts({ manager: authority.publicKey, vault })
+      .signers([authority])
+      .rpc();
+
+    vaultState = await getVaultState();
+    assert.equal(vaultState.performanceFeeBps, 1500);
+    assert.equal(vaultState.feeRecipient.toString(), feeRecipient.toString());
+
+    // Manager hand-over is mirrored in its registry entry
+    const newManager = Keypair.generate();
+    await program.methods
+      .setManager(newManager.publicKey)
+      .accounts({
+        manager: authority.publicKey,
+        vault,
+        registryEntry: await registryEntryOf(vault),
+      })
+      .signers([authority])
+      .rpc();
+
+    vaultState = await getVaultState();
+    assert.equal(vaultState.manager.toString(), newManager.publicKey.toString());
+    const entry = await program.account.vaultRegistryEntry.fetch(
+      registryEntryAddress(vaultState.vaultId)
+    );
+    assert.equal(entry.manager.toString(), newManager.publicKey.toString());
+
+    console.log("✅ Role permissions enforced");
+  });
+
+  // ============================================
+  // TEST 20: Vault Closure
+  // ============================================
+
+  it("Should close an empty vault and reclaim its rent", async () => {
+    const [retired] = await nextVaultAddress();
+    const pda = (seed: string) =>
+      PublicKey.findProgramAddressSync(
+        [Buffer.from(seed), retired.toBuffer()],
+        program.programId
+      )[0];
+    const retiredShareMint = pda("share_mint");
+    const retiredTokenAccount = pda("vault_token_account");
+
+    await program.methods
+      .initializeVault(PERFORMANCE_FEE_BPS)
+      .accounts({
+        authority: authority.publicKey,
+        registry: vaultRegistry,
+        registryEntry: await nextRegistryEntry(),
+        vault: retired,
+        tokenMint,
+        shareMint: retiredShareMint,
+        vaultTokenAccount: retiredTokenAccount,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+        rent: SYSVAR_RENT_PUBKEY,
+      })
+      .signers([authority])
+      .rpc();
+
+    const user1TokenAccount = await getOrCreateAssociatedTokenAccount(
+      provider.connection,
+      authority,
+      tokenMint,
+      user1.publicKey
+    );
+    const [user1ShareAccount] = PublicKey.findProgramAddressSync(
+      [
+        user1.publicKey.toBuffer(),
+        TOKEN_PROGRAM_ID.toBuffer(),
+        retiredShareMint.toBuffer(),
+      ],
+      ASSOCIATED_TOKEN_PROGRAM_ID
+    );
+    const [user1Position] = PublicKey.findProgramAddressSync(
+      [Buffer.from("position"), retired.toBuffer(), user1.publicKey.toBuffer()],
+      program.programId
+    );
+
+    await program.methods
+      .deposit(new anchor.BN(1_000_000), [])
+      .accounts({
+        user: user1.publicKey,
+        vault: retired,
+        userTokenAccount: user1TokenAccount.address,
+        vaultTokenAccount: retiredTokenAccount,
+        userShareAccount: user1ShareAccount,
+        shareMint: retiredShareMint,
+        position: user1Position,
+        allowlistEntry: null,
+        lockedShareAccount: null,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([user1])
+      .rpc();
+
+    const retiredEntry = await registryEntryOf(retired);
+    const closeVault = () =>
+      program.methods
+        .closeVault()
+        .accounts({
+          manager: authority.publicKey,
+          vault: retired,
+          registryEntry: retiredEntry,
+          shareMint: retiredShareMint,
+          vaultTokenAccount: retiredTokenAccount,
+          shareEscrow: pda("withdraw_escrow"),
+          claimEscrow: pda("claim_escrow"),
+          adapterCollateral: pda("adapter_collateral"),
+          navHistory: null,
+          riskPolicy: null,
+          yieldAdapter: null,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([authority])
+        .rpc();
+
+    try {
+      await closeVault();
+      assert.fail("A vault with shares outstanding cannot close");
+    } catch (error) {
+      assert.include(error.message, "VaultNotEmpty");
+    }
+
+    // The only depositor leaves; at the first-deposit ratio nothing is left behind
+    await program.methods
+      .withdraw(new anchor.BN(await getTokenBalance(user1ShareAccount)))
+      .accounts({
+        user: user1.publicKey,
+        vault: retired,
+        userTokenAccount: user1TokenAccount.address,
+        vaultTokenAccount: retiredTokenAccount,
+        userShareAccount: user1ShareAccount,
+        shareMint: retiredShareMint,
+        position: user1Position,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .signers([user1])
+      .rpc();
+    assert.equal(await getTokenBalance(retiredTokenAccount), 0);
+    // Nothing left on the books either
+    const emptied = await program.account.vault.fetch(retired);
+    assert.equal(emptied.totalAssets.toNumber(), 0);
+    assert.equal(emptied.strategyAssets.toNumber(), 0);
+
+    const lamportsBefore = await provider.connection.getBalance(
+      authority.publicKey
+    );
+    await closeVault();
+
+    assert.isNull(await provider.connection.getAccountInfo(retired));
+    assert.isNull(
+      await provider.connection.getAccountInfo(retiredTokenAccount)
+    );
+    assert.isAbove(
+      await provider.connection.getBalance(authority.publicKey),
+      lamportsBefore
+    );
+
+    const mintInfo = await getMint(provider.connection, retiredShareMint);
+    assert.isNull(mintInfo.mintAuthority);
+
+    const entry = await program.account.vaultRegistryEntry.fetch(retiredEntry);
+    assert.deepEqual(entry.status, { closed: {} });
+
+    console.log("✅ Vault closed and rent reclaimed");
+  });
+
+  it("Should sweep the dust of a vault that earned yield and close it", async () => {
+    const [dusty] = await nextVaultAddress();
+    const pda = (seed: string) =>
+      PublicKey.findProgramAddressSync(
+        [Buffer.from(seed), dusty.toBuffer()],
+        program.programId
+      )[0];
+    const dustyShareMint = pda("share_mint");
+    const dustyTokenAccount = pda("vault_token_account");
+    const shareEscrow = pda("withdraw_escrow");
+    const claimEscrow = pda("claim_escrow");
+
+    await program.methods
+      .initializeVault(PERFORMANCE_FEE_BPS)
+      .accounts({
+        authority: authority.publicKey,
+        registry: vaultRegistry,
+        registryEntry: await nextRegistryEntry(),
+        vault: dusty,
+        tokenMint,
+        shareMint: dustyShareMint,
+        vaultTokenAccount: dustyTokenAccount,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+        rent: SYSVAR_RENT_PUBKEY,
+      })
+      .signers([authority])
+      .rpc();
+
+    const user1TokenAccount = await getOrCreateAssociatedTokenAccount(
+      provider.connection,
+      authority,
+      tokenMint,
+      user1.publicKey
+    );
+    const [user1ShareAccount] = PublicKey.findProgramAddressSync(
+      [
+        user1.publicKey.toBuffer(),
+        TOKEN_PROGRAM_ID.toBuffer(),
+        dustyShareMint.toBuffer(),
+      ],
+      ASSOCIATED_TOKEN_PROGRAM_ID
+    );
+    const [user1Position] = PublicKey.findProgramAddressSync(
+      [Buffer.from("position"), dusty.toBuffer(), user1.publicKey.toBuffer()],
+      program.programId
+    );
+
+    await program.methods
+      .deposit(new anchor.BN(1_000_000), [])
+      .accounts({
+        user: user1.publicKey,
+        vault: dusty,
+        userTokenAccount: user1TokenAccount.address,
+        vaultTokenAccount: dustyTokenAccount,
+        userShareAccount: user1ShareAccount,
+        shareMint: dustyShareMint,
+        position: user1Position,
+        allowlistEntry: null,
+        lockedShareAccount: null,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([user1])
+      .rpc();
+
+    // Yield arrives and is booked
+    await mintTo(
+      provider.connection,
+      authority,
+      tokenMint,
+      dustyTokenAccount,
+      authority,
+      333_333
+    );
+    await program.methods
+      .syncVault()
+      .accounts({
+        authority: authority.publicKey,
+        vault: dusty,
+        vaultTokenAccount: dustyTokenAccount,
+      })
+      .signers([authority])
+      .rpc();
+
+    // The only depositor queues every share and the epoch settles
+    const epoch = (await program.account.vault.fetch(dusty)).withdrawEpoch;
+    const epochSeed = epoch.toArrayLike(Buffer, "le", 8);
+    const [withdrawEpoch] = PublicKey.findProgramAddressSync(
+      [Buffer.from("withdraw_epoch"), dusty.toBuffer(), epochSeed],
+      program.programId
+    );
+    const [withdrawalRequest] = PublicKey.findProgramAddressSync(
+      [
+        Buffer.from("withdrawal_request"),
+        dusty.toBuffer(),
+        user1.publicKey.toBuffer(),
+        epochSeed,
+      ],
+      program.programId
+    );
+    await program.methods
+      .requestWithdraw(
+        new anchor.BN(await getTokenBalance(user1ShareAccount))
+      )
+      .accounts({
+        user: user1.publicKey,
+        vault: dusty,
+        userShareAccount: user1ShareAccount,
+        shareEscrow,
+        shareMint: dustyShareMint,
+        withdrawEpoch,
+        withdrawalRequest,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([user1])
+      .rpc();
+    await program.methods
+      .settleWithdrawEpoch()
+      .accounts({
+        authority: authority.publicKey,
+        vault: dusty,
+        withdrawEpoch,
+        vaultTokenAccount: dustyTokenAccount,
+        shareEscrow,
+        claimEscrow,
+        shareMint: dustyShareMint,
+        tokenMint,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([authority])
+      .rpc();
+
+    const feeRecipientAccount = await getOrCreateAssociatedTokenAccount(
+      provider.connection,
+      authority,
+      tokenMint,
+      authority.publicKey
+    );
+    const dustyEntry = await registryEntryOf(dusty);
+    const closeVault = (sweepTo: PublicKey[]) =>
+      program.methods
+        .closeVault()
+        .accounts({
+          manager: authority.publicKey,
+          vault: dusty,
+          registryEntry: dustyEntry,
+          shareMint: dustyShareMint,
+          vaultTokenAccount: dustyTokenAccount,
+          shareEscrow,
+          claimEscrow,
+          adapterCollateral: pda("adapter_collateral"),
+          navHistory: null,
+          riskPolicy: null,
+          yieldAdapter: null,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .remainingAccounts(
+          sweepTo.map((pubkey) => ({
+            pubkey,
+            isWritable: true,
+            isSigner: false,
+          }))
+        )
+        .signers([authority])
+        .rpc();
+
+    // The settled payout still waits in the claim escrow
+    try {
+      await closeVault([feeRecipientAccount.address]);
+      assert.fail("Unclaimed withdrawals must block the closure");
+    } catch (error) {
+      assert.include(error.message, "ClaimsPending");
+    }
+
+    await program.methods
+      .claimWithdrawal(epoch)
+      .accounts({
+        user: user1.publicKey,
+        vault: dusty,
+        withdrawEpoch,
+        withdrawalRequest,
+        claimEscrow,
+        userTokenAccount: user1TokenAccount.address,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([user1])
+      .rpc();
+    assert.equal(await getTokenBalance(claimEscrow), 0);
+
+    // The virtual shares kept their cut of the yield: no shares, some tokens
+    const drained = await program.account.vault.fetch(dusty);
+    assert.equal(drained.totalShares.toNumber(), 0);
+    assert.equal(drained.pendingClaims.toNumber(), 0);
+    const dust = await getTokenBalance(dustyTokenAccount);
+    assert.isAbove(dust, 0);
+    assert.equal(drained.totalAssets.toNumber(), dust);
+
+    // The dust needs somewhere to go
+    try {
+      await closeVault([]);
+      assert.fail("Dust without a fee recipient account must be rejected");
+    } catch (error) {
+      assert.include(error.message, "SweepAccountRequired");
+    }
+
+    const recipientBefore = await getTokenBalance(feeRecipientAccount.address);
+    await closeVault([feeRecipientAccount.address]);
+
+    assert.equal(
+      (await getTokenBalance(feeRecipientAccount.address)) - recipientBefore,
+      dust
+    );
+    assert.isNull(await provider.connection.getAccountInfo(dusty));
+    assert.isNull(await provider.connection.getAccountInfo(dustyTokenAccount));
+    assert.isNull(await provider.connection.getAccountInfo(claimEscrow));
+
+    console.log("✅ Dust swept and vault closed");
+    console.log(`   Dust: ${dust}`);
+  });
+
+  // ============================================
+  // TEST 21: Vault of Vaults
+  // ============================================
+
+  it("Should allocate into a child vault and count it in NAV", async () => {
+    // A fresh parent and child, both managed by `authority`
+    async function createVault() {
+      const [address] = await nextVaultAddress();
+      const pda = (seed: string) =>
+        PublicKey.findProgramAddressSync(
+          [Buffer.from(seed), address.toBuffer()],
+          program.programId
+        )[0];
+      const created = {
+        vault: address,
+        shareMint: pda("share_mint"),
+        vaultTokenAccount: pda("vault_token_account"),
+      };
+      await program.methods
+        .initializeVault(PERFORMANCE_FEE_BPS)
+        .accounts({
+          authority: authority.publicKey,
+          registry: vaultRegistry,
+          registryEntry: await nextRegistryEntry(),
+          vault: created.vault,
+          tokenMint,
+          shareMint: created.shareMint,
+          vaultTokenAccount: created.vaultTokenAccount,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+          rent: SYSVAR_RENT_PUBKEY,
+        })
+        .signers([authority])
+        .rpc();
+      return created;
+    }
+    const ata = (owner: PublicKey, mint: PublicKey) =>
+      PublicKey.findProgramAddressSync(
+        [owner.toBuffer(), TOKEN_PROGRAM_ID.toBuffer(), mint.toBuffer()],
+        ASSOCIATED_TOKEN_PROGRAM_ID
+      )[0];
+    const position = (vaultKey: PublicKey, owner: PublicKey) =>
+      PublicKey.findProgramAddressSync(
+        [Buffer.from("position"), vaultKey.toBuffer(), owner.toBuffer()],
+        program.programId
+      )[0];
+
+    const parent = await createVault();
+    const child = await createVault();
+    const other = await createVault();
+
+    const user1TokenAccount = await getOrCreateAssociatedTokenAccount(
+      provider.connection,
+      authority,
+      tokenMint,
+      user1.publicKey
+    );
+    await program.methods
+      .deposit(new anchor.BN(4_000_000), [])
+      .accounts({
+        user: user1.publicKey,
+        vault: parent.vault,
+        userTokenAccount: user1TokenAccount.address,
+        vaultTokenAccount: parent.vaultTokenAccount,
+        userShareAccount: ata(user1.publicKey, parent.shareMint),
+        shareMint: parent.shareMint,
+        position: position(parent.vault, user1.publicKey),
+        allowlistEntry: null,
+        lockedShareAccount: null,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([user1])
+      .rpc();
+
+    const [childAllocation] = PublicKey.findProgramAddressSync(
+      [
+        Buffer.from("child_allocation"),
+        parent.vault.toBuffer(),
+        child.vault.toBuffer(),
+      ],
+      program.programId
+    );
+    const parentShareAccount = ata(parent.vault, child.shareMint);
+    const parentPosition = position(child.vault, parent.vault);
+
+    await program.methods
+      .addChildVault()
+      .accounts({
+        manager: authority.publicKey,
+        vault: parent.vault,
+        childVault: child.vault,
+        childShareMint: child.shareMint,
+        childAllocation,
+        shareAccount: parentShareAccount,
+        childPosition: parentPosition,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([authority])
+      .rpc();
+
+    let childState = await program.account.vault.fetch(child.vault);
+    assert.equal(childState.parentCount, 1);
+
+    const addChild = (to: typeof parent, added: typeof parent) =>
+      program.methods
+        .addChildVault()
+        .accounts({
+          manager: authority.publicKey,
+          vault: to.vault,
+          childVault: added.vault,
+          childShareMint: added.shareMint,
+          childAllocation: PublicKey.findProgramAddressSync(
+            [
+              Buffer.from("child_allocation"),
+              to.vault.toBuffer(),
+              added.vault.toBuffer(),
+            ],
+            program.programId
+          )[0],
+          shareAccount: ata(to.vault, added.shareMint),
+          childPosition: position(added.vault, to.vault),
+          tokenProgram: TOKEN_PROGRAM_ID,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([authority])
+        .rpc();
+
+    // A vault with children cannot become a child itself
+    try {
+      await addChild(other, parent);
+      assert.fail("Cycles between vaults should be rejected");
+    } catch (error) {
+      assert.include(error.message, "InvalidChildVault");
+    }
+
+    // Nor can a child take children of its own
+    try {
+      await addChild(child, other);
+      assert.fail("Nesting should stay one level deep");
+    } catch (error) {
+      assert.include(error.message, "VaultIsChild");
+    }
+
+    const childOperation = {
+      authority: authority.publicKey,
+      vault: parent.vault,
+      vaultTokenAccount: parent.vaultTokenAccount,
+      childAllocation,
+      childVault: child.vault,
+      childVaultTokenAccount: child.vaultTokenAccount,
+      childShareMint: child.shareMint,
+      shareAccount: parentShareAccount,
+      childPosition: parentPosition,
+      childAllowlistEntry: null,
+      aggregatorProgram: program.programId,
+      tokenProgram: TOKEN_PROGRAM_ID,
+      associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+      systemProgram: SystemProgram.programId,
+    };
+    const nav = (state) =>
+      state.totalAssets.toNumber() +
+      state.adapterAssets.toNumber() +
+      state.strategyAssets.toNumber() +
+      state.childAssets.toNumber();
+
+    // Deposit into the child through CPI; the parent's NAV is unchanged
+    let parentState = await program.account.vault.fetch(parent.vault);
+    const navBefore = nav(parentState);
+    await program.methods
+      .allocateToChild(new anchor.BN(3_000_000), [])
+      .accounts(childOperation)
+      .signers([authority])
+      .rpc();
+
+    parentState = await program.account.vault.fetch(parent.vault);
+    childState = await program.account.vault.fetch(child.vault);
+    assert.equal(parentState.totalAssets.toNumber(), 1_000_000);
+    assert.equal(parentState.childAssets.toNumber(), 3_000_000);
+    assert.equal(nav(parentState), navBefore);
+    assert.equal(childState.totalAssets.toNumber(), 3_000_000);
+    assert.equal(
+      await getTokenBalance(parentShareAccount),
+      childState.totalShares.toNumber()
+    );
+
+    // Child gains flow into the parent's NAV on sync (permissionless)
+    await program.methods
+      .testIncreaseAssets(new anchor.BN(300_000))
+      .accounts({ vault: child.vault, authority: authority.publicKey })
+      .signers([authority])
+      .rpc();
+    const syncChildren = (remaining) =>
+      program.methods
+        .syncChildVaults()
+        .accounts({ vault: parent.vault })
+        .remainingAccounts(remaining)
+        .rpc();
+
+    try {
+      await syncChildren([]);
+      assert.fail("Every child must be passed to the sync");
+    } catch (error) {
+      assert.include(error.message, "ChildVaultsMissing");
+    }
+
+    const children = [
+      { pubkey: childAllocation, isSigner: false, isWritable: false },
+      { pubkey: child.vault, isSigner: false, isWritable: false },
+      { pubkey: parentShareAccount, isSigner: false, isWritable: false },
+    ];
+    await syncChildren(children);
+    parentState = await program.account.vault.fetch(parent.vault);
+    // Valued at the child's share price, which carries 6 decimals
+    assert.approximately(parentState.childAssets.toNumber(), 3_300_000, 5);
+
+    // Only the manager or keeper may move funds between vaults
+    try {
+      await program.methods
+        .allocateToChild(new anchor.BN(1_000), [])
+        .accounts({ ...childOperation, authority: user2.publicKey })
+        .signers([user2])
+        .rpc();
+      assert.fail("Only operators may allocate");
+    } catch (error) {
+      assert.include(error.message, "Unauthorized");
+    }
+
+    // The child's bookkeeping gain was never funded: bring the child back
+    // to its balance, pass the loss on to the parent, then redeem everything
+    await program.methods
+      .syncVault()
+      .accounts({
+        authority: authority.publicKey,
+        vault: child.vault,
+        vaultTokenAccount: child.vaultTokenAccount,
+      })
+      .signers([authority])
+      .rpc();
+    await syncChildren(children);
+    parentState = await program.account.vault.fetch(parent.vault);
+    assert.equal(parentState.childAssets.toNumber(), 3_000_000);
+
+    const shares = await getTokenBalance(parentShareAccount);
+    await program.methods
+      .withdrawFromChild(new anchor.BN(shares))
+      .accounts(childOperation)
+      .signers([authority])
+      .rpc();
+
+    parentState = await program.account.vault.fetch(parent.vault);
+    childState = await program.account.vault.fetch(child.vault);
+    assert.equal(await getTokenBalance(parentShareAccount), 0);
+    assert.equal(childState.totalShares.toNumber(), 0);
+    assert.equal(
+      parentState.totalAssets.toNumber(),
+      await getTokenBalance(parent.vaultTokenAccount)
+    );
+    assert.equal(parentState.childAssets.toNumber(), 0);
+
+    await program.methods
+      .removeChildVault()
+      .accounts({
+        manager: authority.publicKey,
+        vault: parent.vault,
+        childAllocation,
+        childVault: child.vault,
+        shareAccount: parentShareAccount,
+        childPosition: parentPosition,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .signers([authority])
+      .rpc();
+
+    parentState = await program.account.vault.fetch(parent.vault);
+    assert.equal(parentState.childCount, 0);
+    assert.isNull(await provider.connection.getAccountInfo(childAllocation));
+    childState = await program.account.vault.fetch(child.vault);
+    assert.equal(childState.parentCount, 0);
+
+    console.log("✅ Child vault allocated, marked and unwound");
+  });
 });
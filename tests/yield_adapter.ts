@@ -0,0 +1,915 @@
+import * as anchor from "@coral-xyz/anchor";
+import { Program } from "@coral-xyz/anchor";
+import { SolanaAiDefiAggregator } from "../target/types/solana_ai_defi_aggregator";
+import { MockLending } from "../target/types/mock_lending";
+import {
+  PublicKey,
+  Keypair,
+  SystemProgram,
+  SYSVAR_CLOCK_PUBKEY,
+  SYSVAR_RENT_PUBKEY,
+} from "@solana/web3.js";
+import {
+  TOKEN_PROGRAM_ID,
+  ASSOCIATED_TOKEN_PROGRAM_ID,
+  createMint,
+  mintTo,
+  getAccount,
+  getOrCreateAssociatedTokenAccount,
+} from "@solana/spl-token";
+import { assert } from "chai";
+
+describe("Vault Yield Adapter Tests", () => {
+  const provider = anchor.AnchorProvider.env();
+  anchor.setProvider(provider);
+
+  const program = anchor.workspace
+    .solanaAiDefiAggregator as Program<SolanaAiDefiAggregator>;
+  const lending = anchor.workspace.mockLending as Program<MockLending>;
+
+  let manager: Keypair;
+  let keeper: Keypair;
+  let user: Keypair;
+
+  let usdcMint: PublicKey;
+  let userUsdcAccount: PublicKey;
+  let userShareAccount: PublicKey;
+
+  // Vault PDAs
+  let vaultRegistry: PublicKey;
+  let vaultRegistryEntry: PublicKey;
+  let vault: PublicKey;
+  let shareMint: PublicKey;
+  let vaultUsdcAccount: PublicKey;
+  let yieldAdapter: PublicKey;
+  let adapterCollateral: PublicKey;
+  let globalState: PublicKey;
+  let approvedLendingProgram: PublicKey;
+
+  // Lending PDAs
+  const lendingMarket = Keypair.generate().publicKey;
+  let lendingMarketAuthority: PublicKey;
+  let reserve: PublicKey;
+  let reserveLiquiditySupply: PublicKey;
+  let reserveCollateralMint: PublicKey;
+
+  const INITIAL_DEPOSIT = 10_000_000; // 10 USDC
+  const DEPLOY_AMOUNT = 6_000_000; // 6 USDC
+  const INTEREST = 600_000; // 10% on the deployed amount
+  const BORROWED = 4_000_000; // Lent out of the reserve to a borrower
+  const PROTOCOL_FEE = 60_000; // The lending protocol's cut of the interest
+
+  before(async () => {
+    manager = Keypair.generate();
+    keeper = Keypair.generate();
+    user = Keypair.generate();
+
+    await airdrop(manager.publicKey, 10);
+    await airdrop(keeper.publicKey, 1);
+    await airdrop(user.publicKey, 10);
+
+    usdcMint = await createMint(
+      provider.connection,
+      manager,
+      manager.publicKey,
+      null,
+      6
+    );
+
+    // Vault
+    [vaultRegistry] = PublicKey.findProgramAddressSync(
+      [Buffer.from("vault_registry")],
+      program.programId
+    );
+    try {
+      await program.methods
+        .initializeVaultRegistry()
+        .accounts({
+          payer: manager.publicKey,
+          registry: vaultRegistry,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([manager])
+        .rpc();
+    } catch (e) {
+      console.log("⚠️  Vault registry already initialized");
+    }
+    const registryState = await program.account.vaultRegistry.fetch(
+      vaultRegistry
+    );
+
+    [vault] = PublicKey.findProgramAddressSync(
+      [
+        Buffer.from("vault"),
+        registryState.vaultCount.toArrayLike(Buffer, "le", 8),
+      ],
+      program.programId
+    );
+    [vaultRegistryEntry] = PublicKey.findProgramAddressSync(
+      [
+        Buffer.from("vault_registry_entry"),
+        registryState.vaultCount.toArrayLike(Buffer, "le", 8),
+      ],
+      program.programId
+    );
+    [shareMint] = PublicKey.findProgramAddressSync(
+      [Buffer.from("share_mint"), vault.toBuffer()],
+      program.programId
+    );
+    [vaultUsdcAccount] = PublicKey.findProgramAddressSync(
+      [Buffer.from("vault_token_account"), vault.toBuffer()],
+      program.programId
+    );
+    [yieldAdapter] = PublicKey.findProgramAddressSync(
+      [Buffer.from("yield_adapter"), vault.toBuffer()],
+      program.programId
+    );
+    [adapterCollateral] = PublicKey.findProgramAddressSync(
+      [Buffer.from("adapter_collateral"), vault.toBuffer()],
+      program.programId
+    );
+    [globalState] = PublicKey.findProgramAddressSync(
+      [Buffer.from("global_state")],
+      program.programId
+    );
+    [approvedLendingProgram] = PublicKey.findProgramAddressSync(
+      [Buffer.from("lending_program"), lending.programId.toBuffer()],
+      program.programId
+    );
+
+    await program.methods
+      .initializeVault(1000)
+      .accounts({
+        authority: manager.publicKey,
+        registry: vaultRegistry,
+        registryEntry: vaultRegistryEntry,
+        vault: vault,
+        tokenMint: usdcMint,
+        shareMint: shareMint,
+        vaultTokenAccount: vaultUsdcAccount,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+        rent: SYSVAR_RENT_PUBKEY,
+      })
+      .signers([manager])
+      .rpc();
+
+    await program.methods
+      .setKeeper(keeper.publicKey)
+      .accounts({ manager: manager.publicKey, vault: vault })
+      .signers([manager])
+      .rpc();
+
+    await program.methods
+      .setVaultStatus({ active: {} })
+      .accounts({ authority: manager.publicKey, vault: vault })
+      .signers([manager])
+      .rpc();
+
+    // Lending reserve for the vault's base token
+    [lendingMarketAuthority] = PublicKey.findProgramAddressSync(
+      [lendingMarket.toBuffer()],
+      lending.programId
+    );
+    [reserve] = PublicKey.findProgramAddressSync(
+      [Buffer.from("reserve"), lendingMarket.toBuffer(), usdcMint.toBuffer()],
+      lending.programId
+    );
+    [reserveLiquiditySupply] = PublicKey.findProgramAddressSync(
+      [Buffer.from("liquidity_supply"), reserve.toBuffer()],
+      lending.programId
+    );
+    [reserveCollateralMint] = PublicKey.findProgramAddressSync(
+      [Buffer.from("collateral_mint"), reserve.toBuffer()],
+      lending.programId
+    );
+
+    await lending.methods
+      .initReserve()
+      .accounts({
+        payer: manager.publicKey,
+        lendingMarket: lendingMarket,
+        lendingMarketAuthority: lendingMarketAuthority,
+        reserve: reserve,
+        liquidityMint: usdcMint,
+        reserveLiquiditySupply: reserveLiquiditySupply,
+        reserveCollateralMint: reserveCollateralMint,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+        rent: SYSVAR_RENT_PUBKEY,
+      })
+      .signers([manager])
+      .rpc();
+
+    // User deposit
+    userUsdcAccount = (
+      await getOrCreateAssociatedTokenAccount(
+        provider.connection,
+        manager,
+        usdcMint,
+        user.publicKey
+      )
+    ).address;
+    await mintTo(
+      provider.connection,
+      manager,
+      usdcMint,
+      userUsdcAccount,
+      manager,
+      INITIAL_DEPOSIT
+    );
+    [userShareAccount] = PublicKey.findProgramAddressSync(
+      [
+        user.publicKey.toBuffer(),
+        TOKEN_PROGRAM_ID.toBuffer(),
+        shareMint.toBuffer(),
+      ],
+      ASSOCIATED_TOKEN_PROGRAM_ID
+    );
+    await program.methods
+      .deposit(new anchor.BN(INITIAL_DEPOSIT), [])
+      .accounts({
+        user: user.publicKey,
+        vault: vault,
+        userTokenAccount: userUsdcAccount,
+        vaultTokenAccount: vaultUsdcAccount,
+        userShareAccount: userShareAccount,
+        shareMint: shareMint,
+        allowlistEntry: null,
+        lockedShareAccount: null,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([user])
+      .rpc();
+  });
+
+  async function airdrop(publicKey: PublicKey, amount: number) {
+    const sig = await provider.connection.requestAirdrop(
+      publicKey,
+      amount * anchor.web3.LAMPORTS_PER_SOL
+    );
+    await provider.connection.confirmTransaction(sig);
+  }
+
+  async function getTokenBalance(tokenAccount: PublicKey): Promise<number> {
+    const account = await getAccount(provider.connection, tokenAccount);
+    return Number(account.amount);
+  }
+
+  function adapterAccounts(authority: PublicKey) {
+    return {
+      authority: authority,
+      vault: vault,
+      yieldAdapter: yieldAdapter,
+      vaultTokenAccount: vaultUsdcAccount,
+      collateralAccount: adapterCollateral,
+      lendingProgram: lending.programId,
+      approvedLendingProgram: approvedLendingProgram,
+      reserve: reserve,
+      reserveLiquiditySupply: reserveLiquiditySupply,
+      reserveCollateralMint: reserveCollateralMint,
+      lendingMarket: lendingMarket,
+      lendingMarketAuthority: lendingMarketAuthority,
+      clock: SYSVAR_CLOCK_PUBKEY,
+      tokenProgram: TOKEN_PROGRAM_ID,
+    };
+  }
+
+  // The manager doubles as the reserve's borrower
+  async function borrowerAccount() {
+    return (
+      await getOrCreateAssociatedTokenAccount(
+        provider.connection,
+        manager,
+        usdcMint,
+        manager.publicKey
+      )
+    ).address;
+  }
+
+  const reserveBooks = () => ({
+    reserve: reserve,
+    lendingMarket: lendingMarket,
+  });
+
+  it("Should register the lending reserve as the vault's yield adapter", async () => {
+    // Take over the admin role, keeping the swap fee other suites rely on
+    let feeRate = 30;
+    try {
+      feeRate = (await program.account.globalState.fetch(globalState)).feeRate;
+    } catch (e) {
+      // Not initialized yet
+    }
+    await program.methods
+      .initializeGlobalState(feeRate)
+      .accounts({
+        admin: manager.publicKey,
+        globalState,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([manager])
+      .rpc();
+
+    // Only the admin approves lending programs
+    const approve = (admin: Keypair) =>
+      program.methods
+        .approveLendingProgram(lending.programId)
+        .accounts({
+          admin: admin.publicKey,
+          globalState,
+          approvedLendingProgram,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([admin])
+        .rpc();
+    try {
+      await approve(keeper);
+      assert.fail("Keeper should not approve lending programs");
+    } catch (error) {
+      assert.include(error.message, "Unauthorized");
+    }
+
+    // An unapproved program cannot back an adapter
+    try {
+      await program.methods
+        .registerYieldAdapter()
+        .accounts({
+          manager: manager.publicKey,
+          vault: vault,
+          yieldAdapter: yieldAdapter,
+          lendingProgram: lending.programId,
+          approvedLendingProgram: approvedLendingProgram,
+          reserve: reserve,
+          reserveLiquiditySupply: reserveLiquiditySupply,
+          reserveCollateralMint: reserveCollateralMint,
+          lendingMarket: lendingMarket,
+          collateralAccount: adapterCollateral,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([manager])
+        .rpc();
+      assert.fail("Unapproved lending program should be rejected");
+    } catch (error) {
+      assert.include(error.message, "AccountNotInitialized");
+    }
+
+    await approve(manager);
+
+    // Only the manager picks where funds go
+    try {
+      await program.methods
+        .registerYieldAdapter()
+        .accounts({
+          manager: keeper.publicKey,
+          vault: vault,
+          yieldAdapter: yieldAdapter,
+          lendingProgram: lending.programId,
+          approvedLendingProgram: approvedLendingProgram,
+          reserve: reserve,
+          reserveLiquiditySupply: reserveLiquiditySupply,
+          reserveCollateralMint: reserveCollateralMint,
+          lendingMarket: lendingMarket,
+          collateralAccount: adapterCollateral,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([keeper])
+        .rpc();
+      assert.fail("Keeper should not register adapters");
+    } catch (error) {
+      assert.include(error.message, "Unauthorized");
+    }
+
+    await program.methods
+      .registerYieldAdapter()
+      .accounts({
+        manager: manager.publicKey,
+        vault: vault,
+        yieldAdapter: yieldAdapter,
+        lendingProgram: lending.programId,
+        approvedLendingProgram: approvedLendingProgram,
+        reserve: reserve,
+        reserveLiquiditySupply: reserveLiquiditySupply,
+        reserveCollateralMint: reserveCollateralMint,
+        lendingMarket: lendingMarket,
+        collateralAccount: adapterCollateral,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([manager])
+      .rpc();
+
+    const adapter = await program.account.yieldAdapter.fetch(yieldAdapter);
+    assert.equal(adapter.reserve.toString(), reserve.toString());
+    assert.equal(
+      adapter.lendingMarketAuthority.toString(),
+      lendingMarketAuthority.toString()
+    );
+    assert.equal(adapter.deployed.toNumber(), 0);
+  });
+
+  it("Should deploy idle assets and keep NAV unchanged", async () => {
+    try {
+      await program.methods
+        .deployToAdapter(new anchor.BN(DEPLOY_AMOUNT))
+        .accounts(adapterAccounts(user.publicKey))
+        .signers([user])
+        .rpc();
+      assert.fail("Depositors cannot move vault funds");
+    } catch (error) {
+      assert.include(error.message, "Unauthorized");
+    }
+
+    await program.methods
+      .deployToAdapter(new anchor.BN(DEPLOY_AMOUNT))
+      .accounts(adapterAccounts(keeper.publicKey))
+      .signers([keeper])
+      .rpc();
+
+    const vaultState = await program.account.vault.fetch(vault);
+    assert.equal(
+      vaultState.totalAssets.toNumber(),
+      INITIAL_DEPOSIT - DEPLOY_AMOUNT
+    );
+    assert.equal(vaultState.adapterAssets.toNumber(), DEPLOY_AMOUNT);
+    assert.equal(
+      await getTokenBalance(adapterCollateral),
+      DEPLOY_AMOUNT,
+      "First deposit mints collateral 1:1"
+    );
+    assert.equal(
+      await getTokenBalance(vaultUsdcAccount),
+      INITIAL_DEPOSIT - DEPLOY_AMOUNT
+    );
+  });
+
+  it("Should value the position through outstanding borrows", async () => {
+    const syncAdapter = () =>
+      program.methods
+        .syncAdapter()
+        .accounts(adapterAccounts(keeper.publicKey))
+        .signers([keeper])
+        .rpc();
+
+    // Most of the deployed liquidity is lent out; it is still owed to the vault
+    await lending.methods
+      .borrowLiquidity(new anchor.BN(BORROWED))
+      .accounts({
+        destinationLiquidity: await borrowerAccount(),
+        reserve: reserve,
+        reserveLiquiditySupply: reserveLiquiditySupply,
+        lendingMarket: lendingMarket,
+        lendingMarketAuthority: lendingMarketAuthority,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .rpc();
+    assert.equal(
+      await getTokenBalance(reserveLiquiditySupply),
+      DEPLOY_AMOUNT - BORROWED
+    );
+    await syncAdapter();
+    let vaultState = await program.account.vault.fetch(vault);
+    assert.equal(vaultState.adapterAssets.toNumber(), DEPLOY_AMOUNT);
+
+    // Tokens sent straight to the supply account aren't on the reserve's books
+    await mintTo(
+      provider.connection,
+      manager,
+      usdcMint,
+      reserveLiquiditySupply,
+      manager,
+      1_000_000
+    );
+    await syncAdapter();
+    vaultState = await program.account.vault.fetch(vault);
+    assert.equal(vaultState.adapterAssets.toNumber(), DEPLOY_AMOUNT);
+  });
+
+  it("Should mark accrued interest into NAV on sync", async () => {
+    // Interest accrues on the borrows; the protocol keeps its fee out of it
+    await lending.methods
+      .accrueInterest(
+        new anchor.BN(INTEREST + PROTOCOL_FEE),
+        new anchor.BN(PROTOCOL_FEE)
+      )
+      .accounts(reserveBooks())
+      .rpc();
+
+    // Only operators take the mark
+    try {
+      await program.methods
+        .syncAdapter()
+        .accounts(adapterAccounts(user.publicKey))
+        .signers([user])
+        .rpc();
+      assert.fail("Depositors cannot re-mark the adapter");
+    } catch (error) {
+      assert.include(error.message, "Unauthorized");
+    }
+
+    await program.methods
+      .syncAdapter()
+      .accounts(adapterAccounts(keeper.publicKey))
+      .signers([keeper])
+      .rpc();
+
+    const vaultState = await program.account.vault.fetch(vault);
+    assert.equal(vaultState.adapterAssets.toNumber(), DEPLOY_AMOUNT + INTEREST);
+    assert.equal(
+      vaultState.totalAssets.toNumber() + vaultState.adapterAssets.toNumber(),
+      INITIAL_DEPOSIT + INTEREST,
+      "NAV should include the interest"
+    );
+  });
+
+  it("Should recognize a loss when the reserve writes off liquidity", async () => {
+    const LOSS = 300_000;
+    // Mirrors Vault::share_price
+    type VaultState = Awaited<ReturnType<typeof program.account.vault.fetch>>;
+    const priceOf = (state: VaultState) =>
+      state.totalAssets
+        .add(state.adapterAssets)
+        .add(state.strategyAssets)
+        .add(state.childAssets)
+        .addn(1)
+        .mul(new anchor.BN(1_000_000_000))
+        .div(state.totalShares.addn(1_000));
+    const syncAdapter = () =>
+      program.methods
+        .syncAdapter()
+        .accounts(adapterAccounts(keeper.publicKey))
+        .signers([keeper])
+        .rpc({ commitment: "confirmed" });
+
+    // A borrower defaults: the vault's collateral is now worth less
+    await lending.methods
+      .writeOffLiquidity(new anchor.BN(LOSS))
+      .accounts(reserveBooks())
+      .rpc();
+
+    const before = await program.account.vault.fetch(vault);
+    const signature = await syncAdapter();
+    const after = await program.account.vault.fetch(vault);
+    assert.equal(
+      after.adapterAssets.toNumber(),
+      DEPLOY_AMOUNT + INTEREST - LOSS
+    );
+
+    const tx = await provider.connection.getTransaction(signature, {
+      commitment: "confirmed",
+      maxSupportedTransactionVersion: 0,
+    });
+    const parser = new anchor.EventParser(program.programId, program.coder);
+    const losses = [...parser.parseLogs(tx.meta.logMessages)].filter(
+      (event) => event.name === "vaultLossRecognized"
+    );
+    assert.equal(losses.length, 1);
+    const loss = losses[0].data;
+    assert.equal(loss.vault.toString(), vault.toString());
+    assert.deepEqual(loss.source, { adapter: {} });
+    assert.equal(loss.loss.toNumber(), LOSS);
+    assert.equal(loss.sharePriceBefore.toString(), priceOf(before).toString());
+    assert.equal(loss.sharePriceAfter.toString(), priceOf(after).toString());
+    assert.isTrue(loss.sharePriceAfter.lt(loss.sharePriceBefore));
+    assert.equal(loss.cumulativePnl.toString(), after.cumulativePnl.toString());
+
+    // The defaulted debt is recovered; the next sync marks the position back up
+    await lending.methods
+      .accrueInterest(new anchor.BN(LOSS), new anchor.BN(0))
+      .accounts(reserveBooks())
+      .rpc();
+    await syncAdapter();
+    const restored = await program.account.vault.fetch(vault);
+    assert.equal(restored.adapterAssets.toNumber(), DEPLOY_AMOUNT + INTEREST);
+  });
+
+  it("Should recall principal plus interest back into the vault", async () => {
+    const recallAmount = DEPLOY_AMOUNT + INTEREST;
+
+    // The borrower pays back the loan with interest, so it can be redeemed
+    const borrower = await borrowerAccount();
+    const owed = BORROWED + INTEREST + PROTOCOL_FEE;
+    await mintTo(
+      provider.connection,
+      manager,
+      usdcMint,
+      borrower,
+      manager,
+      INTEREST + PROTOCOL_FEE
+    );
+    await lending.methods
+      .repayLiquidity(new anchor.BN(owed))
+      .accounts({
+        sourceLiquidity: borrower,
+        reserve: reserve,
+        reserveLiquiditySupply: reserveLiquiditySupply,
+        lendingMarket: lendingMarket,
+        userTransferAuthority: manager.publicKey,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .signers([manager])
+      .rpc();
+
+    await program.methods
+      .recallFromAdapter(new anchor.BN(recallAmount))
+      .accounts(adapterAccounts(keeper.publicKey))
+      .signers([keeper])
+      .rpc();
+
+    const vaultState = await program.account.vault.fetch(vault);
+    assert.equal(vaultState.adapterAssets.toNumber(), 0);
+    assert.equal(
+      vaultState.totalAssets.toNumber(),
+      INITIAL_DEPOSIT + INTEREST
+    );
+    assert.equal(
+      await getTokenBalance(vaultUsdcAccount),
+      INITIAL_DEPOSIT + INTEREST
+    );
+    assert.equal(await getTokenBalance(adapterCollateral), 0);
+
+    const adapter = await program.account.yieldAdapter.fetch(yieldAdapter);
+    assert.equal(adapter.deployed.toNumber(), 0);
+  });
+
+  it("Should stop deploys once the lending program is revoked", async () => {
+    await program.methods
+      .revokeLendingProgram()
+      .accounts({
+        admin: manager.publicKey,
+        globalState,
+        approvedLendingProgram,
+      })
+      .signers([manager])
+      .rpc();
+
+    try {
+      await program.methods
+        .deployToAdapter(new anchor.BN(DEPLOY_AMOUNT))
+        .accounts({
+          ...adapterAccounts(keeper.publicKey),
+          approvedLendingProgram: null,
+        })
+        .signers([keeper])
+        .rpc();
+      assert.fail("Revoked program should not receive deposits");
+    } catch (error) {
+      assert.include(error.message, "LendingProgramNotApproved");
+    }
+
+    // Approve it again for the shutdown test
+    await program.methods
+      .approveLendingProgram(lending.programId)
+      .accounts({
+        admin: manager.publicKey,
+        globalState,
+        approvedLendingProgram,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([manager])
+      .rpc();
+  });
+
+  it("Should shut down, unwind the adapter and pay exits in kind", async () => {
+    const guardian = Keypair.generate();
+    await airdrop(guardian.publicKey, 1);
+    await program.methods
+      .setGuardian(guardian.publicKey)
+      .accounts({ manager: manager.publicKey, vault: vault })
+      .signers([manager])
+      .rpc();
+
+    await program.methods
+      .deployToAdapter(new anchor.BN(DEPLOY_AMOUNT))
+      .accounts(adapterAccounts(keeper.publicKey))
+      .signers([keeper])
+      .rpc();
+
+    // Half the user's shares wait in the withdrawal queue
+    const vaultBefore = await program.account.vault.fetch(vault);
+    const epochBytes = vaultBefore.withdrawEpoch.toArrayLike(Buffer, "le", 8);
+    const pda = (...seeds: Buffer[]) =>
+      PublicKey.findProgramAddressSync(seeds, program.programId)[0];
+    const shareEscrow = pda(Buffer.from("withdraw_escrow"), vault.toBuffer());
+    const withdrawEpoch = pda(
+      Buffer.from("withdraw_epoch"),
+      vault.toBuffer(),
+      epochBytes
+    );
+    const withdrawalRequest = pda(
+      Buffer.from("withdrawal_request"),
+      vault.toBuffer(),
+      user.publicKey.toBuffer(),
+      epochBytes
+    );
+    const queued = Math.floor((await getTokenBalance(userShareAccount)) / 2);
+    await program.methods
+      .requestWithdraw(new anchor.BN(queued))
+      .accounts({
+        user: user.publicKey,
+        vault: vault,
+        userShareAccount: userShareAccount,
+        shareEscrow,
+        shareMint: shareMint,
+        withdrawEpoch,
+        withdrawalRequest,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([user])
+      .rpc();
+
+    const cancelRequest = () =>
+      program.methods
+        .cancelWithdrawRequest()
+        .accounts({
+          authority: keeper.publicKey,
+          vault: vault,
+          withdrawEpoch,
+          withdrawalRequest,
+          owner: user.publicKey,
+          shareEscrow,
+          ownerShareAccount: userShareAccount,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([keeper])
+        .rpc();
+    try {
+      await cancelRequest();
+      assert.fail("Requests stay queued while the vault runs");
+    } catch (error) {
+      assert.include(error.message, "VaultNotShutdown");
+    }
+
+    // Unwinding is only for shut-down vaults
+    try {
+      await program.methods
+        .unwindAdapter()
+        .accounts(adapterAccounts(user.publicKey))
+        .signers([user])
+        .rpc();
+      assert.fail("Unwind should wait for shutdown");
+    } catch (error) {
+      assert.include(error.message, "VaultNotShutdown");
+    }
+
+    try {
+      await program.methods
+        .emergencyShutdown()
+        .accounts({
+          guardian: keeper.publicKey,
+          vault: vault,
+          registryEntry: vaultRegistryEntry,
+        })
+        .signers([keeper])
+        .rpc();
+      assert.fail("Keeper cannot shut the vault down");
+    } catch (error) {
+      assert.include(error.message, "Unauthorized");
+    }
+
+    await program.methods
+      .emergencyShutdown()
+      .accounts({
+        guardian: guardian.publicKey,
+        vault: vault,
+        registryEntry: vaultRegistryEntry,
+      })
+      .signers([guardian])
+      .rpc();
+
+    let vaultState = await program.account.vault.fetch(vault);
+    assert.deepEqual(vaultState.status, { shutdown: {} });
+    const entry = await program.account.vaultRegistryEntry.fetch(
+      vaultRegistryEntry
+    );
+    assert.deepEqual(entry.status, { shutdown: {} });
+
+    // The queue will never settle in base tokens: anyone hands the shares back
+    const sharesBeforeCancel = await getTokenBalance(userShareAccount);
+    await cancelRequest();
+    assert.equal(
+      await getTokenBalance(userShareAccount),
+      sharesBeforeCancel + queued
+    );
+    assert.equal(await getTokenBalance(shareEscrow), 0);
+
+    // Shutdown is final
+    try {
+      await program.methods
+        .setVaultStatus({ active: {} })
+        .accounts({ authority: manager.publicKey, vault: vault })
+        .signers([manager])
+        .rpc();
+      assert.fail("Shutdown cannot be reverted");
+    } catch (error) {
+      assert.include(error.message, "VaultShutdown");
+    }
+
+    // No new money in, no strategies
+    await mintTo(
+      provider.connection,
+      manager,
+      usdcMint,
+      userUsdcAccount,
+      manager,
+      1_000_000
+    );
+    try {
+      await program.methods
+        .deposit(new anchor.BN(1_000_000), [])
+        .accounts({
+          user: user.publicKey,
+          vault: vault,
+          userTokenAccount: userUsdcAccount,
+          vaultTokenAccount: vaultUsdcAccount,
+          userShareAccount: userShareAccount,
+          shareMint: shareMint,
+          allowlistEntry: null,
+          lockedShareAccount: null,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([user])
+        .rpc();
+      assert.fail("Deposits are closed after shutdown");
+    } catch (error) {
+      assert.include(error.message, "VaultShutdown");
+    }
+    try {
+      await program.methods
+        .deployToAdapter(new anchor.BN(1_000_000))
+        .accounts(adapterAccounts(keeper.publicKey))
+        .signers([keeper])
+        .rpc();
+      assert.fail("Strategies are off after shutdown");
+    } catch (error) {
+      assert.include(error.message, "StrategyDisabled");
+    }
+
+    // Anyone cranks the adapter position home
+    await program.methods
+      .unwindAdapter()
+      .accounts(adapterAccounts(user.publicKey))
+      .signers([user])
+      .rpc();
+
+    vaultState = await program.account.vault.fetch(vault);
+    assert.equal(vaultState.adapterAssets.toNumber(), 0);
+    assert.equal(await getTokenBalance(adapterCollateral), 0);
+    const vaultBalance = await getTokenBalance(vaultUsdcAccount);
+    assert.equal(vaultState.totalAssets.toNumber(), vaultBalance);
+
+    // Base-token withdrawals are closed; the exit is in kind
+    const shares = await getTokenBalance(userShareAccount);
+    try {
+      await program.methods
+        .withdraw(new anchor.BN(shares))
+        .accounts({
+          user: user.publicKey,
+          vault: vault,
+          vaultTokenAccount: vaultUsdcAccount,
+          userTokenAccount: userUsdcAccount,
+          userShareAccount: userShareAccount,
+          shareMint: shareMint,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([user])
+        .rpc();
+      assert.fail("Only in-kind exits after shutdown");
+    } catch (error) {
+      assert.include(error.message, "WithdrawInKindOnly");
+    }
+
+    const userBefore = await getTokenBalance(userUsdcAccount);
+    await program.methods
+      .withdrawInKind(new anchor.BN(shares))
+      .accounts({
+        user: user.publicKey,
+        vault: vault,
+        vaultTokenAccount: vaultUsdcAccount,
+        userTokenAccount: userUsdcAccount,
+        userShareAccount: userShareAccount,
+        shareMint: shareMint,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([user])
+      .rpc();
+
+    // Sole depositor: everything but the slice of Vault::VIRTUAL_SHARES
+    const received = (await getTokenBalance(userUsdcAccount)) - userBefore;
+    const expected = new anchor.BN(vaultBalance)
+      .mul(new anchor.BN(shares))
+      .div(new anchor.BN(shares + 1_000))
+      .toNumber();
+    assert.equal(received, expected);
+    vaultState = await program.account.vault.fetch(vault);
+    assert.equal(vaultState.totalShares.toNumber(), 0);
+  });
+});